@@ -1,17 +1,452 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
 use serde::Deserialize;
 
+use crate::domain::{risk::RiskLimits, secret::SecretString};
+
+/// The top level fields form the default profile. Each entry of `[profiles.<name>]`
+/// inherits any field it does not set from the default profile, except the credentials:
+/// `api_key`, `secret_key`, `signing` and `private_key` belong to one account and are
+/// always taken together from the profile.
+///
+/// ```toml
+/// api_key = "<main_api_key>"
+/// secret_key = "<main_secret_key>"
+/// environment = "testnet"
+///
+/// [profiles.sub1]
+/// api_key = "<sub1_api_key>"
+/// secret_key = "<sub1_secret_key>"
+///
+/// [profiles.sub1.defaults]
+/// symbol = "ETHUSDT"
+/// ```
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
-    pub api_key: String,
-    pub secret_key: String,
+    /// Path of the encrypted keystore used by [SecretSource::Keystore]
+    pub keystore: Option<PathBuf>,
+
+    /// Path of the triggers armed for the order emulator of `bot run`, shared by the profiles
+    pub triggers: Option<PathBuf>,
+
+    /// REST API started by `serve`
+    pub serve: Option<AppConfigServe>,
+
+    #[serde(flatten)]
+    pub default: AppConfigProfile,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, AppConfigProfile>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfigProfile {
+    /// The credentials are inherited all together, see [AppConfig]
+    pub api_key: Option<SecretSource>,
+    pub secret_key: Option<SecretSource>,
+    pub signing: Option<SigningMethod>,
+    /// PEM file of the private key for [SigningMethod::Ed25519] and [SigningMethod::Rsa]
+    pub private_key: Option<PathBuf>,
+    pub environment: Option<BinanceEnvironment>,
+    pub binance: Option<AppConfigBinanceOverrides>,
+    pub defaults: Option<AppConfigDefaults>,
+    /// Limits checked before any order is sent. A profile's table replaces the top level one.
+    pub risk: Option<RiskLimits>,
+}
+
+impl AppConfigProfile {
+    fn has_credentials(&self) -> bool {
+        self.api_key.is_some()
+            || self.secret_key.is_some()
+            || self.signing.is_some()
+            || self.private_key.is_some()
+    }
+}
+
+/// How requests are signed. HMAC uses `secret_key`, the others use `private_key`.
+///
+/// ```toml
+/// signing = "ed25519"
+/// private_key = "config/ed25519.pem"
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMethod {
+    #[default]
+    Hmac,
+    Ed25519,
+    Rsa,
+}
+
+/// Built-in Binance environments. Each one provides the endpoint and the default paths
+/// so the `[binance]` table only needs the values to override.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinanceEnvironment {
+    #[default]
+    Mainnet,
+    Testnet,
+    /// A mock server running locally, the only environment allowed to use plain http
+    Mock,
+}
+
+impl BinanceEnvironment {
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            BinanceEnvironment::Mainnet => "https://api.binance.com",
+            BinanceEnvironment::Testnet => "https://testnet.binance.vision",
+            BinanceEnvironment::Mock => "http://127.0.0.1:8080",
+        }
+    }
+
+    /// Base of the websocket market streams
+    pub fn stream_endpoint(&self) -> &'static str {
+        match self {
+            BinanceEnvironment::Mainnet => "wss://stream.binance.com:9443",
+            BinanceEnvironment::Testnet => "wss://stream.testnet.binance.vision",
+            BinanceEnvironment::Mock => "ws://127.0.0.1:8081",
+        }
+    }
+
+    /// Base of the USDⓈ-M futures API
+    pub fn futures_endpoint(&self) -> &'static str {
+        match self {
+            BinanceEnvironment::Mainnet => "https://fapi.binance.com",
+            BinanceEnvironment::Testnet => "https://testnet.binancefuture.com",
+            BinanceEnvironment::Mock => "http://127.0.0.1:8080",
+        }
+    }
+}
+
+/// Define the Binance API paths with their defaults. This creates:
+/// - [AppConfigBinanceOverrides], the `[binance]` table where every field is optional
+/// - [AppConfigBinance], the paths resolved against the [BinanceEnvironment]
+macro_rules! binance_paths {
+    ($($name:ident => $default:literal,)*) => {
+        #[derive(Debug, Clone, Default, Deserialize)]
+        pub struct AppConfigBinanceOverrides {
+            pub endpoint: Option<String>,
+            pub stream_endpoint: Option<String>,
+            pub futures_endpoint: Option<String>,
+            $(pub $name: Option<String>,)*
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct AppConfigBinance {
+            pub environment: BinanceEnvironment,
+            pub endpoint: String,
+            pub stream_endpoint: String,
+            pub futures_endpoint: String,
+            $(pub $name: String,)*
+        }
+
+        impl AppConfigBinanceOverrides {
+            /// Use the values of `other` for the fields not set in `self`
+            pub fn or(self, other: Self) -> Self {
+                Self {
+                    endpoint: self.endpoint.or(other.endpoint),
+                    stream_endpoint: self.stream_endpoint.or(other.stream_endpoint),
+                    futures_endpoint: self.futures_endpoint.or(other.futures_endpoint),
+                    $($name: self.$name.or(other.$name),)*
+                }
+            }
+
+            /// Fill the fields not set with the values of the environment, the default is
+            /// mainnet. Overriding an endpoint requires the environment to be set, so the
+            /// REST, stream and futures endpoints can't point at different networks.
+            pub fn resolve(
+                self,
+                environment: Option<BinanceEnvironment>,
+            ) -> Result<AppConfigBinance> {
+                let overridden = [
+                    ("endpoint", &self.endpoint),
+                    ("stream_endpoint", &self.stream_endpoint),
+                    ("futures_endpoint", &self.futures_endpoint),
+                ]
+                .into_iter()
+                .find(|(_, value)| value.is_some());
+                if let (None, Some((name, _))) = (environment, overridden) {
+                    bail!(
+                        "[binance] {} is set without environment, set environment to mainnet, testnet or mock",
+                        name
+                    );
+                }
+                let environment = environment.unwrap_or_default();
+                Ok(AppConfigBinance {
+                    environment,
+                    endpoint: self
+                        .endpoint
+                        .unwrap_or_else(|| environment.endpoint().to_owned()),
+                    stream_endpoint: self
+                        .stream_endpoint
+                        .unwrap_or_else(|| environment.stream_endpoint().to_owned()),
+                    futures_endpoint: self
+                        .futures_endpoint
+                        .unwrap_or_else(|| environment.futures_endpoint().to_owned()),
+                    $($name: self.$name.unwrap_or_else(|| $default.to_owned()),)*
+                })
+            }
+        }
+    };
+}
+
+binance_paths! {
+    get_account => "/api/v3/account",
+    get_avg_price => "/api/v3/avgPrice",
+    get_ticker_price => "/api/v3/ticker/price",
+    get_book_ticker => "/api/v3/ticker/bookTicker",
+    get_all_orders => "/api/v3/allOrders",
+    get_my_trades => "/api/v3/myTrades",
+    get_klines => "/api/v3/klines",
+    post_new_order => "/api/v3/order",
+    post_test_order => "/api/v3/order/test",
+    get_exchange_info => "/api/v3/exchangeInfo",
+    get_ping => "/api/v3/ping",
+    post_order_list_oco => "/api/v3/orderList/oco",
+    post_order_list_oto => "/api/v3/orderList/oto",
+    post_order_list_otoco => "/api/v3/orderList/otoco",
+    get_order_list => "/api/v3/orderList",
+    delete_order_list => "/api/v3/orderList",
+    get_open_order_lists => "/api/v3/openOrderList",
+    get_open_orders => "/api/v3/openOrders",
+    delete_open_orders => "/api/v3/openOrders",
+    get_margin_account => "/sapi/v1/margin/account",
+    get_isolated_margin_account => "/sapi/v1/margin/isolated/account",
+    post_margin_borrow_repay => "/sapi/v1/margin/borrow-repay",
+    post_margin_order => "/sapi/v1/margin/order",
+    delete_margin_order => "/sapi/v1/margin/order",
+    get_margin_open_orders => "/sapi/v1/margin/openOrders",
+    get_margin_interest_history => "/sapi/v1/margin/interestHistory",
+    get_margin_max_borrowable => "/sapi/v1/margin/maxBorrowable",
+    get_futures_position_risk => "/fapi/v2/positionRisk",
+    post_futures_leverage => "/fapi/v1/leverage",
+    post_futures_margin_type => "/fapi/v1/marginType",
+    post_futures_order => "/fapi/v1/order",
+    post_futures_test_order => "/fapi/v1/order/test",
+    get_futures_funding_rate => "/fapi/v1/fundingRate",
+    get_futures_income => "/fapi/v1/income",
+}
+
+/// Where to read a credential from. A plain string is the value itself.
+///
+/// ```toml
+/// api_key = { env = "BINANCE_API_KEY" }
+/// secret_key = { file = "/var/run/secrets/binance/secret_key" }
+/// # or from the keystore managed by `keys add/list/remove`
+/// secret_key = { keystore = "main-secret" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SecretSource {
+    Value(SecretString),
+    Env { env: String },
+    File { file: PathBuf },
+    Keystore { keystore: String },
+}
+
+/// The `[serve]` table. Clients send the token as `Authorization: Bearer <token>`.
+///
+/// ```toml
+/// [serve]
+/// listen = "0.0.0.0:8000"
+/// token = { env = "BINANCE_RS_SERVE_TOKEN" }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfigServe {
+    /// `127.0.0.1:8000` when omitted
+    pub listen: Option<String>,
+    pub token: Option<SecretSource>,
+}
+
+/// Values used by the commands when an argument is omitted
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfigDefaults {
+    pub symbol: Option<String>,
+}
+
+/// A profile with every field resolved against the default profile
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub api_key: SecretSource,
+    pub secret_key: Option<SecretSource>,
+    pub signing: SigningMethod,
+    pub private_key: Option<PathBuf>,
     pub binance: AppConfigBinance,
+    pub defaults: AppConfigDefaults,
+    pub risk: RiskLimits,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct AppConfigBinance {
-    pub endpoint: String,
-    pub get_account: String,
-    pub get_avg_price: String,
-    pub get_all_orders: String,
-    pub post_new_order: String,
+pub const DEFAULT_PROFILE: &str = "default";
+
+impl AppConfig {
+    /// Names of all the profiles, starting with the default profile when it has credentials
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names = vec![];
+        if self.default.api_key.is_some() {
+            names.push(DEFAULT_PROFILE.to_owned());
+        }
+        names.extend(self.profiles.keys().cloned());
+        names
+    }
+
+    /// Resolve the profile by name. `None` or `"default"` gives the top level profile.
+    ///
+    /// ```no_run
+    /// use binance_rs::{
+    ///     app_config::AppConfig,
+    ///     infrastructure::{binance_repo::BinanceRepo, secret_facade::SecretResolver},
+    /// };
+    /// # fn example(cfg: &AppConfig, secrets: &SecretResolver) -> anyhow::Result<()> {
+    /// let profile = cfg.profile(Some("sub1"))?;
+    /// let credentials = secrets.credentials(&profile)?;
+    /// let binance_svc = BinanceRepo::new(profile.binance.clone(), credentials)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let name = name.unwrap_or(DEFAULT_PROFILE);
+        let overrides = match name {
+            DEFAULT_PROFILE => AppConfigProfile::default(),
+            _ => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("profile {name} not found in config"))?,
+        };
+        let base = self.default.clone();
+        // Mixing the key of one account with the secret of another never signs, and a
+        // profile without credentials would be the default account under another name
+        let credentials = match name {
+            DEFAULT_PROFILE => &base,
+            _ if overrides.has_credentials() => &overrides,
+            _ => bail!(
+                "profile {name} sets no credentials, set its api_key with secret_key or private_key"
+            ),
+        };
+        Ok(Profile {
+            name: name.to_owned(),
+            api_key: credentials
+                .api_key
+                .clone()
+                .ok_or_else(|| anyhow!("profile {name} missing api_key"))?,
+            secret_key: credentials.secret_key.clone(),
+            signing: credentials.signing.unwrap_or_default(),
+            private_key: credentials.private_key.clone(),
+            binance: overrides
+                .binance
+                .unwrap_or_default()
+                .or(base.binance.unwrap_or_default())
+                .resolve(overrides.environment.or(base.environment))
+                .with_context(|| format!("profile {name}"))?,
+            defaults: overrides.defaults.or(base.defaults).unwrap_or_default(),
+            risk: overrides.risk.or(base.risk).unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(config: &str) -> Result<Profile> {
+        toml::from_str::<AppConfig>(config)?.profile(None)
+    }
+
+    #[test]
+    fn endpoint_override_requires_environment() {
+        let err =
+            profile("api_key = \"k\"\n[binance]\nendpoint = \"https://testnet.binance.vision\"")
+                .unwrap_err();
+        assert!(format!("{err:#}").contains("endpoint is set without environment"));
+    }
+
+    #[test]
+    fn endpoint_override_keeps_the_other_endpoints_of_the_environment() {
+        let profile = profile(
+            "api_key = \"k\"\nenvironment = \"testnet\"\n[binance]\nendpoint = \"http://proxy.local\"",
+        )
+        .unwrap();
+        assert_eq!(profile.binance.endpoint, "http://proxy.local");
+        assert_eq!(
+            profile.binance.stream_endpoint,
+            "wss://stream.testnet.binance.vision"
+        );
+        assert_eq!(
+            profile.binance.futures_endpoint,
+            "https://testnet.binancefuture.com"
+        );
+    }
+
+    #[test]
+    fn environment_defaults_to_mainnet() {
+        let profile = profile("api_key = \"k\"").unwrap();
+        assert_eq!(profile.binance.environment, BinanceEnvironment::Mainnet);
+        assert_eq!(profile.binance.endpoint, "https://api.binance.com");
+    }
+
+    fn key_env(source: &SecretSource) -> &str {
+        match source {
+            SecretSource::Env { env } => env,
+            _ => panic!("expected an env source, got {source:?}"),
+        }
+    }
+
+    const MAIN: &str = "api_key = { env = \"MAIN_KEY\" }\nsecret_key = { env = \"MAIN_SECRET\" }\n";
+
+    #[test]
+    fn profile_takes_its_credentials_together() {
+        let cfg = toml::from_str::<AppConfig>(&format!(
+            "{MAIN}[profiles.sub1]\napi_key = {{ env = \"SUB_KEY\" }}\nsecret_key = {{ env = \"SUB_SECRET\" }}"
+        ))
+        .unwrap();
+        let sub1 = cfg.profile(Some("sub1")).unwrap();
+        assert_eq!(key_env(&sub1.api_key), "SUB_KEY");
+        assert_eq!(key_env(sub1.secret_key.as_ref().unwrap()), "SUB_SECRET");
+        let default = cfg.profile(None).unwrap();
+        assert_eq!(key_env(&default.api_key), "MAIN_KEY");
+        assert_eq!(cfg.profile_names(), vec!["default", "sub1"]);
+    }
+
+    #[test]
+    fn profile_with_part_of_the_credentials_does_not_borrow_the_rest() {
+        let cfg = toml::from_str::<AppConfig>(&format!(
+            "{MAIN}[profiles.sub1]\napi_key = {{ env = \"SUB_KEY\" }}"
+        ))
+        .unwrap();
+        let sub1 = cfg.profile(Some("sub1")).unwrap();
+        assert_eq!(key_env(&sub1.api_key), "SUB_KEY");
+        assert!(sub1.secret_key.is_none());
+
+        let cfg = toml::from_str::<AppConfig>(&format!(
+            "{MAIN}[profiles.sub1]\nsigning = \"ed25519\"\nprivate_key = \"sub1.pem\""
+        ))
+        .unwrap();
+        let err = cfg.profile(Some("sub1")).unwrap_err();
+        assert_eq!(err.to_string(), "profile sub1 missing api_key");
+    }
+
+    #[test]
+    fn profile_without_credentials_is_refused() {
+        let cfg = toml::from_str::<AppConfig>(&format!(
+            "{MAIN}[profiles.sub1.defaults]\nsymbol = \"ETHUSDT\""
+        ))
+        .unwrap();
+        let err = cfg.profile(Some("sub1")).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("profile sub1 sets no credentials"));
+    }
+
+    #[test]
+    fn profile_inherits_the_other_fields() {
+        let cfg = toml::from_str::<AppConfig>(&format!(
+            "{MAIN}environment = \"testnet\"\n[defaults]\nsymbol = \"BTCUSDT\"\n[profiles.sub1]\napi_key = {{ env = \"SUB_KEY\" }}"
+        ))
+        .unwrap();
+        let sub1 = cfg.profile(Some("sub1")).unwrap();
+        assert_eq!(sub1.binance.environment, BinanceEnvironment::Testnet);
+        assert_eq!(sub1.defaults.symbol.as_deref(), Some("BTCUSDT"));
+    }
 }
@@ -0,0 +1,375 @@
+use std::{collections::BTreeSet, fs, path::Path, thread, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::output::Output,
+    domain::alert::{Alert, AlertRule, AlertWatch, MarketUpdate},
+    infrastructure::{
+        binance_stream::{BinanceStream, BinanceStreamEvent},
+        notifier::{CommandNotifier, Notifier, NotifierConfig, WebhookNotifier},
+    },
+};
+
+/// Wait before reconnecting a dropped stream
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Rules and notifiers of `alerts run`. Without notifiers the alerts are printed to stdout.
+#[derive(Debug, Deserialize)]
+pub struct AlertsFile {
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+}
+
+impl AlertsFile {
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read alerts {}", path.display()))?;
+        let mut file = toml::from_str::<Self>(&content)
+            .with_context(|| format!("cannot parse alerts {}", path.display()))?;
+        if file.alerts.is_empty() {
+            bail!("no alerts in {}", path.display());
+        }
+        for rule in file.alerts.iter_mut() {
+            rule.symbol = rule.symbol.to_uppercase();
+        }
+        Ok(file)
+    }
+
+    /// Create the notifiers, printing with `output` for stdout
+    pub fn notifiers(&self, output: Output) -> Result<Vec<Box<dyn Notifier>>> {
+        if self.notifiers.is_empty() {
+            return Ok(vec![Box::new(OutputNotifier(output))]);
+        }
+        self.notifiers
+            .iter()
+            .map(|config| -> Result<Box<dyn Notifier>> {
+                Ok(match config.clone() {
+                    NotifierConfig::Stdout => Box::new(OutputNotifier(output)),
+                    NotifierConfig::Command { program, args } => {
+                        Box::new(CommandNotifier::new(program, args))
+                    }
+                    NotifierConfig::Webhook { url, headers } => {
+                        Box::new(WebhookNotifier::new(url, headers)?)
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Print the alerts in the output format of the command
+struct OutputNotifier(Output);
+
+impl Notifier for OutputNotifier {
+    fn describe(&self) -> String {
+        "stdout".to_owned()
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<()> {
+        self.0.record(alert)
+    }
+}
+
+/// Result of delivering an alert to a notifier
+#[derive(Debug, Serialize)]
+pub struct Delivery {
+    pub notifier: String,
+    pub delivered: bool,
+    pub error: Option<String>,
+}
+
+/// Send the alert to every notifier. A failing notifier doesn't stop the others.
+pub fn deliver(notifiers: &[Box<dyn Notifier>], alert: &Alert) -> Vec<Delivery> {
+    notifiers
+        .iter()
+        .map(|notifier| {
+            let result = notifier.notify(alert);
+            if let Err(err) = &result {
+                tracing::error!(
+                    "alert {} - {} failed: {:#}",
+                    alert.name,
+                    notifier.describe(),
+                    err
+                );
+            }
+            Delivery {
+                notifier: notifier.describe(),
+                delivered: result.is_ok(),
+                error: result.err().map(|err| format!("{err:#}")),
+            }
+        })
+        .collect()
+}
+
+/// Evaluate the alert rules against the trades and book tickers of their symbols
+/// and deliver the alerts to the notifiers.
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use binance_rs::application::{
+///     alerts::{AlertMonitor, AlertsFile},
+///     output::Output,
+/// };
+/// # fn example(stream_endpoint: &str, output: Output) -> anyhow::Result<()> {
+/// let file = AlertsFile::read_from_file(Path::new("alerts.toml"))?;
+/// AlertMonitor::new(stream_endpoint, file.alerts.clone(), file.notifiers(output)?).run()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AlertMonitor<'a> {
+    stream_endpoint: &'a str,
+    watches: Vec<AlertWatch>,
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl<'a> AlertMonitor<'a> {
+    pub fn new(
+        stream_endpoint: &'a str,
+        rules: Vec<AlertRule>,
+        notifiers: Vec<Box<dyn Notifier>>,
+    ) -> Self {
+        Self {
+            stream_endpoint,
+            watches: rules.into_iter().map(AlertWatch::new).collect(),
+            notifiers,
+        }
+    }
+
+    /// Run until stopped, reconnecting the stream when it drops
+    pub fn run(&mut self) -> Result<()> {
+        let streams = self
+            .watches
+            .iter()
+            .map(|watch| match watch.rule.condition.needs_book() {
+                true => BinanceStream::book_ticker(&watch.rule.symbol),
+                false => BinanceStream::agg_trade(&watch.rule.symbol),
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        tracing::info!(
+            "{} alerts, notifying {}",
+            self.watches.len(),
+            self.notifiers
+                .iter()
+                .map(|n| n.describe())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        loop {
+            if let Err(err) = self.watch(&streams) {
+                tracing::error!("{:#}, reconnecting in {:?}", err, RECONNECT_DELAY);
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+
+    fn watch(&mut self, streams: &[String]) -> Result<()> {
+        let mut stream = BinanceStream::subscribe(self.stream_endpoint, streams)?;
+        loop {
+            let (symbol, update) = match stream.next_event()? {
+                Some(BinanceStreamEvent::AggTrade(trade)) => (
+                    trade.symbol,
+                    MarketUpdate::Trade {
+                        price: trade.price.value(),
+                    },
+                ),
+                Some(BinanceStreamEvent::BookTicker(book)) => (
+                    book.symbol,
+                    MarketUpdate::Book {
+                        bid: book.bid_price.value(),
+                        ask: book.ask_price.value(),
+                    },
+                ),
+                None => continue,
+            };
+            self.on_update(&symbol, &update, Utc::now());
+        }
+    }
+
+    /// Evaluate the rules of the symbol and deliver the alerts they raise
+    fn on_update(&mut self, symbol: &str, update: &MarketUpdate, now: DateTime<Utc>) {
+        for watch in self.watches.iter_mut().filter(|w| w.rule.symbol == symbol) {
+            if let Some(alert) = watch.update(update, now) {
+                tracing::info!("alert {} - {}", alert.name, alert.message);
+                deliver(&self.notifiers, &alert);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc::{self, Receiver},
+        time::Duration as StdDuration,
+    };
+
+    use chrono::Duration;
+    use serde_json::Value;
+    use tiny_http::{Response, Server};
+
+    use super::*;
+
+    const ALERTS: &str = r#"
+[[alerts]]
+name = "btc-above-30k"
+symbol = "BTCUSDT"
+type = "price_cross"
+level = 30000.0
+direction = "up"
+cooldown_secs = 60
+
+[[alerts]]
+name = "eth-spread"
+symbol = "ETHUSDT"
+type = "spread"
+max_bps = 5.0
+
+[[notifiers]]
+type = "webhook"
+url = "http://{addr}/alerts"
+headers = { Authorization = "Bearer secret" }
+"#;
+
+    /// A webhook receiver on a free local port answering `status`, sending each
+    /// request received as (path, authorization header, JSON body)
+    fn receiver(status: u16) -> (String, Receiver<(String, String, Value)>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Authorization"))
+                    .map(|h| h.value.to_string())
+                    .unwrap_or_default();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body = serde_json::from_str::<Value>(&body).unwrap();
+                sender
+                    .send((request.url().to_owned(), authorization, body))
+                    .unwrap();
+                request.respond(Response::empty(status)).unwrap();
+            }
+        });
+        (addr, receiver)
+    }
+
+    fn monitor(addr: &str) -> AlertMonitor<'static> {
+        let file = toml::from_str::<AlertsFile>(&ALERTS.replace("{addr}", addr)).unwrap();
+        let notifiers = file.notifiers(Output::default()).unwrap();
+        AlertMonitor::new("ws://127.0.0.1:1", file.alerts, notifiers)
+    }
+
+    fn trade(price: f64) -> MarketUpdate {
+        MarketUpdate::Trade { price }
+    }
+
+    #[test]
+    fn webhook_receives_the_alert() {
+        let (addr, received) = receiver(200);
+        let mut monitor = monitor(&addr);
+        let now = Utc::now();
+        monitor.on_update("BTCUSDT", &trade(29990.0), now);
+        monitor.on_update("BTCUSDT", &trade(30010.5), now);
+
+        let (path, authorization, body) = received.recv_timeout(StdDuration::from_secs(5)).unwrap();
+        assert_eq!(path, "/alerts");
+        assert_eq!(authorization, "Bearer secret");
+        assert_eq!(body["name"], "btc-above-30k");
+        assert_eq!(body["symbol"], "BTCUSDT");
+        assert_eq!(body["condition"], "price_cross");
+        assert_eq!(body["price"], 30010.5);
+        assert_eq!(body["message"], "BTCUSDT crossed above 30000");
+        assert!(received.try_recv().is_err());
+    }
+
+    #[test]
+    fn webhook_is_notified_once_per_crossing() {
+        let (addr, received) = receiver(200);
+        let mut monitor = monitor(&addr);
+        let start = Utc::now();
+        // Above the level from the first trade: no crossing seen
+        monitor.on_update("BTCUSDT", &trade(30100.0), start);
+        monitor.on_update("BTCUSDT", &trade(29990.0), start);
+        // Crossing up, then staying above
+        for price in [30010.0, 30020.0, 30500.0] {
+            monitor.on_update("BTCUSDT", &trade(price), start);
+        }
+        // Crossing down then up again within the cooldown
+        monitor.on_update("BTCUSDT", &trade(29900.0), start + Duration::seconds(10));
+        monitor.on_update("BTCUSDT", &trade(30001.0), start + Duration::seconds(20));
+        // Crossing up again after the cooldown
+        monitor.on_update("BTCUSDT", &trade(29900.0), start + Duration::seconds(70));
+        monitor.on_update("BTCUSDT", &trade(30002.0), start + Duration::seconds(80));
+        // Other symbols don't touch the rule
+        monitor.on_update("ETHUSDT", &trade(29000.0), start + Duration::seconds(90));
+        monitor.on_update("ETHUSDT", &trade(31000.0), start + Duration::seconds(90));
+
+        let prices = received
+            .iter()
+            .take(2)
+            .map(|(_, _, body)| body["price"].as_f64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(prices, vec![30010.0, 30002.0]);
+        assert!(received
+            .recv_timeout(StdDuration::from_millis(200))
+            .is_err());
+    }
+
+    #[test]
+    fn spread_is_notified_once_while_wide() {
+        let (addr, received) = receiver(200);
+        let mut monitor = monitor(&addr);
+        let now = Utc::now();
+        let book = |bid, ask| MarketUpdate::Book { bid, ask };
+        monitor.on_update("ETHUSDT", &book(2000.0, 2000.5), now);
+        monitor.on_update("ETHUSDT", &book(2000.0, 2002.0), now);
+        monitor.on_update("ETHUSDT", &book(2000.0, 2003.0), now);
+        monitor.on_update("ETHUSDT", &book(2000.0, 2000.5), now);
+        monitor.on_update("ETHUSDT", &book(1999.0, 2001.0), now);
+
+        let names = received
+            .iter()
+            .take(2)
+            .map(|(_, _, body)| body["name"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["eth-spread", "eth-spread"]);
+        assert!(received
+            .recv_timeout(StdDuration::from_millis(200))
+            .is_err());
+    }
+
+    #[test]
+    fn failing_webhook_is_reported() {
+        let (addr, received) = receiver(500);
+        let file = toml::from_str::<AlertsFile>(&ALERTS.replace("{addr}", &addr)).unwrap();
+        let notifiers = file.notifiers(Output::default()).unwrap();
+        let alert = Alert {
+            name: "test".to_owned(),
+            symbol: "BTCUSDT".to_owned(),
+            condition: "price_cross",
+            message: "test alert".to_owned(),
+            price: 30000.0,
+            time: Utc::now(),
+        };
+        let deliveries = deliver(&notifiers, &alert);
+        assert_eq!(deliveries.len(), 1);
+        assert!(!deliveries[0].delivered);
+        assert!(deliveries[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .ends_with("answered 500 Internal Server Error"));
+        assert!(received.try_recv().is_ok());
+    }
+}
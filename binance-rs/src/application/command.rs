@@ -1,9 +1,59 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{Map, Value};
 
-use crate::infrastructure::binance_repo::BinanceRepo;
+use crate::{
+    app_config::{AppConfig, Profile},
+    application::{
+        alerts::{deliver, AlertMonitor, AlertsFile},
+        dashboard::Dashboard,
+        dry_run::{DryRun, DryRunReport},
+        emulator::OrderEmulator,
+        kill_switch::KillSwitch,
+        order_template::{OrderTemplate, OrderTemplateResolver},
+        output::{Output, OutputFormat},
+        repl::Repl,
+        risk::RiskControl,
+        server::ApiServer,
+    },
+    domain::{
+        alert::Alert,
+        binance::{
+            BinanceOrderList, BinanceOrderSide, BinanceOrderStatus, BinanceOrderType,
+            BinanceSpotOrder, BinanceSpotOrderRequest, BinanceTimeInForce, F64String, Price,
+        },
+        futures::{
+            BinanceFuturesMarginType, BinanceFuturesOrderRequest, BinanceFuturesOrderType,
+            BinancePositionSide, BinanceWorkingType,
+        },
+        margin::{
+            BinanceBorrowRepayRequest, BinanceBorrowRepayType, BinanceMarginOrderRequest,
+            BinanceSideEffectType,
+        },
+        secret::SecretString,
+        trigger::{Trigger, TriggerCondition},
+    },
+    infrastructure::{
+        binance_repo::BinanceRepo,
+        keystore::{read_passphrase, Keystore},
+        logging::LogFormat,
+        risk_store::RiskStore,
+        secret_facade::SecretResolver,
+        transport::ReplayTransport,
+        trigger_store::TriggerStore,
+    },
+};
 
 /// Making order to Binance trading platform using HMAC SHA 256 API key and Secret
 #[derive(Parser, Debug)]
@@ -13,6 +63,30 @@ pub struct Args {
     #[clap(long)]
     pub config: PathBuf,
 
+    /// Profile in the config to use. Use the top level credentials if not provided
+    #[clap(long)]
+    pub profile: Option<String>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Validate orders locally and with Binance's test order endpoint without placing them
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Format of the logs written to stderr, their levels are set with RUST_LOG
+    #[clap(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Record the Binance requests and responses to this cassette, keys and signatures scrubbed
+    #[clap(long, conflicts_with = "replay_cassette")]
+    pub record_cassette: Option<PathBuf>,
+
+    /// Answer the Binance requests from this cassette instead of the network
+    #[clap(long)]
+    pub replay_cassette: Option<PathBuf>,
+
     /// Command
     #[clap(subcommand)]
     pub command: Command,
@@ -20,73 +94,1539 @@ pub struct Args {
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
-    Account,
-    GetPrice { symbol: String },
-    GetAllOrders { symbol: String },
-    NewOrder { order: PathBuf },
+    Account {
+        /// Aggregate the balances of every profile in the config
+        #[clap(long)]
+        all_profiles: bool,
+    },
+    GetPrice {
+        symbol: Option<String>,
+    },
+    GetAllOrders {
+        symbol: Option<String>,
+
+        /// Query the orders of every profile in the config
+        #[clap(long)]
+        all_profiles: bool,
+    },
+    NewOrder {
+        order: PathBuf,
+    },
+    /// Place a BUY order from the command line
+    Buy(TradeArgs),
+    /// Place a SELL order from the command line
+    Sell(TradeArgs),
+    /// Place, query and cancel OCO, OTO and OTOCO order lists
+    OrderList {
+        #[clap(subcommand)]
+        command: OrderListCommand,
+    },
+    /// Cross and isolated margin: account, borrow, repay, orders and interest
+    Margin {
+        #[clap(subcommand)]
+        command: MarginCommand,
+    },
+    /// USDⓈ-M futures: positions, leverage, margin type, orders, funding and income.
+    /// Orders are checked by the `[risk]` limits except `max_position` and `daily_loss_limit`.
+    Futures {
+        #[clap(subcommand)]
+        command: FuturesCommand,
+    },
+    /// Client-side stop loss, take profit and trailing stop orders placed by `bot run`
+    Bot {
+        #[clap(subcommand)]
+        command: BotCommand,
+    },
+    /// Kill switch: cancel every open order of every symbol, then optionally sell the balances
+    Panic {
+        /// Market-sell every other asset to this one, e.g. USDT
+        #[clap(long)]
+        sell_to: Option<String>,
+
+        /// Assets never sold, e.g. `--keep BNB,ETH`
+        #[clap(long, value_delimiter = ',')]
+        keep: Vec<String>,
+
+        /// Act without asking for confirmation
+        #[clap(long, short)]
+        yes: bool,
+    },
+    /// Shell keeping one session open, with history and completion of commands and symbols
+    Interactive,
+    /// Terminal dashboard of the balances, open orders, fills and live price, with order entry
+    Tui {
+        /// Symbols to switch between with `n`, the profile default symbol otherwise
+        #[clap(long, value_delimiter = ',')]
+        symbols: Vec<String>,
+
+        /// Seconds between two reads of the balances, open orders and fills
+        #[clap(long, default_value_t = 10)]
+        refresh: u64,
+    },
+    /// REST API of the profile for other services, configured by the `[serve]` table
+    Serve {
+        /// Address to listen on, overrides `listen` of the `[serve]` table
+        #[clap(long)]
+        listen: Option<String>,
+    },
+    /// Notify price alerts evaluated against the live trades and book tickers
+    Alerts {
+        #[clap(subcommand)]
+        command: AlertsCommand,
+    },
+    /// Manage the secrets of the encrypted keystore
+    Keys {
+        #[clap(subcommand)]
+        command: KeysCommand,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct TradeArgs {
+    symbol: String,
+
+    /// Quantity of the base asset
+    #[clap(long, required_unless_present = "quote", conflicts_with = "quote")]
+    quantity: Option<f64>,
+
+    /// Amount of the quote asset to spend or receive, MARKET orders only
+    #[clap(long)]
+    quote: Option<f64>,
+
+    /// Limit price. Without a price the order is a MARKET order
+    #[clap(long)]
+    price: Option<f64>,
+
+    /// LIMIT, MARKET, STOP_LOSS, STOP_LOSS_LIMIT, TAKE_PROFIT, TAKE_PROFIT_LIMIT or LIMIT_MAKER.
+    /// Defaults to LIMIT when a price is given
+    #[clap(long = "type", value_parser = BinanceOrderType::from_str)]
+    order_type: Option<BinanceOrderType>,
+
+    /// Time in force of LIMIT, STOP_LOSS_LIMIT and TAKE_PROFIT_LIMIT orders
+    #[clap(long, value_parser = ["GTC", "IOC", "FOK"])]
+    time_in_force: Option<String>,
+
+    /// Trigger price of stop loss and take profit orders
+    #[clap(long)]
+    stop_price: Option<f64>,
+
+    /// Trailing stop distance in basis points for stop loss and take profit orders
+    #[clap(long)]
+    trailing_delta: Option<u32>,
+
+    /// Visible quantity of an iceberg order
+    #[clap(long)]
+    iceberg_qty: Option<f64>,
+
+    /// Custom id of the order, unique among the open orders
+    #[clap(long)]
+    client_order_id: Option<String>,
+
+    /// Place the order without asking for confirmation
+    #[clap(long, short)]
+    yes: bool,
+}
+
+impl TradeArgs {
+    /// The order of the flags, LIMIT when a price is given and MARKET otherwise
+    fn order(&self, side: BinanceOrderSide) -> BinanceSpotOrderRequest {
+        let order_type = self.order_type.clone().unwrap_or(match self.price {
+            Some(_) => BinanceOrderType::Limit,
+            None => BinanceOrderType::Market,
+        });
+        let time_in_force = self.time_in_force.clone().map(BinanceTimeInForce::from);
+        let time_in_force = match order_type.requires_time_in_force() {
+            true => time_in_force.or(Some(BinanceTimeInForce::Gtc)),
+            false => time_in_force,
+        };
+        let mut order = BinanceSpotOrderRequest::new(self.symbol.to_uppercase(), side, order_type);
+        order.quantity = self.quantity.map(F64String::from);
+        order.quote_order_qty = self.quote.map(F64String::from);
+        order.price = self.price.map(F64String::from);
+        order.time_in_force = time_in_force;
+        order.stop_price = self.stop_price.map(F64String::from);
+        order.trailing_delta = self.trailing_delta;
+        order.iceberg_qty = self.iceberg_qty.map(F64String::from);
+        order.new_client_order_id = self.client_order_id.clone();
+        order
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OrderListCommand {
+    /// Place the order list of a toml file, e.g. `order-list new oco-order.toml`
+    New { file: PathBuf },
+    /// Show an order list and its orders
+    Get { order_list_id: i64 },
+    /// Show the open order lists
+    Open,
+    /// Cancel every order of an order list
+    Cancel { symbol: String, order_list_id: i64 },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MarginCommand {
+    /// Show the assets of the cross margin account, or the isolated pairs with `--isolated`
+    Account {
+        /// Show the isolated pairs instead of the cross margin account
+        #[clap(long)]
+        isolated: bool,
+
+        /// Isolated pairs to show, up to 5, e.g. `--symbols BTCUSDT,ETHUSDT`. Every pair by default
+        #[clap(long, value_delimiter = ',', requires = "isolated")]
+        symbols: Vec<String>,
+    },
+    /// Borrow an asset, e.g. `margin borrow USDT 100`
+    Borrow(LoanArgs),
+    /// Repay a borrowed asset, the interest first
+    Repay(LoanArgs),
+    /// Place a BUY order on the margin account
+    Buy(MarginTradeArgs),
+    /// Place a SELL order on the margin account
+    Sell(MarginTradeArgs),
+    /// Show the open margin orders, of every symbol when none is given
+    Orders {
+        symbol: Option<String>,
+
+        /// Orders of the isolated pair of the symbol
+        #[clap(long, requires = "symbol")]
+        isolated: bool,
+    },
+    /// Cancel an open margin order
+    Cancel {
+        symbol: String,
+        order_id: i64,
+
+        /// The order is on the isolated pair of the symbol
+        #[clap(long)]
+        isolated: bool,
+    },
+    /// Show the last interests charged, most recent first
+    Interest {
+        #[clap(long)]
+        asset: Option<String>,
+
+        /// Symbol of the isolated pair, the cross margin account otherwise
+        #[clap(long)]
+        isolated: Option<String>,
+
+        /// Number of interests shown, up to 100
+        #[clap(long, default_value_t = 10)]
+        limit: u32,
+    },
+    /// Show how much of an asset can still be borrowed
+    MaxBorrowable {
+        asset: String,
+
+        /// Symbol of the isolated pair, the cross margin account otherwise
+        #[clap(long)]
+        isolated: Option<String>,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct LoanArgs {
+    asset: String,
+
+    amount: f64,
+
+    /// Symbol of the isolated pair, the cross margin account otherwise
+    #[clap(long)]
+    isolated: Option<String>,
+
+    /// Act without asking for confirmation
+    #[clap(long, short)]
+    yes: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct MarginTradeArgs {
+    #[clap(flatten)]
+    trade: TradeArgs,
+
+    /// Trade on the isolated pair of the symbol instead of the cross margin account
+    #[clap(long)]
+    isolated: bool,
+
+    /// NO_SIDE_EFFECT, MARGIN_BUY to borrow what the order needs, AUTO_REPAY to repay the
+    /// debt with what it gets, or AUTO_BORROW_REPAY for both
+    #[clap(long, value_parser = BinanceSideEffectType::from_str)]
+    side_effect: Option<BinanceSideEffectType>,
+
+    /// Repay what a MARGIN_BUY or AUTO_BORROW_REPAY order borrowed when it is canceled
+    #[clap(long)]
+    auto_repay_at_cancel: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum FuturesCommand {
+    /// Show the open positions, of every symbol when none is given
+    Positions {
+        symbol: Option<String>,
+
+        /// Show the empty positions too
+        #[clap(long)]
+        all: bool,
+    },
+    /// Change the initial leverage of a symbol, e.g. `futures leverage BTCUSDT 3`
+    Leverage { symbol: String, leverage: u32 },
+    /// Switch a symbol between ISOLATED and CROSSED margin
+    MarginType {
+        symbol: String,
+
+        #[clap(value_parser = BinanceFuturesMarginType::from_str)]
+        margin_type: BinanceFuturesMarginType,
+    },
+    /// Place a BUY futures order
+    Buy(FuturesTradeArgs),
+    /// Place a SELL futures order
+    Sell(FuturesTradeArgs),
+    /// Show the last funding rates of a symbol, oldest first
+    Funding {
+        symbol: Option<String>,
+
+        #[clap(long, default_value_t = 10)]
+        limit: u32,
+    },
+    /// Show the last changes of the futures wallet, e.g. `futures income --type FUNDING_FEE`
+    Income {
+        #[clap(long)]
+        symbol: Option<String>,
+
+        /// TRANSFER, REALIZED_PNL, FUNDING_FEE, COMMISSION, ...
+        #[clap(long = "type")]
+        income_type: Option<String>,
+
+        #[clap(long, default_value_t = 20)]
+        limit: u32,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct FuturesTradeArgs {
+    symbol: String,
+
+    /// Quantity of the base asset
+    #[clap(long, required_unless_present = "close_position")]
+    quantity: Option<f64>,
+
+    /// Limit price. Without a price the order is a MARKET order
+    #[clap(long)]
+    price: Option<f64>,
+
+    /// LIMIT, MARKET, STOP, STOP_MARKET, TAKE_PROFIT, TAKE_PROFIT_MARKET or
+    /// TRAILING_STOP_MARKET. Defaults to LIMIT when a price is given
+    #[clap(long = "type", value_parser = BinanceFuturesOrderType::from_str)]
+    order_type: Option<BinanceFuturesOrderType>,
+
+    /// Time in force of LIMIT orders, GTX for post only
+    #[clap(long, value_parser = ["GTC", "IOC", "FOK", "GTX"])]
+    time_in_force: Option<String>,
+
+    /// Trigger price of STOP, STOP_MARKET, TAKE_PROFIT and TAKE_PROFIT_MARKET orders
+    #[clap(long)]
+    stop_price: Option<f64>,
+
+    /// Trigger the stop price on the mark price instead of the last price
+    #[clap(long)]
+    mark_price: bool,
+
+    /// Only reduce the position, never open or increase one
+    #[clap(long)]
+    reduce_only: bool,
+
+    /// Close the whole position once triggered, STOP_MARKET and TAKE_PROFIT_MARKET only
+    #[clap(long, conflicts_with_all = ["quantity", "reduce_only"])]
+    close_position: bool,
+
+    /// LONG or SHORT in hedge mode
+    #[clap(long, value_parser = BinancePositionSide::from_str)]
+    position_side: Option<BinancePositionSide>,
+
+    /// Trailing distance of TRAILING_STOP_MARKET orders in percent, from 0.1 to 10
+    #[clap(long)]
+    callback_rate: Option<f64>,
+
+    /// Price activating TRAILING_STOP_MARKET orders, the current price by default
+    #[clap(long)]
+    activation_price: Option<f64>,
+
+    /// Custom id of the order, unique among the open orders
+    #[clap(long)]
+    client_order_id: Option<String>,
+
+    /// Place the order without asking for confirmation
+    #[clap(long, short)]
+    yes: bool,
+}
+
+impl FuturesTradeArgs {
+    /// The order of the flags, LIMIT when a price is given and MARKET otherwise
+    fn order(&self, side: BinanceOrderSide) -> BinanceFuturesOrderRequest {
+        let order_type = self.order_type.unwrap_or(match self.price {
+            Some(_) => BinanceFuturesOrderType::Limit,
+            None => BinanceFuturesOrderType::Market,
+        });
+        let time_in_force = self.time_in_force.clone().map(BinanceTimeInForce::from);
+        let time_in_force = match order_type {
+            BinanceFuturesOrderType::Limit => time_in_force.or(Some(BinanceTimeInForce::Gtc)),
+            _ => time_in_force,
+        };
+        let mut order =
+            BinanceFuturesOrderRequest::new(self.symbol.to_uppercase(), side, order_type);
+        order.position_side = self.position_side;
+        order.time_in_force = time_in_force;
+        order.quantity = self.quantity.map(F64String::from);
+        order.reduce_only = self.reduce_only.then_some(true);
+        order.price = self.price.map(F64String::from);
+        order.stop_price = self.stop_price.map(F64String::from);
+        order.close_position = self.close_position.then_some(true);
+        order.activation_price = self.activation_price.map(F64String::from);
+        order.callback_rate = self.callback_rate.map(F64String::from);
+        order.working_type = self.mark_price.then_some(BinanceWorkingType::MarkPrice);
+        order.new_client_order_id = self.client_order_id.clone();
+        order
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BotCommand {
+    /// Watch the trades and place the order of each trigger of the profile once its condition is met
+    Run,
+    /// Arm a trigger, e.g. `bot arm BTCUSDT sell --quantity 0.01 --trailing-delta 200`
+    Arm(ArmArgs),
+    /// Show the armed triggers of the profile
+    List,
+    /// Remove an armed trigger
+    Disarm { id: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AlertsCommand {
+    /// Watch the symbols of the rules of a toml file, e.g. `alerts run alerts.toml`
+    Run { file: PathBuf },
+    /// Send a test alert through every notifier of a toml file
+    Test { file: PathBuf },
+}
+
+#[derive(Debug, clap::Args)]
+#[clap(group(
+    clap::ArgGroup::new("condition")
+        .required(true)
+        .args(["stop_loss", "take_profit", "trailing_delta"])
+))]
+pub struct ArmArgs {
+    symbol: String,
+
+    /// BUY or SELL
+    #[clap(value_parser = BinanceOrderSide::from_str)]
+    side: BinanceOrderSide,
+
+    /// Quantity of the base asset
+    #[clap(long)]
+    quantity: f64,
+
+    /// Stop price of a stop loss: SELL when the price falls to it, BUY when it rises to it
+    #[clap(long)]
+    stop_loss: Option<f64>,
+
+    /// Stop price of a take profit: SELL when the price rises to it, BUY when it falls to it
+    #[clap(long)]
+    take_profit: Option<f64>,
+
+    /// Trailing stop distance in basis points from the best price since armed
+    #[clap(long)]
+    trailing_delta: Option<u32>,
+
+    /// Place a LIMIT order at this price instead of a MARKET order
+    #[clap(long)]
+    limit_price: Option<f64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeysCommand {
+    /// Encrypt and store a secret, e.g. `keys add main-secret`
+    Add {
+        name: String,
+
+        /// Read the secret from stdin instead of prompting for it
+        #[clap(long)]
+        stdin: bool,
+    },
+    List,
+    Remove {
+        name: String,
+    },
+}
+
+/// Columns of [BinanceSpotOrder] shown in the table output
+const ORDER_TABLE_COLUMNS: &[&str] = &[
+    "orderId",
+    "clientOrderId",
+    "symbol",
+    "side",
+    "type",
+    "price",
+    "origQty",
+    "executedQty",
+    "cummulativeQuoteQty",
+    "status",
+];
+
+/// Columns of [BinanceOrderList] shown in the table output
+const ORDER_LIST_TABLE_COLUMNS: &[&str] = &[
+    "orderListId",
+    "listClientOrderId",
+    "contingencyType",
+    "symbol",
+    "listStatusType",
+    "listOrderStatus",
+    "transactionTime",
+];
+
+/// Fields of [BinanceMarginAccount](crate::domain::margin::BinanceMarginAccount) shown above its assets in the table output
+const MARGIN_ACCOUNT_TABLE_COLUMNS: &[&str] = &[
+    "marginLevel",
+    "totalAssetOfBtc",
+    "totalLiabilityOfBtc",
+    "totalNetAssetOfBtc",
+    "borrowEnabled",
+    "tradeEnabled",
+];
+
+/// Columns of [IsolatedMarginPair](crate::domain::margin::IsolatedMarginPair) shown in the table output
+const ISOLATED_MARGIN_TABLE_COLUMNS: &[&str] = &[
+    "symbol",
+    "marginLevel",
+    "marginLevelStatus",
+    "liquidatePrice",
+    "baseAsset.asset",
+    "baseAsset.netAsset",
+    "baseAsset.borrowed",
+    "quoteAsset.asset",
+    "quoteAsset.netAsset",
+    "quoteAsset.borrowed",
+];
+
+/// Columns of [BinanceMarginInterest](crate::domain::margin::BinanceMarginInterest) shown in the table output
+const MARGIN_INTEREST_TABLE_COLUMNS: &[&str] = &[
+    "interestAccuredTime",
+    "asset",
+    "principal",
+    "interest",
+    "interestRate",
+    "type",
+    "isolatedSymbol",
+];
+
+/// Columns of [BinanceFuturesPosition](crate::domain::futures::BinanceFuturesPosition)
+/// shown in the table output
+const FUTURES_POSITION_TABLE_COLUMNS: &[&str] = &[
+    "symbol",
+    "positionSide",
+    "positionAmt",
+    "entryPrice",
+    "markPrice",
+    "unRealizedProfit",
+    "liquidationPrice",
+    "leverage",
+    "marginType",
+];
+
+#[derive(Debug, Serialize)]
+struct SymbolPrice {
+    symbol: String,
+    #[serde(flatten)]
+    price: Price,
+}
+
+#[derive(Debug, Serialize)]
+struct ProfileOrder {
+    profile: String,
+    #[serde(flatten)]
+    order: BinanceSpotOrder,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchOrderResult {
+    index: usize,
+    symbol: String,
+    side: String,
+    success: bool,
+    #[serde(rename = "orderId")]
+    order_id: Option<i64>,
+    status: Option<BinanceOrderStatus>,
+    #[serde(rename = "executedQty")]
+    executed_qty: Option<Decimal>,
+    error: Option<String>,
+}
+
+impl BatchOrderResult {
+    fn new(index: usize, template: &OrderTemplate, result: Result<BinanceSpotOrder>) -> Self {
+        let mut report = Self {
+            index,
+            symbol: template.symbol().to_owned(),
+            side: template.side().to_owned(),
+            success: result.is_ok(),
+            order_id: None,
+            status: None,
+            executed_qty: None,
+            error: None,
+        };
+        match result {
+            Ok(order) => {
+                report.order_id = order.order_id;
+                report.status = order.status;
+                report.executed_qty = order.executed_qty;
+            }
+            Err(err) => report.error = Some(format!("{err:#}")),
+        }
+        report
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct IndexedDryRunReport {
+    index: usize,
+    #[serde(flatten)]
+    report: DryRunReport,
+}
+
+#[derive(Debug, Serialize)]
+struct OrderListLegReport {
+    leg: &'static str,
+    #[serde(flatten)]
+    report: DryRunReport,
+}
+
+/// Futures order checked locally then with the test order endpoint
+#[derive(Debug, Serialize)]
+struct FuturesDryRunReport<'a> {
+    #[serde(flatten)]
+    order: &'a BinanceFuturesOrderRequest,
+    valid: bool,
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct KeyEntry<'a> {
+    name: &'a str,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct KeyChange<'a> {
+    name: &'a str,
+    action: &'a str,
+    keystore: &'a Path,
 }
 
 pub struct CommandHandler {
+    cfg: AppConfig,
+    profile: Profile,
+    secrets: SecretResolver,
     binance: BinanceRepo,
+    output: Output,
 }
 
 impl CommandHandler {
-    pub fn new(binance: BinanceRepo) -> Self {
-        Self { binance }
+    /// Create the handler with the [BinanceRepo] of the selected profile
+    pub fn new(cfg: AppConfig, profile: Option<&str>, output: Output) -> Result<Self> {
+        let profile = cfg.profile(profile)?;
+        let secrets = SecretResolver::new(keystore_path(&cfg));
+        let binance = BinanceRepo::new(profile.binance.clone(), secrets.credentials(&profile)?)?;
+        Ok(Self {
+            cfg,
+            profile,
+            secrets,
+            binance,
+            output,
+        })
+    }
+
+    /// Record to or replay from the cassette every request of the profile
+    pub fn with_cassette(
+        mut self,
+        record: Option<PathBuf>,
+        replay: Option<PathBuf>,
+    ) -> Result<Self> {
+        if let Some(path) = record {
+            self.binance = self.binance.recording(path);
+        } else if let Some(path) = replay {
+            let transport = ReplayTransport::read_from_file(&path)?;
+            self.binance = self.binance.with_transport(Box::new(transport));
+        }
+        Ok(self)
     }
+
+    pub fn binance(&self) -> &BinanceRepo {
+        &self.binance
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn output(&self) -> Output {
+        self.output
+    }
+
     pub fn handle_args(&self, args: Args) -> Result<()> {
-        match args.command {
-            Command::Account => {
-                let account = self.binance.get_account()?;
-                println!("Accounts:");
-                for balance in account.balances {
-                    println!("{:<5}: {}", balance.asset, balance.free);
+        self.handle_command(args.command, args.dry_run)
+    }
+
+    /// Run one command, from the command line or a line of the `interactive` shell
+    pub fn handle_command(&self, command: Command, dry_run: bool) -> Result<()> {
+        match command {
+            Command::Account { all_profiles } => {
+                if all_profiles {
+                    return self.print_all_accounts();
                 }
+                let account = self.binance.get_account()?;
+                self.output.records(&account.balances)?;
             }
             Command::GetPrice { symbol } => {
+                let symbol = self.symbol_or_default(symbol)?;
                 let price = self.binance.get_price(&symbol)?;
-                println!("Price {}: {}", symbol, price.price);
+                self.output.record(&SymbolPrice { symbol, price })?;
             }
-            Command::GetAllOrders { symbol } => {
-                let orders = self.binance.get_orders(&symbol)?;
-                if orders.is_empty() {
-                    println!("{}: Empty orders", symbol);
-                    return Ok(());
+            Command::GetAllOrders {
+                symbol,
+                all_profiles,
+            } => {
+                let symbol = self.symbol_or_default(symbol)?;
+                if !all_profiles {
+                    let orders = self.binance.get_orders(&symbol)?;
+                    return self
+                        .output
+                        .records_with_columns(&orders, ORDER_TABLE_COLUMNS);
                 }
-                for order in orders {
-                    println!(
-                        "ID: BinanceID {} - ClientID {}",
-                        order.order_id.unwrap_or_default(),
-                        order.client_order_id.unwrap_or_default()
-                    );
-                    println!(
-                        "Symbol {:<5} - Side {}",
-                        order.symbol.unwrap_or_default(),
-                        order.side.unwrap_or_default()
-                    );
-                    println!(
-                        "Price {:<8} - Quantity {}",
-                        order.price.unwrap_or_default(),
-                        order.orig_qty.unwrap_or_default()
-                    );
-                    let status = order.status.unwrap_or_default();
-                    println!("Status {:<8}", status);
-                    if status == "FILLED" {
-                        println!("Executed {:<8}", order.executed_qty.unwrap_or_default());
-                        println!(
-                            "└─Cummulative quote qty {:<8}",
-                            order.cummulative_quote_qty.unwrap_or_default()
-                        );
+                let mut orders = vec![];
+                for (name, binance) in self.all_profiles()? {
+                    match binance.get_orders(&symbol) {
+                        Ok(profile_orders) => {
+                            orders.extend(profile_orders.into_iter().map(|order| ProfileOrder {
+                                profile: name.to_owned(),
+                                order,
+                            }))
+                        }
+                        Err(err) => {
+                            tracing::error!("profile {} - cannot get orders: {:#}", name, err)
+                        }
                     }
-                    println!("==========")
                 }
+                let columns = [&["profile"], ORDER_TABLE_COLUMNS].concat();
+                self.output.records_with_columns(&orders, &columns)?;
             }
             Command::NewOrder { order } => {
-                let order = BinanceRepo::read_order_from_file(order)?;
-                let re = self.binance.make_spot_order(order)?;
-                println!("Created Order: {:?}", re);
+                let file = OrderTemplate::read_from_file(&order)?;
+                if file.batch {
+                    return self.submit_batch(&file.orders, dry_run);
+                }
+                let resolver = OrderTemplateResolver::new(&self.binance);
+                let order = resolver.resolve(&file.orders[0])?;
+                if dry_run {
+                    return self.dry_run(&order);
+                }
+                let re = self.place_order(order)?;
+                self.output.record(&re)?;
+            }
+            Command::Buy(trade) => self.trade(BinanceOrderSide::Buy, trade, dry_run)?,
+            Command::Sell(trade) => self.trade(BinanceOrderSide::Sell, trade, dry_run)?,
+            Command::OrderList { command } => self.order_list(command, dry_run)?,
+            Command::Margin { command } => self.margin(command, dry_run)?,
+            Command::Futures { command } => self.futures(command, dry_run)?,
+            Command::Bot { command } => self.bot(command, dry_run)?,
+            Command::Panic { sell_to, keep, yes } => self.panic(sell_to, keep, yes, dry_run)?,
+            Command::Alerts { command } => self.alerts(command)?,
+            Command::Serve { listen } => self.serve(listen, dry_run)?,
+            Command::Interactive => Repl::new(self)?.run()?,
+            Command::Tui { symbols, refresh } => {
+                let symbols = match symbols.is_empty() {
+                    true => vec![self.symbol_or_default(None)?],
+                    false => symbols,
+                };
+                Dashboard::new(self, symbols, Duration::from_secs(refresh.max(1)), dry_run).run()?
+            }
+            Command::Keys { command } => handle_keys(&self.cfg, &command, self.output)?,
+        }
+        Ok(())
+    }
+
+    /// Serve the REST API until the process is stopped. The token is required.
+    fn serve(&self, listen: Option<String>, dry_run: bool) -> Result<()> {
+        let serve = self.cfg.serve.clone().unwrap_or_default();
+        let source = serve
+            .token
+            .ok_or_else(|| anyhow!("serve requires a token in the [serve] table of the config"))?;
+        let token = self.secrets.resolve(&source).context("serve token")?;
+        if token.expose().is_empty() {
+            bail!("the serve token is empty");
+        }
+        let listen = listen
+            .or(serve.listen)
+            .unwrap_or_else(|| "127.0.0.1:8000".to_owned());
+        ApiServer::new(self, token, dry_run).run(&listen)
+    }
+
+    /// Show what the kill switch will do, ask for confirmation then report every action
+    fn panic(
+        &self,
+        sell_to: Option<String>,
+        keep: Vec<String>,
+        yes: bool,
+        dry_run: bool,
+    ) -> Result<()> {
+        let switch = KillSwitch::new(&self.binance, sell_to.clone(), keep);
+        let plan = switch.plan()?;
+        if dry_run {
+            return self.output.records(&switch.dry_run(&plan));
+        }
+        let open_orders = plan.open_orders.values().map(Vec::len).sum::<usize>();
+        eprintln!(
+            "cancel {} open orders on {} symbols",
+            open_orders,
+            plan.open_orders.len()
+        );
+        if let Some(sell_to) = &sell_to {
+            for (asset, total) in plan.balances.iter() {
+                eprintln!("sell {total} {asset} to {sell_to}");
+            }
+        }
+        if !yes && !confirm("Proceed?")? {
+            bail!("panic cancelled");
+        }
+        let actions = switch.execute(&plan);
+        self.output.records(&actions)?;
+        let failed = actions.iter().filter(|a| a.status == "failed").count();
+        if failed > 0 {
+            bail!("{} of {} actions failed", failed, actions.len());
+        }
+        Ok(())
+    }
+
+    fn alerts(&self, command: AlertsCommand) -> Result<()> {
+        match command {
+            AlertsCommand::Run { file } => {
+                let file = AlertsFile::read_from_file(&file)?;
+                let notifiers = file.notifiers(self.output)?;
+                AlertMonitor::new(
+                    &self.profile.binance.stream_endpoint,
+                    file.alerts,
+                    notifiers,
+                )
+                .run()
+            }
+            AlertsCommand::Test { file } => {
+                let file = AlertsFile::read_from_file(&file)?;
+                let rule = &file.alerts[0];
+                let alert = Alert {
+                    name: rule.name.to_owned(),
+                    symbol: rule.symbol.to_owned(),
+                    condition: rule.condition.name(),
+                    message: format!("test alert of {}", rule.name),
+                    price: 0.0,
+                    time: Utc::now(),
+                };
+                let deliveries = deliver(&file.notifiers(self.output)?, &alert);
+                self.output.records(&deliveries)?;
+                if deliveries.iter().any(|d| !d.delivered) {
+                    bail!("some notifiers failed");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn bot(&self, command: BotCommand, dry_run: bool) -> Result<()> {
+        let store = TriggerStore::new(triggers_path(&self.cfg));
+        match command {
+            BotCommand::Run => OrderEmulator::new(
+                &self.binance,
+                &self.profile.name,
+                self.risk(),
+                &self.profile.binance.stream_endpoint,
+                &store,
+                self.output,
+                dry_run,
+            )
+            .run(),
+            BotCommand::Arm(arm) => {
+                let condition = match (arm.stop_loss, arm.take_profit, arm.trailing_delta) {
+                    (Some(stop_price), _, _) => TriggerCondition::StopLoss { stop_price },
+                    (_, Some(stop_price), _) => TriggerCondition::TakeProfit { stop_price },
+                    (_, _, Some(trailing_delta)) => TriggerCondition::TrailingStop {
+                        trailing_delta,
+                        extreme: None,
+                    },
+                    _ => unreachable!("clap requires one condition"),
+                };
+                let mut trigger = Trigger::new(
+                    self.profile.name.to_owned(),
+                    arm.symbol.to_uppercase(),
+                    arm.side,
+                    arm.quantity,
+                    condition,
+                );
+                trigger.limit_price = arm.limit_price;
+                check_order(&trigger.order())?;
+                let armed = store.update(|triggers| {
+                    if triggers.iter().any(|armed| armed.id == trigger.id) {
+                        return false;
+                    }
+                    triggers.push(trigger.clone());
+                    true
+                })?;
+                if !armed {
+                    bail!("trigger {} is already armed, arm it again", trigger.id);
+                }
+                self.output.record(&trigger)
+            }
+            BotCommand::List => {
+                let triggers = store
+                    .load()?
+                    .into_iter()
+                    .filter(|trigger| trigger.profile == self.profile.name)
+                    .collect::<Vec<_>>();
+                self.output.records(&triggers)
+            }
+            BotCommand::Disarm { id } => {
+                let removed = store.update(|triggers| {
+                    let index = triggers.iter().position(|trigger| {
+                        trigger.id == id && trigger.profile == self.profile.name
+                    })?;
+                    Some(triggers.remove(index))
+                })?;
+                match removed {
+                    Some(trigger) => self.output.record(&trigger),
+                    None => bail!(
+                        "no trigger {} of profile {} in {}",
+                        id,
+                        self.profile.name,
+                        store.path().display()
+                    ),
+                }
+            }
+        }
+    }
+
+    fn order_list(&self, command: OrderListCommand, dry_run: bool) -> Result<()> {
+        match command {
+            OrderListCommand::New { file } => {
+                let order_list = BinanceRepo::read_order_list_from_file(&file)?;
+                let errors = order_list.validate();
+                if !errors.is_empty() {
+                    bail!("invalid order list: {}", errors.join("; "));
+                }
+                if dry_run {
+                    // Binance has no test endpoint for order lists, check every leg on its own
+                    let checker = self.dry_runner();
+                    let mut reports = vec![];
+                    for (leg, order) in order_list.legs() {
+                        let report = checker.check(&order)?;
+                        reports.push(OrderListLegReport { leg, report });
+                    }
+                    self.output.records(&reports)?;
+                    let invalid = reports.iter().filter(|r| !r.report.valid).count();
+                    if invalid > 0 {
+                        bail!("{} of {} orders are invalid", invalid, reports.len());
+                    }
+                    return Ok(());
+                }
+                let order_list = self.risk().make_order_list(order_list)?;
+                self.print_order_list(&order_list)
+            }
+            OrderListCommand::Get { order_list_id } => {
+                let order_list = self.binance.get_order_list(order_list_id)?;
+                self.print_order_list(&order_list)
+            }
+            OrderListCommand::Open => {
+                let order_lists = self.binance.get_open_order_lists()?;
+                self.output
+                    .records_with_columns(&order_lists, ORDER_LIST_TABLE_COLUMNS)
+            }
+            OrderListCommand::Cancel {
+                symbol,
+                order_list_id,
+            } => {
+                let order_list = self
+                    .binance
+                    .cancel_order_list(&symbol.to_uppercase(), order_list_id)?;
+                self.print_order_list(&order_list)
+            }
+        }
+    }
+
+    fn margin(&self, command: MarginCommand, dry_run: bool) -> Result<()> {
+        match command {
+            MarginCommand::Account { isolated, symbols } => {
+                if isolated {
+                    let symbols = symbols.iter().map(|s| s.to_uppercase()).collect::<Vec<_>>();
+                    let account = self.binance.get_isolated_margin_account(&symbols)?;
+                    if self.output.format() != OutputFormat::Table {
+                        return self.output.record(&account);
+                    }
+                    return self
+                        .output
+                        .records_with_columns(&account.assets, ISOLATED_MARGIN_TABLE_COLUMNS);
+                }
+                let account = self.binance.get_margin_account()?;
+                if self.output.format() != OutputFormat::Table {
+                    return self.output.record(&account);
+                }
+                self.output
+                    .records_with_columns(&[&account], MARGIN_ACCOUNT_TABLE_COLUMNS)?;
+                self.output.separator()?;
+                // Every asset of the margin account is listed, most of them empty
+                let assets = account
+                    .user_assets
+                    .iter()
+                    .filter(|asset| {
+                        asset.free.value()
+                            + asset.locked.value()
+                            + asset.borrowed.value()
+                            + asset.interest.value()
+                            != 0.0
+                    })
+                    .collect::<Vec<_>>();
+                self.output.records(&assets)
+            }
+            MarginCommand::Borrow(loan) => {
+                self.borrow_repay(BinanceBorrowRepayType::Borrow, loan, dry_run)
+            }
+            MarginCommand::Repay(loan) => {
+                self.borrow_repay(BinanceBorrowRepayType::Repay, loan, dry_run)
+            }
+            MarginCommand::Buy(trade) => self.margin_trade(BinanceOrderSide::Buy, trade, dry_run),
+            MarginCommand::Sell(trade) => self.margin_trade(BinanceOrderSide::Sell, trade, dry_run),
+            MarginCommand::Orders { symbol, isolated } => {
+                let symbol = symbol.map(|s| s.to_uppercase());
+                let orders = self
+                    .binance
+                    .get_margin_open_orders(symbol.as_deref(), isolated)?;
+                let columns = [ORDER_TABLE_COLUMNS, &["isIsolated"]].concat();
+                self.output.records_with_columns(&orders, &columns)
+            }
+            MarginCommand::Cancel {
+                symbol,
+                order_id,
+                isolated,
+            } => {
+                let order =
+                    self.binance
+                        .cancel_margin_order(&symbol.to_uppercase(), order_id, isolated)?;
+                self.output.record(&order)
+            }
+            MarginCommand::Interest {
+                asset,
+                isolated,
+                limit,
+            } => {
+                let asset = asset.map(|a| a.to_uppercase());
+                let isolated = isolated.map(|s| s.to_uppercase());
+                let history = self.binance.get_margin_interest_history(
+                    asset.as_deref(),
+                    isolated.as_deref(),
+                    limit.clamp(1, 100),
+                )?;
+                self.output
+                    .records_with_columns(&history.rows, MARGIN_INTEREST_TABLE_COLUMNS)
+            }
+            MarginCommand::MaxBorrowable { asset, isolated } => {
+                let isolated = isolated.map(|s| s.to_uppercase());
+                let max = self
+                    .binance
+                    .get_margin_max_borrowable(&asset.to_uppercase(), isolated.as_deref())?;
+                self.output.record(&max)
+            }
+        }
+    }
+
+    /// Borrow or repay once confirmed. The dry run only shows the request.
+    fn borrow_repay(
+        &self,
+        kind: BinanceBorrowRepayType,
+        loan: LoanArgs,
+        dry_run: bool,
+    ) -> Result<()> {
+        if loan.amount <= 0.0 {
+            bail!("amount must be positive");
+        }
+        let request = BinanceBorrowRepayRequest::new(
+            kind,
+            loan.asset.to_uppercase(),
+            loan.amount,
+            loan.isolated.map(|s| s.to_uppercase()),
+        );
+        if dry_run {
+            return self.output.record(&request);
+        }
+        if !loan.yes {
+            let account = match &request.symbol {
+                Some(symbol) => format!("isolated pair {symbol}"),
+                None => "cross margin account".to_owned(),
+            };
+            eprintln!(
+                "{:?} {} {} on the {}",
+                kind, loan.amount, request.asset, account
+            );
+            if kind == BinanceBorrowRepayType::Borrow {
+                let max = self
+                    .binance
+                    .get_margin_max_borrowable(&request.asset, request.symbol.as_deref())?;
+                eprintln!("  max borrowable  {}", max.amount);
+            }
+            if !confirm("Proceed?")? {
+                bail!("{kind:?} cancelled");
+            }
+        }
+        let transaction = self.binance.margin_borrow_repay(&request)?;
+        self.output.record(&transaction)
+    }
+
+    /// Build the margin order from the flags and place it once confirmed. The margin API
+    /// has no test endpoint, the dry run checks the order as a spot order.
+    fn margin_trade(
+        &self,
+        side: BinanceOrderSide,
+        args: MarginTradeArgs,
+        dry_run: bool,
+    ) -> Result<()> {
+        let mut order = BinanceMarginOrderRequest::new(args.trade.order(side));
+        order.is_isolated = args.isolated;
+        order.side_effect_type = args.side_effect;
+        order.auto_repay_at_cancel = args.auto_repay_at_cancel.then_some(true);
+        let errors = order.validate();
+        if !errors.is_empty() {
+            bail!("invalid order: {}", errors.join("; "));
+        }
+        if dry_run {
+            return self.dry_run(&order.order);
+        }
+        if !args.trade.yes {
+            let spot = &order.order;
+            let account = match order.is_isolated {
+                true => "isolated pair",
+                false => "cross margin account",
+            };
+            eprintln!(
+                "{:?} {:?} {} on the {}",
+                spot.side, spot.order_type, spot.symbol, account
+            );
+            if let Some(quantity) = spot.quantity {
+                eprintln!("  quantity     {quantity}");
+            }
+            if let Some(quote) = spot.quote_order_qty {
+                eprintln!("  quote        {quote}");
+            }
+            if let Some(price) = spot.price {
+                eprintln!("  price        {price}");
+            }
+            if let Some(side_effect) = order.side_effect_type {
+                eprintln!("  side effect  {side_effect:?}");
+            }
+            if !confirm("Place order?")? {
+                bail!("order cancelled");
+            }
+        }
+        let re = self.risk().make_margin_order(order)?;
+        self.output.record(&re)
+    }
+
+    fn futures(&self, command: FuturesCommand, dry_run: bool) -> Result<()> {
+        let futures = self.binance.futures();
+        match command {
+            FuturesCommand::Positions { symbol, all } => {
+                let symbol = symbol.map(|s| s.to_uppercase());
+                let positions = futures
+                    .get_positions(symbol.as_deref())?
+                    .into_iter()
+                    .filter(|position| all || !position.position_amt.is_zero())
+                    .collect::<Vec<_>>();
+                self.output
+                    .records_with_columns(&positions, FUTURES_POSITION_TABLE_COLUMNS)
+            }
+            FuturesCommand::Leverage { symbol, leverage } => {
+                let leverage = futures.change_leverage(&symbol.to_uppercase(), leverage)?;
+                self.output.record(&leverage)
+            }
+            FuturesCommand::MarginType {
+                symbol,
+                margin_type,
+            } => {
+                let symbol = symbol.to_uppercase();
+                futures.change_margin_type(&symbol, margin_type)?;
+                let mut record = Map::new();
+                record.insert("symbol".to_owned(), Value::from(symbol));
+                record.insert("marginType".to_owned(), Value::from(margin_type.as_str()));
+                self.output.record(&record)
+            }
+            FuturesCommand::Buy(trade) => self.futures_trade(BinanceOrderSide::Buy, trade, dry_run),
+            FuturesCommand::Sell(trade) => {
+                self.futures_trade(BinanceOrderSide::Sell, trade, dry_run)
+            }
+            FuturesCommand::Funding { symbol, limit } => {
+                let symbol = self.symbol_or_default(symbol)?.to_uppercase();
+                let rates = futures.get_funding_rates(&symbol, limit.clamp(1, 1000))?;
+                self.output.records(&rates)
+            }
+            FuturesCommand::Income {
+                symbol,
+                income_type,
+                limit,
+            } => {
+                let symbol = symbol.map(|s| s.to_uppercase());
+                let income_type = income_type.map(|t| t.to_uppercase());
+                let income = futures.get_income(
+                    symbol.as_deref(),
+                    income_type.as_deref(),
+                    limit.clamp(1, 1000),
+                )?;
+                self.output.records(&income)
+            }
+        }
+    }
+
+    /// Build the futures order from the flags and place it once confirmed
+    fn futures_trade(
+        &self,
+        side: BinanceOrderSide,
+        trade: FuturesTradeArgs,
+        dry_run: bool,
+    ) -> Result<()> {
+        let order = trade.order(side);
+        let futures = self.binance.futures();
+        let mut errors = order.validate();
+        if dry_run {
+            if errors.is_empty() {
+                if let Err(err) = futures.test_order(&order) {
+                    errors.push(format!("{err:#}"));
+                }
+            }
+            if errors.is_empty() {
+                errors.extend(self.risk().check_futures(&order)?);
             }
+            let report = FuturesDryRunReport {
+                order: &order,
+                valid: errors.is_empty(),
+                errors,
+            };
+            self.output.record(&report)?;
+            if !report.valid {
+                bail!("order is invalid: {}", report.errors.join("; "));
+            }
+            return Ok(());
+        }
+        if !errors.is_empty() {
+            bail!("invalid order: {}", errors.join("; "));
+        }
+        if !trade.yes {
+            eprintln!(
+                "{:?} {} {} futures",
+                order.side,
+                order.order_type.as_str(),
+                order.symbol
+            );
+            match order.quantity {
+                Some(quantity) => eprintln!("  quantity     {quantity}"),
+                None => eprintln!("  quantity     the whole position"),
+            }
+            if let Some(price) = order.price {
+                eprintln!("  price        {price}");
+            }
+            if let Some(stop_price) = order.stop_price {
+                eprintln!("  stop price   {stop_price}");
+            }
+            if order.reduce_only == Some(true) {
+                eprintln!("  reduce only");
+            }
+            if !confirm("Place order?")? {
+                bail!("order cancelled");
+            }
+        }
+        let re = self.risk().make_futures_order(order)?;
+        self.output.record(&re)
+    }
+
+    /// The table shows the list then its orders, the other formats the whole response
+    fn print_order_list(&self, order_list: &BinanceOrderList) -> Result<()> {
+        if self.output.format() != OutputFormat::Table {
+            return self.output.record(order_list);
+        }
+        self.output
+            .records_with_columns(&[order_list], ORDER_LIST_TABLE_COLUMNS)?;
+        self.output.separator()?;
+        match order_list.order_reports.is_empty() {
+            true => self.output.records(&order_list.orders),
+            false => self
+                .output
+                .records_with_columns(&order_list.order_reports, ORDER_TABLE_COLUMNS),
+        }
+    }
+
+    /// Resolve and place every order of the batch, continuing past failures,
+    /// then report the result of each order
+    fn submit_batch(&self, templates: &[OrderTemplate], dry_run: bool) -> Result<()> {
+        let resolver = OrderTemplateResolver::new(&self.binance);
+        if dry_run {
+            let checker = self.dry_runner();
+            let mut reports = vec![];
+            for (index, template) in templates.iter().enumerate() {
+                let report = resolver
+                    .resolve(template)
+                    .and_then(|order| checker.check(&order))
+                    .unwrap_or_else(|err| {
+                        tracing::error!("order {} - {:#}", index, err);
+                        DryRunReport::failed(
+                            template.symbol().to_owned(),
+                            BinanceOrderSide::from(template.side().to_owned()),
+                            BinanceOrderType::from(template.order_type().to_owned()),
+                            &err,
+                        )
+                    });
+                reports.push(IndexedDryRunReport { index, report });
+            }
+            self.output.records(&reports)?;
+            let invalid = reports.iter().filter(|r| !r.report.valid).count();
+            if invalid > 0 {
+                bail!("{} of {} orders are invalid", invalid, reports.len());
+            }
+            return Ok(());
+        }
+
+        let results = templates
+            .iter()
+            .enumerate()
+            .map(|(index, template)| {
+                let result = resolver
+                    .resolve(template)
+                    .and_then(|order| self.place_order(order));
+                if let Err(err) = &result {
+                    tracing::error!("order {} - {:#}", index, err);
+                }
+                BatchOrderResult::new(index, template, result)
+            })
+            .collect::<Vec<_>>();
+        self.output.records(&results)?;
+
+        let failed = results.iter().filter(|result| !result.success).count();
+        if failed > 0 {
+            bail!("{} of {} orders failed", failed, results.len());
         }
         Ok(())
     }
+
+    /// Check the order locally then place it through the risk limits
+    pub fn place_order(&self, order: BinanceSpotOrderRequest) -> Result<BinanceSpotOrder> {
+        check_order(&order)?;
+        self.risk().make_spot_order(order)
+    }
+
+    /// Every order goes through the risk limits of the profile
+    fn risk(&self) -> RiskControl<'_> {
+        let path = self
+            .profile
+            .risk
+            .state
+            .clone()
+            .unwrap_or_else(|| RiskStore::default_path(&self.profile.name));
+        RiskControl::new(&self.binance, &self.profile.risk, RiskStore::new(path))
+    }
+
+    pub fn dry_runner(&self) -> DryRun<'_> {
+        DryRun::new(&self.binance).with_risk(self.risk())
+    }
+
+    /// Check the order without placing it, failing when it is invalid
+    fn dry_run(&self, order: &BinanceSpotOrderRequest) -> Result<()> {
+        let report = self.dry_runner().check(order)?;
+        self.output.record(&report)?;
+        if !report.valid {
+            bail!("order is invalid: {}", report.errors.join("; "));
+        }
+        Ok(())
+    }
+
+    /// Build the order from the flags, show the estimated notional and fees
+    /// then place the order once confirmed
+    fn trade(&self, side: BinanceOrderSide, trade: TradeArgs, dry_run: bool) -> Result<()> {
+        let order = trade.order(side);
+        if dry_run {
+            return self.dry_run(&order);
+        }
+        check_order(&order)?;
+        if !trade.yes {
+            let price = match trade.price.or(trade.stop_price) {
+                Some(price) => price,
+                None => self.binance.get_price(&order.symbol)?.price.value(),
+            };
+            let rates = self.binance.get_account()?.commission_rates;
+            let fee_rate = match order.order_type.has_limit_price() {
+                true => rates.maker.value(),
+                false => rates.taker.value(),
+            };
+            let (quantity, notional) = match (trade.quantity, trade.quote) {
+                (Some(quantity), _) => (quantity, quantity * price),
+                (None, Some(quote)) => (quote / price, quote),
+                (None, None) => unreachable!("clap requires --quantity or --quote"),
+            };
+            eprintln!("{:?} {:?} {}", order.side, order.order_type, order.symbol);
+            eprintln!("  quantity  ~{quantity}");
+            eprintln!("  price     ~{price}");
+            eprintln!("  notional  ~{notional}");
+            eprintln!(
+                "  fee       ~{} ({}%)",
+                notional * fee_rate,
+                fee_rate * 100.0
+            );
+            if !confirm("Place order?")? {
+                bail!("order cancelled");
+            }
+        }
+
+        let re = self.risk().make_spot_order(order)?;
+        self.output.record(&re)
+    }
+
+    /// Use the symbol from the arguments or fallback to the profile defaults
+    fn symbol_or_default(&self, symbol: Option<String>) -> Result<String> {
+        symbol
+            .or_else(|| self.profile.defaults.symbol.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "symbol is required, profile {} has no default symbol",
+                    self.profile.name
+                )
+            })
+    }
+
+    /// Create a [BinanceRepo] for every profile in the config
+    fn all_profiles(&self) -> Result<Vec<(String, BinanceRepo)>> {
+        self.cfg
+            .profile_names()
+            .into_iter()
+            .map(|name| {
+                let profile = self.cfg.profile(Some(&name))?;
+                let binance =
+                    BinanceRepo::new(profile.binance.clone(), self.secrets.credentials(&profile)?)?;
+                Ok((name, binance))
+            })
+            .collect()
+    }
+
+    /// Print the total (free + locked) of each asset with one column per profile and the total
+    fn print_all_accounts(&self) -> Result<()> {
+        let profiles = self.all_profiles()?;
+        let mut totals: BTreeMap<String, BTreeMap<String, f64>> = BTreeMap::new();
+        for (name, binance) in profiles.iter() {
+            let account = match binance.get_account() {
+                Ok(account) => account,
+                Err(err) => {
+                    tracing::error!("profile {} - cannot get account: {:#}", name, err);
+                    continue;
+                }
+            };
+            for balance in account.balances {
+                let total = balance.free.value() + balance.locked.value();
+                if total == 0.0 {
+                    continue;
+                }
+                *totals
+                    .entry(balance.asset)
+                    .or_default()
+                    .entry(name.to_owned())
+                    .or_default() += total;
+            }
+        }
+
+        let records = totals
+            .into_iter()
+            .map(|(asset, per_profile)| {
+                let mut record = Map::new();
+                record.insert("asset".to_owned(), Value::from(asset));
+                for (name, _) in profiles.iter() {
+                    let total = per_profile.get(name).copied().unwrap_or_default();
+                    record.insert(name.to_owned(), Value::from(total));
+                }
+                let total = per_profile.values().sum::<f64>();
+                record.insert("total".to_owned(), Value::from(total));
+                Value::Object(record)
+            })
+            .collect::<Vec<_>>();
+        self.output.records(&records)
+    }
+}
+
+/// Fail with every problem of the order before sending it
+fn check_order(order: &BinanceSpotOrderRequest) -> Result<()> {
+    let errors = order.validate();
+    if !errors.is_empty() {
+        bail!("invalid order: {}", errors.join("; "));
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on stderr, anything but `y` or `yes` is a no
+pub fn confirm(question: &str) -> Result<bool> {
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+pub fn keystore_path(cfg: &AppConfig) -> PathBuf {
+    cfg.keystore.clone().unwrap_or_else(Keystore::default_path)
+}
+
+pub fn triggers_path(cfg: &AppConfig) -> PathBuf {
+    cfg.triggers
+        .clone()
+        .unwrap_or_else(TriggerStore::default_path)
+}
+
+/// Handle the `keys` commands. These don't need any credentials so they can
+/// run before a [CommandHandler] is created.
+pub fn handle_keys(cfg: &AppConfig, command: &KeysCommand, output: Output) -> Result<()> {
+    let mut keystore = Keystore::open(keystore_path(cfg))?;
+    match command {
+        KeysCommand::Add { name, stdin } => {
+            let secret = if *stdin {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                line.trim_end_matches(['\n', '\r']).to_owned()
+            } else {
+                rpassword::prompt_password(format!("Secret for {name}: "))?
+            };
+            let passphrase = read_passphrase(keystore.is_empty())?;
+            keystore.verify(&passphrase)?;
+            keystore.insert(name, &SecretString::new(secret), &passphrase)?;
+            keystore.save()?;
+            output.record(&KeyChange {
+                name,
+                action: "added",
+                keystore: keystore.path(),
+            })?;
+        }
+        KeysCommand::List => {
+            let keys = keystore
+                .entries()
+                .map(|(name, entry)| KeyEntry {
+                    name,
+                    created_at: entry.created_at,
+                })
+                .collect::<Vec<_>>();
+            output.records(&keys)?;
+        }
+        KeysCommand::Remove { name } => {
+            keystore.remove(name)?;
+            keystore.save()?;
+            output.record(&KeyChange {
+                name,
+                action: "removed",
+                keystore: keystore.path(),
+            })?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,671 @@
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Sparkline, Table},
+    Frame, Terminal,
+};
+
+use crate::{
+    application::command::CommandHandler,
+    domain::binance::{
+        convert_price, BinanceOrderSide, BinanceOrderType, BinanceSpotOrder,
+        BinanceSpotOrderRequest, BinanceTimeInForce, BinanceTrade, F64String,
+    },
+    infrastructure::binance_stream::{BinanceStream, BinanceStreamEvent},
+};
+
+/// Asset the balances are valued in
+const VALUATION_ASSET: &str = "USDT";
+/// Candlesticks of the chart
+const CHART_INTERVAL: &str = "1m";
+const CHART_POINTS: u32 = 120;
+/// Fills shown for the selected symbol
+const FILLS_LIMIT: u32 = 20;
+/// Wait for a key at most this long before reading the stream again
+const INPUT_POLL: Duration = Duration::from_millis(100);
+const STREAM_POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Entry,
+    Confirm,
+}
+
+/// Fields of the order entry panel
+#[derive(Debug)]
+struct OrderEntry {
+    side: BinanceOrderSide,
+    order_type: BinanceOrderType,
+    quantity: String,
+    price: String,
+    /// Index of the selected field: side, type, quantity, price
+    field: usize,
+}
+
+impl OrderEntry {
+    const FIELDS: usize = 4;
+
+    fn to_order(&self, symbol: &str) -> Result<BinanceSpotOrderRequest> {
+        let mut order = BinanceSpotOrderRequest::new(
+            symbol.to_owned(),
+            self.side.clone(),
+            self.order_type.clone(),
+        );
+        let quantity = self
+            .quantity
+            .parse::<f64>()
+            .with_context(|| format!("invalid quantity {:?}", self.quantity))?;
+        order.quantity = Some(F64String::from(quantity));
+        if self.order_type == BinanceOrderType::Limit {
+            let price = self
+                .price
+                .parse::<f64>()
+                .with_context(|| format!("invalid price {:?}", self.price))?;
+            order.price = Some(F64String::from(price));
+            order.time_in_force = Some(BinanceTimeInForce::Gtc);
+        }
+        Ok(order)
+    }
+
+    fn describe(&self, symbol: &str) -> String {
+        match self.order_type {
+            BinanceOrderType::Limit => format!(
+                "{} {} {} {} @ {}",
+                self.side, self.order_type, self.quantity, symbol, self.price
+            ),
+            _ => format!(
+                "{} {} {} {}",
+                self.side, self.order_type, self.quantity, symbol
+            ),
+        }
+    }
+}
+
+struct BalanceRow {
+    asset: String,
+    total: f64,
+    value: Option<f64>,
+}
+
+/// Restore the terminal however the dashboard ends
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+/// Live terminal view of the balances, open orders, recent fills and price of the selected
+/// symbol, with an order entry panel. The REST data is refreshed every `refresh`,
+/// the price follows the trade stream.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use binance_rs::application::{command::CommandHandler, dashboard::Dashboard};
+/// # fn example(handler: &CommandHandler) -> anyhow::Result<()> {
+/// Dashboard::new(handler, vec!["BTCUSDT".to_owned()], Duration::from_secs(10), false).run()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Dashboard<'a> {
+    handler: &'a CommandHandler,
+    symbols: Vec<String>,
+    selected: usize,
+    refresh: Duration,
+    dry_run: bool,
+    mode: Mode,
+    entry: OrderEntry,
+    status: String,
+    balances: Vec<BalanceRow>,
+    total_value: f64,
+    open_orders: Vec<BinanceSpotOrder>,
+    fills: Vec<BinanceTrade>,
+    /// Close of each candlestick, the last one follows the trades
+    closes: Vec<f64>,
+    last_open_time: Option<DateTime<Utc>>,
+    last_price: Option<f64>,
+}
+
+impl<'a> Dashboard<'a> {
+    pub fn new(
+        handler: &'a CommandHandler,
+        symbols: Vec<String>,
+        refresh: Duration,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            handler,
+            symbols: symbols.iter().map(|s| s.to_uppercase()).collect(),
+            selected: 0,
+            refresh,
+            dry_run,
+            mode: Mode::Normal,
+            entry: OrderEntry {
+                side: BinanceOrderSide::Buy,
+                order_type: BinanceOrderType::Limit,
+                quantity: String::new(),
+                price: String::new(),
+                field: 0,
+            },
+            status: "q quit  n next symbol  o order entry  r refresh".to_owned(),
+            balances: vec![],
+            total_value: 0.0,
+            open_orders: vec![],
+            fills: vec![],
+            closes: vec![],
+            last_open_time: None,
+            last_price: None,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        enable_raw_mode().context("cannot enable raw mode")?;
+        let _guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen).context("cannot enter alternate screen")?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+        'symbols: loop {
+            self.load_chart();
+            self.refresh_rest();
+            let mut refreshed_at = Instant::now();
+            let mut stream = self.subscribe();
+            loop {
+                terminal.draw(|frame| self.draw(frame))?;
+                if event::poll(INPUT_POLL)? {
+                    if let Event::Key(key) = event::read()? {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        match self.on_key(key) {
+                            Action::None => {}
+                            Action::Quit => break 'symbols,
+                            Action::Refresh => refreshed_at -= self.refresh,
+                            Action::NextSymbol => continue 'symbols,
+                        }
+                    }
+                }
+                if let Some(current) = stream.as_mut() {
+                    if let Err(err) = self.read_trades(current) {
+                        self.status = format!("stream: {err:#}");
+                        stream = None;
+                    }
+                }
+                if refreshed_at.elapsed() >= self.refresh {
+                    self.refresh_rest();
+                    if stream.is_none() {
+                        stream = self.subscribe();
+                    }
+                    refreshed_at = Instant::now();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbols[self.selected]
+    }
+
+    fn subscribe(&mut self) -> Option<BinanceStream> {
+        let endpoint = &self.handler.profile().binance.stream_endpoint;
+        let result = BinanceStream::subscribe(endpoint, &[BinanceStream::agg_trade(self.symbol())])
+            .and_then(|mut stream| {
+                stream.set_read_timeout(STREAM_POLL)?;
+                Ok(stream)
+            });
+        match result {
+            Ok(stream) => Some(stream),
+            Err(err) => {
+                self.status = format!("stream: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Read the trades waiting on the stream
+    fn read_trades(&mut self, stream: &mut BinanceStream) -> Result<()> {
+        while let Some(event) = stream.next_event()? {
+            if let BinanceStreamEvent::AggTrade(trade) = event {
+                self.on_price(trade.price.value());
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the last candlestick, or start a new one once its interval is over
+    fn on_price(&mut self, price: f64) {
+        self.last_price = Some(price);
+        let now = Utc::now();
+        match self.last_open_time {
+            Some(open) if now - open >= chrono::Duration::minutes(1) => {
+                self.closes.push(price);
+                if self.closes.len() > CHART_POINTS as usize {
+                    self.closes.remove(0);
+                }
+                let minutes = (now - open).num_minutes();
+                self.last_open_time = Some(open + chrono::Duration::minutes(minutes));
+            }
+            _ => match self.closes.last_mut() {
+                Some(close) => *close = price,
+                None => {
+                    self.closes.push(price);
+                    self.last_open_time = Some(now);
+                }
+            },
+        }
+    }
+
+    fn load_chart(&mut self) {
+        self.closes.clear();
+        self.last_open_time = None;
+        self.last_price = None;
+        let binance = self.handler.binance();
+        match binance.get_klines(self.symbol(), CHART_INTERVAL, CHART_POINTS) {
+            Ok(klines) => {
+                self.closes = klines.iter().map(|kline| kline.close).collect();
+                self.last_open_time = klines.last().map(|kline| kline.open_time);
+                self.last_price = self.closes.last().copied();
+            }
+            Err(err) => self.status = format!("klines: {err:#}"),
+        }
+    }
+
+    /// Read the balances, open orders and fills again, keeping the previous data on failure
+    fn refresh_rest(&mut self) {
+        let binance = self.handler.binance();
+        let balances = binance.get_account().and_then(|account| {
+            let prices = binance
+                .get_prices()?
+                .into_iter()
+                .map(|price| (price.symbol, price.price.value()))
+                .collect::<HashMap<_, _>>();
+            Ok(account
+                .balances
+                .into_iter()
+                .map(|balance| {
+                    let total = balance.free.value() + balance.locked.value();
+                    let value = convert_price(&prices, &balance.asset, VALUATION_ASSET)
+                        .map(|price| price * total);
+                    BalanceRow {
+                        asset: balance.asset,
+                        total,
+                        value,
+                    }
+                })
+                .filter(|row| row.total > 0.0)
+                .collect::<Vec<_>>())
+        });
+        match balances {
+            Ok(balances) => {
+                self.total_value = balances.iter().filter_map(|row| row.value).sum();
+                self.balances = balances;
+            }
+            Err(err) => self.status = format!("account: {err:#}"),
+        }
+        match binance.get_open_orders() {
+            Ok(orders) => self.open_orders = orders,
+            Err(err) => self.status = format!("open orders: {err:#}"),
+        }
+        match binance.get_my_trades(self.symbol(), FILLS_LIMIT) {
+            Ok(mut fills) => {
+                fills.reverse();
+                self.fills = fills;
+            }
+            Err(err) => self.status = format!("fills: {err:#}"),
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Action {
+        match self.mode {
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+                KeyCode::Char('r') => return Action::Refresh,
+                KeyCode::Char('n') => {
+                    self.selected = (self.selected + 1) % self.symbols.len();
+                    return Action::NextSymbol;
+                }
+                KeyCode::Char('o') => {
+                    self.mode = Mode::Entry;
+                    self.status = "tab next field  space toggle  enter place  esc leave".to_owned();
+                }
+                _ => {}
+            },
+            Mode::Entry => match key.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    self.status.clear();
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    self.entry.field = (self.entry.field + 1) % OrderEntry::FIELDS
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    self.entry.field =
+                        (self.entry.field + OrderEntry::FIELDS - 1) % OrderEntry::FIELDS
+                }
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right => self.toggle(),
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                    if let Some(text) = self.entry_text() {
+                        text.push(c);
+                    }
+                }
+                KeyCode::Backspace => {
+                    if let Some(text) = self.entry_text() {
+                        text.pop();
+                    }
+                }
+                KeyCode::Enter => self.submit(),
+                _ => {}
+            },
+            Mode::Confirm => {
+                self.mode = Mode::Entry;
+                match key.code {
+                    KeyCode::Char('y') => {
+                        self.place();
+                        return Action::Refresh;
+                    }
+                    _ => self.status = "order not placed".to_owned(),
+                }
+            }
+        }
+        Action::None
+    }
+
+    fn toggle(&mut self) {
+        match self.entry.field {
+            0 => {
+                self.entry.side = match self.entry.side {
+                    BinanceOrderSide::Buy => BinanceOrderSide::Sell,
+                    _ => BinanceOrderSide::Buy,
+                }
+            }
+            1 => {
+                self.entry.order_type = match self.entry.order_type {
+                    BinanceOrderType::Limit => BinanceOrderType::Market,
+                    _ => BinanceOrderType::Limit,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn entry_text(&mut self) -> Option<&mut String> {
+        match self.entry.field {
+            2 => Some(&mut self.entry.quantity),
+            3 if self.entry.order_type == BinanceOrderType::Limit => Some(&mut self.entry.price),
+            _ => None,
+        }
+    }
+
+    /// Check the order, then ask for confirmation, or show the dry run report
+    fn submit(&mut self) {
+        let order = match self.entry.to_order(self.symbol()) {
+            Ok(order) => order,
+            Err(err) => {
+                self.status = format!("{err:#}");
+                return;
+            }
+        };
+        if self.dry_run {
+            self.status = match self.handler.dry_runner().check(&order) {
+                Ok(report) if report.valid => format!(
+                    "dry run: valid, notional ~{:.2}, commission ~{:.4}",
+                    report.notional.unwrap_or_default(),
+                    report.expected_commission.unwrap_or_default()
+                ),
+                Ok(report) => format!("dry run: {}", report.errors.join("; ")),
+                Err(err) => format!("dry run: {err:#}"),
+            };
+            return;
+        }
+        self.mode = Mode::Confirm;
+        self.status = format!("place {}? y to confirm", self.entry.describe(self.symbol()));
+    }
+
+    fn place(&mut self) {
+        let result = self
+            .entry
+            .to_order(self.symbol())
+            .and_then(|order| self.handler.place_order(order));
+        self.status = match result {
+            Ok(order) => format!(
+                "placed order {} {}",
+                order.order_id.unwrap_or_default(),
+                order.status.map(|s| s.to_string()).unwrap_or_default()
+            ),
+            Err(err) => format!("{err:#}"),
+        };
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+                Constraint::Min(8),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+        let middle = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(rows[1]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[2]);
+
+        self.draw_chart(frame, rows[0]);
+        self.draw_balances(frame, middle[0]);
+        self.draw_open_orders(frame, middle[1]);
+        self.draw_fills(frame, bottom[0]);
+        self.draw_entry(frame, bottom[1]);
+        let mode = match self.dry_run {
+            true => " DRY RUN ",
+            false => "",
+        };
+        let status = Line::from(vec![
+            Span::styled(mode, Style::default().fg(Color::Black).bg(Color::Yellow)),
+            Span::raw(format!(" {}", self.status)),
+        ]);
+        frame.render_widget(Paragraph::new(status), rows[3]);
+    }
+
+    fn draw_chart(&self, frame: &mut Frame, area: Rect) {
+        let min = self.closes.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self
+            .closes
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let range = (max - min).max(f64::EPSILON);
+        // Leave the lowest close one step above the bottom so it stays visible
+        let data = self
+            .closes
+            .iter()
+            .map(|close| ((close - min) / range * 100.0) as u64 + 1)
+            .collect::<Vec<_>>();
+        // Show the most recent candlesticks that fit
+        let width = area.width.saturating_sub(2) as usize;
+        let data = &data[data.len().saturating_sub(width)..];
+        let title = match self.last_price {
+            Some(price) => format!(
+                " {}  {}  low {}  high {}  ({} {}) ",
+                self.symbol(),
+                price,
+                min,
+                max,
+                self.closes.len(),
+                CHART_INTERVAL
+            ),
+            None => format!(" {} ", self.symbol()),
+        };
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(data)
+            .style(Style::default().fg(Color::Green));
+        frame.render_widget(sparkline, area);
+    }
+
+    fn draw_balances(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.balances.iter().map(|row| {
+            Row::new(vec![
+                row.asset.to_owned(),
+                format!("{}", row.total),
+                row.value.map(|v| format!("{v:.2}")).unwrap_or_default(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ],
+        )
+        .header(header(&["asset", "total", VALUATION_ASSET]))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Balances  {:.2} {} ",
+            self.total_value, VALUATION_ASSET
+        )));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_open_orders(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.open_orders.iter().map(|order| {
+            Row::new(vec![
+                order.symbol.clone().unwrap_or_default(),
+                order
+                    .side
+                    .as_ref()
+                    .map(|s| s.to_string())
+                    .unwrap_or_default(),
+                order
+                    .binance_spot_order_type
+                    .as_ref()
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                order.price.unwrap_or_default().to_string(),
+                order.orig_qty.unwrap_or_default().to_string(),
+                order.executed_qty.unwrap_or_default().to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(5),
+                Constraint::Length(16),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ],
+        )
+        .header(header(&[
+            "symbol", "side", "type", "price", "qty", "filled",
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Open orders  {} ", self.open_orders.len())),
+        );
+        frame.render_widget(table, area);
+    }
+
+    fn draw_fills(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.fills.iter().map(|fill| {
+            Row::new(vec![
+                fill.time.format("%m-%d %H:%M:%S").to_string(),
+                match fill.is_buyer {
+                    true => "BUY".to_owned(),
+                    false => "SELL".to_owned(),
+                },
+                fill.price.to_string(),
+                fill.qty.to_string(),
+                format!("{} {}", fill.commission, fill.commission_asset),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(14),
+                Constraint::Length(5),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+            ],
+        )
+        .header(header(&["time", "side", "price", "qty", "commission"]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Recent fills {} ", self.symbol())),
+        );
+        frame.render_widget(table, area);
+    }
+
+    fn draw_entry(&self, frame: &mut Frame, area: Rect) {
+        let price = match self.entry.order_type {
+            BinanceOrderType::Limit => self.entry.price.to_owned(),
+            _ => "market".to_owned(),
+        };
+        let fields = [
+            ("side", self.entry.side.to_string()),
+            ("type", self.entry.order_type.to_string()),
+            ("quantity", self.entry.quantity.to_owned()),
+            ("price", price),
+        ];
+        let lines = fields
+            .iter()
+            .enumerate()
+            .map(|(index, (name, value))| {
+                let style = match self.mode != Mode::Normal && index == self.entry.field {
+                    true => Style::default().add_modifier(Modifier::REVERSED),
+                    false => Style::default(),
+                };
+                Line::from(vec![
+                    Span::raw(format!("{name:<10}")),
+                    Span::styled(format!(" {value} "), style),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let title = match self.mode {
+            Mode::Normal => format!(" Order {}  (o) ", self.symbol()),
+            _ => format!(" Order {} ", self.symbol()),
+        };
+        let paragraph =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+        frame.render_widget(paragraph, area);
+    }
+}
+
+enum Action {
+    None,
+    Quit,
+    Refresh,
+    NextSymbol,
+}
+
+fn header(columns: &[&str]) -> Row<'static> {
+    Row::new(columns.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+        .style(Style::default().add_modifier(Modifier::BOLD))
+}
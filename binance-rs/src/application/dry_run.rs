@@ -0,0 +1,144 @@
+use std::{cell::RefCell, collections::HashMap, slice};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    application::risk::RiskControl,
+    domain::binance::{
+        BinanceOrderSide, BinanceOrderType, BinanceSpotOrderRequest, BinanceSymbolInfo,
+    },
+    infrastructure::binance_repo::BinanceRepo,
+};
+
+/// Result of checking an order without placing it
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub symbol: String,
+    pub side: BinanceOrderSide,
+    #[serde(rename = "type")]
+    pub order_type: BinanceOrderType,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    pub notional: Option<f64>,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    #[serde(rename = "commissionRate")]
+    pub commission_rate: Option<f64>,
+    #[serde(rename = "expectedCommission")]
+    pub expected_commission: Option<f64>,
+    #[serde(rename = "commissionAsset")]
+    pub commission_asset: Option<String>,
+}
+
+impl DryRunReport {
+    /// Report of an order that could not be checked at all, e.g. an unknown symbol
+    pub fn failed(
+        symbol: String,
+        side: BinanceOrderSide,
+        order_type: BinanceOrderType,
+        error: &anyhow::Error,
+    ) -> Self {
+        Self {
+            symbol,
+            side,
+            order_type,
+            quantity: None,
+            price: None,
+            notional: None,
+            valid: false,
+            errors: vec![format!("{:#}", error)],
+            commission_rate: None,
+            expected_commission: None,
+            commission_asset: None,
+        }
+    }
+}
+
+/// Check orders locally against the symbol rules then with Binance's test order endpoint.
+/// Nothing reaches the matching engine.
+pub struct DryRun<'a> {
+    binance: &'a BinanceRepo,
+    risk: Option<RiskControl<'a>>,
+    symbols: RefCell<HashMap<String, BinanceSymbolInfo>>,
+}
+
+impl<'a> DryRun<'a> {
+    pub fn new(binance: &'a BinanceRepo) -> Self {
+        Self {
+            binance,
+            risk: None,
+            symbols: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Also report the risk limits the orders would break
+    pub fn with_risk(mut self, risk: RiskControl<'a>) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    pub fn check(&self, order: &BinanceSpotOrderRequest) -> Result<DryRunReport> {
+        let mut errors = order.validate();
+        let info = self.symbol(&order.symbol)?;
+        // Conditional orders without a limit price are estimated at their trigger price
+        let price = match order.price.or(order.stop_price) {
+            Some(price) => price.value(),
+            None => self.binance.get_price(&order.symbol)?.price.value(),
+        };
+        errors.extend(info.check_order(order, price));
+        if let Some(risk) = &self.risk {
+            errors.extend(risk.check(slice::from_ref(order))?);
+        }
+
+        let quantity = order.quantity.map(|q| q.value());
+        let notional = quantity
+            .map(|quantity| quantity * price)
+            .or_else(|| order.quote_order_qty.map(|q| q.value()));
+        let mut report = DryRunReport {
+            symbol: order.symbol.to_owned(),
+            side: order.side.clone(),
+            order_type: order.order_type.clone(),
+            quantity,
+            price: Some(price),
+            notional,
+            valid: false,
+            errors: vec![],
+            commission_rate: None,
+            expected_commission: None,
+            commission_asset: None,
+        };
+
+        // Only ask Binance once the order passes the local checks
+        if errors.is_empty() {
+            match self.binance.test_spot_order(order.clone()) {
+                Ok(commission) => {
+                    let rate = commission.standard_commission_for_order.map(|rate| {
+                        match order.order_type.has_limit_price() {
+                            true => rate.maker.value(),
+                            false => rate.taker.value(),
+                        }
+                    });
+                    report.commission_rate = rate;
+                    report.expected_commission = rate.zip(notional).map(|(r, n)| r * n);
+                    report.commission_asset = Some(info.quote_asset.to_owned());
+                }
+                Err(err) => errors.push(format!("{err:#}")),
+            }
+        }
+        report.valid = errors.is_empty();
+        report.errors = errors;
+        Ok(report)
+    }
+
+    fn symbol(&self, symbol: &str) -> Result<BinanceSymbolInfo> {
+        if let Some(info) = self.symbols.borrow().get(symbol) {
+            return Ok(info.clone());
+        }
+        let info = self.binance.get_symbol_info(symbol)?;
+        self.symbols
+            .borrow_mut()
+            .insert(symbol.to_owned(), info.clone());
+        Ok(info)
+    }
+}
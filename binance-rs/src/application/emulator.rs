@@ -0,0 +1,342 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    application::{dry_run::DryRun, output::Output, risk::RiskControl},
+    domain::{
+        binance::{BinanceOrderSide, BinanceOrderStatus},
+        trigger::Trigger,
+    },
+    infrastructure::{
+        binance_repo::BinanceRepo,
+        binance_stream::{BinanceStream, BinanceStreamEvent},
+        trigger_store::TriggerStore,
+    },
+};
+
+/// How long to wait for a trade before checking the trigger file for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Wait before reconnecting a dropped stream
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Wait before placing the order of a trigger again after its first failure,
+/// doubled after each following one
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Disarm a trigger whose order failed this many times
+const MAX_ATTEMPTS: u32 = 5;
+/// Write the trailing stop extremes at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What happened to a fired trigger
+#[derive(Debug, Serialize)]
+pub struct TriggerEvent {
+    pub id: String,
+    pub symbol: String,
+    pub side: BinanceOrderSide,
+    pub condition: &'static str,
+    /// Last trade price that fired the trigger
+    pub price: f64,
+    pub placed: bool,
+    /// Orders tried for the trigger, the trigger is disarmed after [MAX_ATTEMPTS] failures
+    pub attempts: u32,
+    #[serde(rename = "orderId")]
+    pub order_id: Option<i64>,
+    pub status: Option<BinanceOrderStatus>,
+    #[serde(rename = "executedQty")]
+    pub executed_qty: Option<Decimal>,
+    pub error: Option<String>,
+}
+
+/// Client-side stop loss, take profit and trailing stop orders.
+/// Watches the trades of the symbols of the triggers armed by the profile and places
+/// their order with [RiskControl::make_spot_order] once their condition is met.
+/// A trigger is removed from the store before its order is sent, so a crash never
+/// places it twice, and armed again when the order fails.
+///
+/// ```no_run
+/// use binance_rs::{
+///     application::{emulator::OrderEmulator, output::Output, risk::RiskControl},
+///     infrastructure::{binance_repo::BinanceRepo, trigger_store::TriggerStore},
+/// };
+/// # fn example(
+/// #     binance: &BinanceRepo,
+/// #     risk: RiskControl,
+/// #     stream_endpoint: &str,
+/// #     store: &TriggerStore,
+/// #     output: Output,
+/// # ) -> anyhow::Result<()> {
+/// OrderEmulator::new(binance, "default", risk, stream_endpoint, store, output, false).run()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrderEmulator<'a> {
+    binance: &'a BinanceRepo,
+    profile: &'a str,
+    risk: RiskControl<'a>,
+    stream_endpoint: &'a str,
+    store: &'a TriggerStore,
+    output: Output,
+    dry_run: bool,
+}
+
+/// Triggers being watched and what is left to do with them
+struct Armed {
+    triggers: Vec<Trigger>,
+    retries: HashMap<String, Retry>,
+    unsaved: bool,
+    saved_at: Instant,
+}
+
+/// Failed orders of a trigger and when to try again
+struct Retry {
+    attempts: u32,
+    at: Instant,
+}
+
+impl<'a> OrderEmulator<'a> {
+    /// With `dry_run` fired triggers check their order with the test order endpoint
+    /// and stay armed in the store
+    pub fn new(
+        binance: &'a BinanceRepo,
+        profile: &'a str,
+        risk: RiskControl<'a>,
+        stream_endpoint: &'a str,
+        store: &'a TriggerStore,
+        output: Output,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            binance,
+            profile,
+            risk,
+            stream_endpoint,
+            store,
+            output,
+            dry_run,
+        }
+    }
+
+    /// Run until stopped, reconnecting the stream when it drops or the symbols change
+    pub fn run(&self) -> Result<()> {
+        let mut armed = Armed {
+            triggers: self.load()?,
+            retries: HashMap::new(),
+            unsaved: false,
+            saved_at: Instant::now(),
+        };
+        tracing::info!(
+            "{} triggers armed by profile {} in {}",
+            armed.triggers.len(),
+            self.profile,
+            self.store.path().display()
+        );
+        loop {
+            let symbols = symbols(&armed.triggers);
+            if symbols.is_empty() {
+                thread::sleep(POLL_INTERVAL);
+                self.reload(&mut armed)?;
+                continue;
+            }
+            if let Err(err) = self.watch(&mut armed, &symbols) {
+                tracing::error!("{:#}, reconnecting in {:?}", err, RECONNECT_DELAY);
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+
+    /// Follow the trades of the symbols until the symbols of the triggers change
+    fn watch(&self, armed: &mut Armed, symbols: &BTreeSet<String>) -> Result<()> {
+        let streams = symbols
+            .iter()
+            .map(|symbol| BinanceStream::agg_trade(symbol))
+            .collect::<Vec<_>>();
+        let mut stream = BinanceStream::subscribe(self.stream_endpoint, &streams)?;
+        stream.set_read_timeout(POLL_INTERVAL)?;
+        let mut modified = self.store.modified();
+        loop {
+            if self.store.modified() != modified {
+                self.reload(armed)?;
+                modified = self.store.modified();
+                if self::symbols(&armed.triggers) != *symbols {
+                    return Ok(());
+                }
+            }
+            if armed.unsaved && armed.saved_at.elapsed() >= SAVE_INTERVAL {
+                self.save_extremes(armed)?;
+                modified = self.store.modified();
+            }
+            if let Some(BinanceStreamEvent::AggTrade(trade)) = stream.next_event()? {
+                if self.on_trade(armed, &trade.symbol, trade.price.value())? {
+                    modified = self.store.modified();
+                }
+                if self::symbols(&armed.triggers) != *symbols {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Update the triggers of the symbol with the trade price and place the orders of
+    /// the fired ones. Return whether the store was written.
+    fn on_trade(&self, armed: &mut Armed, symbol: &str, price: f64) -> Result<bool> {
+        let mut fired = vec![];
+        for trigger in armed.triggers.iter_mut().filter(|t| t.symbol == symbol) {
+            let before = trigger.condition.clone();
+            let waiting =
+                matches!(armed.retries.get(&trigger.id), Some(retry) if Instant::now() < retry.at);
+            if trigger.update(price) && !waiting {
+                fired.push(trigger.clone());
+            } else if trigger.condition != before {
+                armed.unsaved = true;
+            }
+        }
+        if fired.is_empty() {
+            return Ok(false);
+        }
+
+        // Every trigger is settled in the store before any event is written,
+        // a failing output can't leave a placed trigger armed
+        let mut events = vec![];
+        for trigger in fired {
+            events.push(self.fire(armed, trigger, price)?);
+        }
+        for event in events {
+            self.output.record(&event)?;
+        }
+        Ok(!self.dry_run)
+    }
+
+    /// Disarm the trigger then place its order, arming it again for a later attempt
+    /// when the order fails
+    fn fire(&self, armed: &mut Armed, trigger: Trigger, price: f64) -> Result<TriggerEvent> {
+        let attempts = armed.retries.get(&trigger.id).map_or(0, |r| r.attempts) + 1;
+        let mut event = TriggerEvent {
+            id: trigger.id.to_owned(),
+            symbol: trigger.symbol.to_owned(),
+            side: trigger.side.clone(),
+            condition: trigger.condition.name(),
+            price,
+            placed: false,
+            attempts,
+            order_id: None,
+            status: None,
+            executed_qty: None,
+            error: None,
+        };
+        armed.triggers.retain(|t| t.id != trigger.id);
+        if !self.dry_run {
+            self.store
+                .update(|stored| stored.retain(|t| t.id != trigger.id))?;
+        }
+
+        let order = trigger.order();
+        let result = match self.dry_run {
+            true => DryRun::new(self.binance)
+                .with_risk(self.risk.clone())
+                .check(&order)
+                .and_then(|report| match report.valid {
+                    true => Ok(None),
+                    false => Err(anyhow!(report.errors.join("; "))),
+                }),
+            false => self.risk.make_spot_order(order).map(Some),
+        };
+        match result {
+            Ok(order) => {
+                armed.retries.remove(&trigger.id);
+                event.placed = true;
+                if let Some(order) = order {
+                    tracing::info!("trigger {} placed order {:?}", trigger.id, order.order_id);
+                    event.order_id = order.order_id;
+                    event.status = order.status;
+                    event.executed_qty = order.executed_qty;
+                }
+            }
+            Err(err) if attempts >= MAX_ATTEMPTS => {
+                tracing::error!(
+                    "trigger {} cannot place order, disarmed after {} attempts: {:#}",
+                    trigger.id,
+                    attempts,
+                    err
+                );
+                armed.retries.remove(&trigger.id);
+                event.error = Some(format!("{err:#}, disarmed after {attempts} attempts"));
+            }
+            Err(err) => {
+                let delay = RETRY_DELAY * 2u32.pow(attempts - 1);
+                tracing::error!(
+                    "trigger {} cannot place order, retrying in {:?}: {:#}",
+                    trigger.id,
+                    delay,
+                    err
+                );
+                event.error = Some(format!("{err:#}"));
+                armed.retries.insert(
+                    trigger.id.to_owned(),
+                    Retry {
+                        attempts,
+                        at: Instant::now() + delay,
+                    },
+                );
+                if !self.dry_run {
+                    // Still retried by this run, only a restart would lose it
+                    if let Err(err) = self.store.update(|stored| stored.push(trigger.clone())) {
+                        tracing::error!(
+                            "trigger {} cannot be stored again, kept until `bot run` stops: {:#}",
+                            trigger.id,
+                            err
+                        );
+                    }
+                }
+                armed.triggers.push(trigger);
+            }
+        }
+        Ok(event)
+    }
+
+    /// The triggers of the store armed by the profile
+    fn load(&self) -> Result<Vec<Trigger>> {
+        let mut triggers = self.store.load()?;
+        triggers.retain(|t| t.profile == self.profile);
+        Ok(triggers)
+    }
+
+    /// Read the triggers changed by `bot arm` or `bot disarm`,
+    /// keeping the extremes followed in memory that are not saved yet
+    fn reload(&self, armed: &mut Armed) -> Result<()> {
+        let mut triggers = self.load()?;
+        for trigger in triggers.iter_mut() {
+            if let Some(current) = armed.triggers.iter().find(|t| t.id == trigger.id) {
+                trigger.condition = current.condition.clone();
+            }
+        }
+        armed.triggers = triggers;
+        Ok(())
+    }
+
+    /// Write the trailing stop extremes so a restart continues from them
+    fn save_extremes(&self, armed: &mut Armed) -> Result<()> {
+        if !self.dry_run {
+            let triggers = &armed.triggers;
+            self.store.update(|stored| {
+                for trigger in stored.iter_mut().filter(|t| t.profile == self.profile) {
+                    if let Some(current) = triggers.iter().find(|t| t.id == trigger.id) {
+                        trigger.condition = current.condition.clone();
+                    }
+                }
+            })?;
+        }
+        armed.unsaved = false;
+        armed.saved_at = Instant::now();
+        Ok(())
+    }
+}
+
+fn symbols(triggers: &[Trigger]) -> BTreeSet<String> {
+    triggers.iter().map(|t| t.symbol.to_owned()).collect()
+}
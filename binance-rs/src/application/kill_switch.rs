@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    application::order_template::{floor_to, format_decimal},
+    domain::binance::{
+        BinanceCanceledOrder, BinanceOrderSide, BinanceOrderType, BinanceSpotOrder,
+        BinanceSpotOrderRequest, BinanceSymbolInfo, F64String,
+    },
+    infrastructure::binance_repo::BinanceRepo,
+};
+
+/// What `panic` is about to do, shown before asking for confirmation
+#[derive(Debug)]
+pub struct PanicPlan {
+    /// Open orders by symbol
+    pub open_orders: BTreeMap<String, Vec<BinanceSpotOrder>>,
+    /// Total balance of each asset to sell
+    pub balances: BTreeMap<String, f64>,
+}
+
+/// One action taken by `panic`
+#[derive(Debug, Serialize)]
+pub struct PanicAction {
+    pub action: &'static str,
+    pub symbol: String,
+    pub detail: String,
+    /// done, failed, skipped or planned
+    pub status: &'static str,
+    pub error: Option<String>,
+}
+
+/// Cancel every open order then optionally market-sell the balances to one asset.
+/// Every step is attempted even when the previous ones failed.
+/// The orders are sent directly, bypassing the risk limits, so the switch still
+/// works once a limit such as the daily loss is reached.
+///
+/// ```no_run
+/// use binance_rs::{
+///     application::kill_switch::KillSwitch, infrastructure::binance_repo::BinanceRepo,
+/// };
+/// # fn example(binance: &BinanceRepo) -> anyhow::Result<()> {
+/// let switch = KillSwitch::new(binance, Some("USDT".to_owned()), vec!["BNB".to_owned()]);
+/// let plan = switch.plan()?;
+/// let actions = switch.execute(&plan);
+/// # Ok(())
+/// # }
+/// ```
+pub struct KillSwitch<'a> {
+    binance: &'a BinanceRepo,
+    sell_to: Option<String>,
+    keep: Vec<String>,
+}
+
+impl<'a> KillSwitch<'a> {
+    /// Without `sell_to` only the open orders are cancelled. The `keep` assets are never sold.
+    pub fn new(binance: &'a BinanceRepo, sell_to: Option<String>, keep: Vec<String>) -> Self {
+        Self {
+            binance,
+            sell_to: sell_to.map(|asset| asset.to_uppercase()),
+            keep: keep.iter().map(|asset| asset.to_uppercase()).collect(),
+        }
+    }
+
+    pub fn plan(&self) -> Result<PanicPlan> {
+        let mut open_orders: BTreeMap<String, Vec<BinanceSpotOrder>> = BTreeMap::new();
+        for order in self.binance.get_open_orders()? {
+            let symbol = order.symbol.clone().unwrap_or_default();
+            open_orders.entry(symbol).or_default().push(order);
+        }
+        let mut balances = BTreeMap::new();
+        if self.sell_to.is_some() {
+            for balance in self.binance.get_account()?.balances {
+                let total = balance.free.value() + balance.locked.value();
+                if total > 0.0 && self.sells(&balance.asset) {
+                    balances.insert(balance.asset, total);
+                }
+            }
+        }
+        Ok(PanicPlan {
+            open_orders,
+            balances,
+        })
+    }
+
+    /// The actions of the plan without taking them
+    pub fn dry_run(&self, plan: &PanicPlan) -> Vec<PanicAction> {
+        let mut actions = vec![];
+        for (symbol, orders) in plan.open_orders.iter() {
+            for order in orders {
+                actions.push(PanicAction {
+                    action: "cancel",
+                    symbol: symbol.to_owned(),
+                    detail: describe_order(order),
+                    status: "planned",
+                    error: None,
+                });
+            }
+        }
+        if let Some(sell_to) = &self.sell_to {
+            for (asset, total) in plan.balances.iter() {
+                actions.push(PanicAction {
+                    action: "sell",
+                    symbol: format!("{asset}{sell_to}"),
+                    detail: format!("{total} {asset}"),
+                    status: "planned",
+                    error: None,
+                });
+            }
+        }
+        actions
+    }
+
+    /// Cancel the open orders of every symbol of the plan, then sell the free balances
+    pub fn execute(&self, plan: &PanicPlan) -> Vec<PanicAction> {
+        let mut actions = vec![];
+        for symbol in plan.open_orders.keys() {
+            match self.binance.cancel_open_orders(symbol) {
+                Ok(canceled) => actions.extend(canceled.iter().map(|canceled| PanicAction {
+                    action: "cancel",
+                    symbol: symbol.to_owned(),
+                    detail: match canceled {
+                        BinanceCanceledOrder::Order(order) => describe_order(order),
+                        BinanceCanceledOrder::OrderList(list) => {
+                            format!(
+                                "{} order list {}",
+                                list.contingency_type, list.order_list_id
+                            )
+                        }
+                    },
+                    status: "done",
+                    error: None,
+                })),
+                Err(err) => {
+                    tracing::error!("cannot cancel the open orders of {}: {:#}", symbol, err);
+                    actions.push(PanicAction {
+                        action: "cancel",
+                        symbol: symbol.to_owned(),
+                        detail: format!("{} open orders", plan.open_orders[symbol].len()),
+                        status: "failed",
+                        error: Some(format!("{err:#}")),
+                    });
+                }
+            }
+        }
+        if self.sell_to.is_some() {
+            actions.extend(self.sell_balances());
+        }
+        actions
+    }
+
+    /// Market-sell the free balances, read again since the cancelled orders released the locked ones.
+    /// Assets without a `{asset}{sell_to}` market are skipped.
+    fn sell_balances(&self) -> Vec<PanicAction> {
+        let sell_to = self.sell_to.clone().unwrap_or_default();
+        let account = match self.binance.get_account() {
+            Ok(account) => account,
+            Err(err) => {
+                return vec![PanicAction {
+                    action: "sell",
+                    symbol: "*".to_owned(),
+                    detail: "read the balances".to_owned(),
+                    status: "failed",
+                    error: Some(format!("{err:#}")),
+                }]
+            }
+        };
+        let markets = match self.binance.get_all_symbol_info() {
+            Ok(symbols) => symbols
+                .into_iter()
+                .map(|info| (info.symbol.clone(), info))
+                .collect::<BTreeMap<_, _>>(),
+            Err(err) => {
+                return vec![PanicAction {
+                    action: "sell",
+                    symbol: "*".to_owned(),
+                    detail: "read the markets".to_owned(),
+                    status: "failed",
+                    error: Some(format!("{err:#}")),
+                }]
+            }
+        };
+        account
+            .balances
+            .iter()
+            .filter(|balance| balance.free.value() > 0.0 && self.sells(&balance.asset))
+            .map(|balance| {
+                let symbol = format!("{}{}", balance.asset, sell_to);
+                let mut action = PanicAction {
+                    action: "sell",
+                    symbol: symbol.to_owned(),
+                    detail: format!("{} {}", balance.free, balance.asset),
+                    status: "failed",
+                    error: None,
+                };
+                let Some(info) = markets.get(&symbol) else {
+                    action.status = "skipped";
+                    action.error = Some(format!("no {symbol} market"));
+                    return action;
+                };
+                match self.sell(info, balance.free.value()) {
+                    Ok(Ok(order)) => {
+                        action.status = "done";
+                        action.detail = format!(
+                            "{} {} for {} {}",
+                            order.executed_qty.unwrap_or_default(),
+                            balance.asset,
+                            order.cummulative_quote_qty.unwrap_or_default(),
+                            sell_to
+                        );
+                    }
+                    Ok(Err(reasons)) => {
+                        action.status = "skipped";
+                        action.error = Some(reasons.join("; "));
+                    }
+                    Err(err) => {
+                        tracing::error!("cannot sell {}: {:#}", balance.asset, err);
+                        action.error = Some(format!("{err:#}"));
+                    }
+                }
+                action
+            })
+            .collect()
+    }
+
+    /// Place a MARKET SELL of the quantity rounded down to the lot size.
+    /// Quantities the symbol filters refuse, such as dust, return the reasons instead.
+    /// Sent without [RiskControl](crate::application::risk::RiskControl) on purpose,
+    /// the limits must not keep `panic` from closing the positions.
+    fn sell(
+        &self,
+        info: &BinanceSymbolInfo,
+        free: f64,
+    ) -> Result<Result<BinanceSpotOrder, Vec<String>>> {
+        let symbol = info.symbol.as_str();
+        let step = info.step_size();
+        let quantity = format_decimal(floor_to(free, step), step).parse::<f64>()?;
+        let mut order = BinanceSpotOrderRequest::new(
+            symbol.to_owned(),
+            BinanceOrderSide::Sell,
+            BinanceOrderType::Market,
+        );
+        order.quantity = Some(F64String::from(quantity));
+        let price = self.binance.get_price(symbol)?.price.value();
+        let errors = info.check_order(&order, price);
+        if !errors.is_empty() {
+            return Ok(Err(errors));
+        }
+        Ok(Ok(self.binance.make_spot_order(order)?))
+    }
+
+    fn sells(&self, asset: &str) -> bool {
+        self.sell_to.as_deref() != Some(asset) && !self.keep.iter().any(|keep| keep == asset)
+    }
+}
+
+fn describe_order(order: &BinanceSpotOrder) -> String {
+    format!(
+        "order {} {} {} {}",
+        order.order_id.unwrap_or_default(),
+        order.side.as_ref().map(|s| s.as_str()).unwrap_or_default(),
+        order
+            .binance_spot_order_type
+            .as_ref()
+            .map(|t| t.as_str())
+            .unwrap_or_default(),
+        order.orig_qty.unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transport::testing::replayed;
+
+    /// The cassette has open orders on BTCUSDT and ETHUSDT, whose cancel fails, and the
+    /// balances BTC, USDT, ETH, BNB dust and XRP, which has only an XRPBTC market
+    fn execute(binance: &BinanceRepo) -> Vec<(String, &'static str, Option<String>)> {
+        let switch = KillSwitch::new(binance, Some("usdt".to_owned()), vec!["eth".to_owned()]);
+        let plan = switch.plan().unwrap();
+        switch
+            .execute(&plan)
+            .into_iter()
+            .map(|action| {
+                (
+                    format!("{} {}", action.action, action.symbol),
+                    action.status,
+                    action.error,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plan_leaves_out_keep_and_sell_to() {
+        let binance = replayed("kill_switch.json");
+        let switch = KillSwitch::new(&binance, Some("USDT".to_owned()), vec!["ETH".to_owned()]);
+        let plan = switch.plan().unwrap();
+        assert_eq!(
+            plan.open_orders.keys().collect::<Vec<_>>(),
+            ["BTCUSDT", "ETHUSDT"]
+        );
+        assert_eq!(
+            plan.balances.keys().collect::<Vec<_>>(),
+            ["BNB", "BTC", "XRP"]
+        );
+        assert_eq!(plan.balances["BTC"], 1.6);
+    }
+
+    #[test]
+    fn execute_goes_on_after_a_failed_cancel() {
+        let binance = replayed("kill_switch.json");
+        let actions = execute(&binance);
+        assert_eq!(actions[0], ("cancel BTCUSDT".to_owned(), "done", None));
+        assert_eq!(actions[1], ("cancel BTCUSDT".to_owned(), "done", None));
+        assert_eq!(actions[2].0, "cancel ETHUSDT");
+        assert_eq!(actions[2].1, "failed");
+        assert!(actions[2]
+            .2
+            .as_ref()
+            .unwrap()
+            .contains("Unknown order sent"));
+        assert_eq!(actions[3], ("sell BTCUSDT".to_owned(), "done", None));
+    }
+
+    #[test]
+    fn execute_sells_neither_keep_nor_sell_to() {
+        let binance = replayed("kill_switch.json");
+        let sold = execute(&binance)
+            .into_iter()
+            .filter(|(action, _, _)| action.starts_with("sell"))
+            .map(|(action, _, _)| action)
+            .collect::<Vec<_>>();
+        assert_eq!(sold, ["sell BTCUSDT", "sell BNBUSDT", "sell XRPUSDT"]);
+    }
+
+    #[test]
+    fn execute_skips_dust_and_assets_without_market() {
+        let binance = replayed("kill_switch.json");
+        let actions = execute(&binance);
+        assert_eq!(actions[4].0, "sell BNBUSDT");
+        assert_eq!(actions[4].1, "skipped");
+        assert!(actions[4]
+            .2
+            .as_ref()
+            .unwrap()
+            .contains("quantity 0 is below the minimum 0.00001"));
+        assert_eq!(
+            actions[5],
+            (
+                "sell XRPUSDT".to_owned(),
+                "skipped",
+                Some("no XRPUSDT market".to_owned())
+            )
+        );
+        assert_eq!(actions.len(), 6);
+    }
+}
@@ -1,2 +1,12 @@
+pub mod alerts;
 pub mod command;
+pub mod dashboard;
+pub mod dry_run;
+pub mod emulator;
+pub mod kill_switch;
+pub mod order_template;
+pub mod output;
+pub mod repl;
+pub mod risk;
+pub mod server;
 
@@ -0,0 +1,527 @@
+use std::{cell::RefCell, collections::HashMap, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use toml::{Table, Value};
+
+use crate::{
+    domain::binance::{BinanceSpotAccount, BinanceSpotOrderRequest, BinanceSymbolInfo},
+    infrastructure::binance_repo::BinanceRepo,
+};
+
+/// An order read from a toml file. String values can be templates resolved at submit time:
+/// - `{{ expression }}` with `+ - * / ( )` over the variables `price` (current average price),
+///   `base_free` and `quote_free` (free balances of the symbol assets)
+/// - `"25%"` for `quantity` or `quoteOrderQty`, a percent of the free balance being spent
+///
+/// ```toml
+/// [[orders]]
+/// symbol = "BTCUSDT"
+/// side = "BUY"
+/// type = "LIMIT"
+/// timeInForce = "GTC"
+/// price = "{{ price * 0.99 }}"
+/// quantity = "10%"
+///
+/// [[orders]]
+/// symbol = "ETHUSDT"
+/// side = "SELL"
+/// type = "MARKET"
+/// quantity = "{{ base_free / 2 }}"
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderTemplate {
+    table: Table,
+}
+
+/// Content of an order file, either a single order or an `[[orders]]` array
+#[derive(Debug)]
+pub struct OrderFile {
+    pub batch: bool,
+    pub orders: Vec<OrderTemplate>,
+}
+
+impl OrderTemplate {
+    pub fn read_from_file(file_path: &Path) -> Result<OrderFile> {
+        let content = fs::read_to_string(file_path).context("cannot read order from file")?;
+        let mut table = toml::from_str::<Table>(&content).context("cannot parse order file")?;
+        match table.remove("orders") {
+            Some(Value::Array(orders)) => {
+                let orders = orders
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, order)| match order {
+                        Value::Table(table) => Ok(OrderTemplate { table }),
+                        _ => Err(anyhow!("orders[{i}] is not a table")),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(OrderFile {
+                    batch: true,
+                    orders,
+                })
+            }
+            Some(_) => bail!("orders must be an array of tables"),
+            None => Ok(OrderFile {
+                batch: false,
+                orders: vec![OrderTemplate { table }],
+            }),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        self.table
+            .get("symbol")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    pub fn side(&self) -> &str {
+        self.table
+            .get("side")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    pub fn order_type(&self) -> &str {
+        self.table
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    fn is_template(&self) -> bool {
+        self.table.values().any(|value| match value {
+            Value::String(s) => is_expression(s) || s.ends_with('%'),
+            _ => false,
+        })
+    }
+}
+
+/// Resolve [OrderTemplate] to [BinanceSpotOrderRequest] with the current prices and balances.
+/// Prices, symbol rules and the account are fetched once and shared by all the orders.
+pub struct OrderTemplateResolver<'a> {
+    binance: &'a BinanceRepo,
+    account: RefCell<Option<BinanceSpotAccount>>,
+    symbols: RefCell<HashMap<String, (BinanceSymbolInfo, f64)>>,
+}
+
+impl<'a> OrderTemplateResolver<'a> {
+    pub fn new(binance: &'a BinanceRepo) -> Self {
+        Self {
+            binance,
+            account: RefCell::new(None),
+            symbols: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn resolve(&self, template: &OrderTemplate) -> Result<BinanceSpotOrderRequest> {
+        let mut table = template.table.clone();
+        if template.is_template() {
+            let (info, price) = self.symbol(template.symbol())?;
+            let vars = HashMap::from([
+                ("price", price),
+                ("base_free", self.free_balance(&info.base_asset)?),
+                ("quote_free", self.free_balance(&info.quote_asset)?),
+            ]);
+            let buy = template.side().eq_ignore_ascii_case("BUY");
+
+            // Resolve the price first so percent quantities of LIMIT orders use it
+            for key in ["price", "stopPrice"] {
+                if let Some(value) = resolve_expression(&table, key, &vars)? {
+                    let value = round_to(value, info.tick_size());
+                    table.insert(
+                        key.to_owned(),
+                        Value::String(format_decimal(value, info.tick_size())),
+                    );
+                }
+            }
+            let order_price = match table.get("price").and_then(Value::as_str) {
+                Some(s) => s
+                    .parse::<f64>()
+                    .with_context(|| format!("invalid price {s}"))?,
+                None => price,
+            };
+
+            for key in ["quantity", "icebergQty"] {
+                let value = match (
+                    resolve_expression(&table, key, &vars)?,
+                    percent(&table, key)?,
+                ) {
+                    (Some(value), _) => value,
+                    (None, Some(percent)) if buy => vars["quote_free"] * percent / order_price,
+                    (None, Some(percent)) => vars["base_free"] * percent,
+                    (None, None) => continue,
+                };
+                let value = floor_to(value, info.step_size());
+                table.insert(
+                    key.to_owned(),
+                    Value::String(format_decimal(value, info.step_size())),
+                );
+            }
+
+            let key = "quoteOrderQty";
+            let value = match (
+                resolve_expression(&table, key, &vars)?,
+                percent(&table, key)?,
+            ) {
+                (Some(value), _) => Some(value),
+                (None, Some(percent)) if buy => Some(vars["quote_free"] * percent),
+                (None, Some(percent)) => Some(vars["base_free"] * percent * order_price),
+                (None, None) => None,
+            };
+            if let Some(value) = value {
+                table.insert(key.to_owned(), Value::String(format_decimal(value, None)));
+            }
+        }
+
+        Value::Table(table)
+            .try_into::<BinanceSpotOrderRequest>()
+            .context("cannot parse order")
+    }
+
+    fn symbol(&self, symbol: &str) -> Result<(BinanceSymbolInfo, f64)> {
+        if let Some(cached) = self.symbols.borrow().get(symbol) {
+            return Ok(cached.clone());
+        }
+        let info = self.binance.get_symbol_info(symbol)?;
+        let price = self.binance.get_price(symbol)?.price.value();
+        self.symbols
+            .borrow_mut()
+            .insert(symbol.to_owned(), (info.clone(), price));
+        Ok((info, price))
+    }
+
+    fn free_balance(&self, asset: &str) -> Result<f64> {
+        let mut account = self.account.borrow_mut();
+        if account.is_none() {
+            *account = Some(self.binance.get_account()?);
+        }
+        Ok(account
+            .as_ref()
+            .unwrap()
+            .balances
+            .iter()
+            .find(|balance| balance.asset == asset)
+            .map(|balance| balance.free.value())
+            .unwrap_or_default())
+    }
+}
+
+fn is_expression(s: &str) -> bool {
+    let s = s.trim();
+    s.starts_with("{{") && s.ends_with("}}")
+}
+
+/// Evaluate the `{{ expression }}` of the key if it is one
+fn resolve_expression(table: &Table, key: &str, vars: &HashMap<&str, f64>) -> Result<Option<f64>> {
+    match table.get(key).and_then(Value::as_str) {
+        Some(s) if is_expression(s) => {
+            let s = s.trim();
+            let expression = &s[2..s.len() - 2];
+            let value = Expression::evaluate(expression, vars)
+                .with_context(|| format!("cannot evaluate {key} = {s}"))?;
+            Ok(Some(value))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The `"25%"` value of the key as a fraction
+fn percent(table: &Table, key: &str) -> Result<Option<f64>> {
+    match table.get(key).and_then(Value::as_str) {
+        Some(s) if s.ends_with('%') => {
+            let percent = s[..s.len() - 1]
+                .trim()
+                .parse::<f64>()
+                .with_context(|| format!("invalid percent {key} = {s}"))?;
+            if !(0.0..=100.0).contains(&percent) {
+                bail!("{key} = {s} must be between 0% and 100%");
+            }
+            Ok(Some(percent / 100.0))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn floor_to(value: f64, step: Option<f64>) -> f64 {
+    match step {
+        Some(step) if step > 0.0 => (value / step + 1e-9).floor() * step,
+        _ => value,
+    }
+}
+
+fn round_to(value: f64, step: Option<f64>) -> f64 {
+    match step {
+        Some(step) if step > 0.0 => (value / step).round() * step,
+        _ => value,
+    }
+}
+
+/// Format with the number of decimals of the step, 8 decimals without a step
+pub fn format_decimal(value: f64, step: Option<f64>) -> String {
+    let decimals = match step {
+        Some(step) if step > 0.0 => (-step.log10()).round().max(0.0) as usize,
+        _ => 8,
+    };
+    let formatted = format!("{value:.decimals$}");
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned()
+    } else {
+        formatted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Variable(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+/// Arithmetic expression with `+ - * /`, parentheses, numbers and variables
+struct Expression<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    vars: &'a HashMap<&'a str, f64>,
+}
+
+impl<'a> Expression<'a> {
+    fn evaluate(source: &str, vars: &'a HashMap<&'a str, f64>) -> Result<f64> {
+        let mut expression = Self {
+            tokens: Self::tokenize(source)?,
+            position: 0,
+            vars,
+        };
+        let value = expression.sum()?;
+        if let Some(token) = expression.peek() {
+            bail!("unexpected {token:?}");
+        }
+        if !value.is_finite() {
+            bail!("result is not a finite number");
+        }
+        Ok(value)
+    }
+
+    fn tokenize(source: &str) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        let chars = source.chars().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                ' ' | '\t' => i += 1,
+                '+' | '-' | '*' | '/' => {
+                    tokens.push(Token::Operator(c));
+                    i += 1;
+                }
+                '(' => {
+                    tokens.push(Token::Open);
+                    i += 1;
+                }
+                ')' => {
+                    tokens.push(Token::Close);
+                    i += 1;
+                }
+                '0'..='9' | '.' => {
+                    let start = i;
+                    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                        i += 1;
+                    }
+                    let number = chars[start..i].iter().collect::<String>();
+                    let number = number
+                        .parse::<f64>()
+                        .map_err(|_| anyhow!("invalid number {number}"))?;
+                    tokens.push(Token::Number(number));
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let start = i;
+                    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    tokens.push(Token::Variable(chars[start..i].iter().collect()));
+                }
+                _ => bail!("unexpected character {c}"),
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn sum(&mut self) -> Result<f64> {
+        let mut value = self.product()?;
+        while let Some(Token::Operator(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+            let rhs = self.product()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<f64> {
+        let mut value = self.factor()?;
+        while let Some(Token::Operator(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.position += 1;
+            let rhs = self.factor()?;
+            value = if op == '*' { value * rhs } else { value / rhs };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<f64> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Variable(name)) => self
+                .vars
+                .get(name.as_str())
+                .copied()
+                .ok_or_else(|| anyhow!("unknown variable {name}")),
+            Some(Token::Operator('-')) => Ok(-self.factor()?),
+            Some(Token::Open) => {
+                let value = self.sum()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(value),
+                    _ => bail!("missing )"),
+                }
+            }
+            Some(token) => bail!("unexpected {token:?}"),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str) -> Result<f64> {
+        let vars = HashMap::from([
+            ("price", 20000.0),
+            ("base_free", 0.5),
+            ("quote_free", 1000.0),
+        ]);
+        Expression::evaluate(source, &vars)
+    }
+
+    #[test]
+    fn expression_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("10 - 4 - 3").unwrap(), 3.0);
+        assert_eq!(evaluate("8 / 4 / 2").unwrap(), 1.0);
+        assert_eq!(evaluate("price * 0.99 - 100").unwrap(), 19700.0);
+        assert_eq!(evaluate("-base_free * 2").unwrap(), -1.0);
+    }
+
+    #[test]
+    fn expression_parentheses() {
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("quote_free / (price / 2)").unwrap(), 0.1);
+        assert_eq!(evaluate("((base_free))").unwrap(), 0.5);
+        assert_eq!(evaluate("-(1 - 3)").unwrap(), 2.0);
+    }
+
+    #[test]
+    fn expression_division_by_zero() {
+        let err = evaluate("quote_free / 0").unwrap_err();
+        assert_eq!(err.to_string(), "result is not a finite number");
+        assert!(evaluate("0 / (price - price)").is_err());
+    }
+
+    #[test]
+    fn expression_malformed() {
+        for (source, error) in [
+            ("", "unexpected end of expression"),
+            ("1 +", "unexpected end of expression"),
+            ("(1 + 2", "missing )"),
+            ("1 + 2)", "unexpected Close"),
+            ("2 3", "unexpected Number(3.0)"),
+            ("* 2", "unexpected Operator('*')"),
+            ("1.2.3", "invalid number 1.2.3"),
+            ("price ^ 2", "unexpected character ^"),
+            ("ask * 2", "unknown variable ask"),
+        ] {
+            assert_eq!(evaluate(source).unwrap_err().to_string(), error, "{source}");
+        }
+    }
+
+    #[test]
+    fn resolve_expression_of_key() {
+        let vars = HashMap::from([("price", 20000.0)]);
+        let table = toml::from_str::<Table>(
+            "price = \"{{ price * 0.99 }}\"\nquantity = \"0.1\"\nstopPrice = \"{{ ask }}\"",
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_expression(&table, "price", &vars).unwrap(),
+            Some(19800.0)
+        );
+        assert_eq!(resolve_expression(&table, "quantity", &vars).unwrap(), None);
+        assert_eq!(resolve_expression(&table, "missing", &vars).unwrap(), None);
+        let err = resolve_expression(&table, "stopPrice", &vars).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "cannot evaluate stopPrice = {{ ask }}: unknown variable ask"
+        );
+    }
+
+    #[test]
+    fn percent_of_key() {
+        let table = toml::from_str::<Table>(
+            "quantity = \"25%\"\nquoteOrderQty = \" 100 %\"\nprice = \"0.1\"\nicebergQty = \"150%\"\nstopPrice = \"abc%\"",
+        )
+        .unwrap();
+        assert_eq!(percent(&table, "quantity").unwrap(), Some(0.25));
+        assert_eq!(percent(&table, "quoteOrderQty").unwrap(), Some(1.0));
+        assert_eq!(percent(&table, "price").unwrap(), None);
+        assert!(percent(&table, "icebergQty").is_err());
+        assert!(percent(&table, "stopPrice").is_err());
+    }
+
+    #[test]
+    fn floor_to_step_size() {
+        assert_eq!(floor_to(0.123456, Some(0.001)), 0.123);
+        assert_eq!(format_decimal(floor_to(0.3, Some(0.1)), Some(0.1)), "0.3");
+        assert_eq!(floor_to(0.0999, Some(0.1)), 0.0);
+        assert_eq!(floor_to(12.7, Some(1.0)), 12.0);
+        assert_eq!(floor_to(0.123456, None), 0.123456);
+        assert_eq!(floor_to(0.123456, Some(0.0)), 0.123456);
+    }
+
+    #[test]
+    fn round_to_tick_size() {
+        assert_eq!(
+            format_decimal(round_to(19999.996, Some(0.01)), Some(0.01)),
+            "20000"
+        );
+        assert_eq!(
+            format_decimal(round_to(19800.004, Some(0.01)), Some(0.01)),
+            "19800"
+        );
+        assert_eq!(
+            format_decimal(round_to(0.066666, Some(0.00001)), Some(0.00001)),
+            "0.06667"
+        );
+        assert_eq!(round_to(19999.996, None), 19999.996);
+    }
+
+    #[test]
+    fn format_decimal_trims_zeros() {
+        assert_eq!(format_decimal(0.1 + 0.2, Some(0.01)), "0.3");
+        assert_eq!(format_decimal(1.5, Some(1.0)), "2");
+        assert_eq!(format_decimal(100.0, Some(0.001)), "100");
+        assert_eq!(format_decimal(0.000000016, None), "0.00000002");
+        assert_eq!(format_decimal(1e-9, None), "0");
+    }
+}
@@ -0,0 +1,219 @@
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for humans
+    #[default]
+    Table,
+    /// A JSON array of records, or a single JSON object
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// Print the serde types of the commands in the selected [OutputFormat].
+/// Nested objects are flattened to `parent.child` columns for table and csv.
+///
+/// ```no_run
+/// use binance_rs::{
+///     application::output::{Output, OutputFormat},
+///     domain::binance::BinanceSpotAccount,
+/// };
+/// # fn example(account: &BinanceSpotAccount) -> anyhow::Result<()> {
+/// let output = Output::new(OutputFormat::Json);
+/// output.records(&account.balances)?;
+/// // [{"asset": "BTC", "free": 0.1, "locked": 0.0}, ...]
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Print a list of records, one row per record
+    pub fn records<T: Serialize>(&self, records: &[T]) -> Result<()> {
+        self.records_with_columns(records, &[])
+    }
+
+    /// Print a list of records. The table only shows the given columns so wide records
+    /// stay readable, the other formats always contain every field.
+    pub fn records_with_columns<T: Serialize>(
+        &self,
+        records: &[T],
+        table_columns: &[&str],
+    ) -> Result<()> {
+        let values = records
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<Value>, _>>()
+            .context("cannot serialize output")?;
+        let mut stdout = io::stdout().lock();
+        match self.format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut stdout, &values)?;
+                writeln!(stdout)?;
+            }
+            OutputFormat::Ndjson => {
+                for value in values.iter() {
+                    serde_json::to_writer(&mut stdout, value)?;
+                    writeln!(stdout)?;
+                }
+            }
+            OutputFormat::Csv => {
+                let (columns, rows) = Self::to_rows(&values, &[]);
+                let mut writer = csv::Writer::from_writer(stdout);
+                writer.write_record(&columns)?;
+                for row in rows {
+                    writer.write_record(&row)?;
+                }
+                writer.flush()?;
+            }
+            OutputFormat::Table => {
+                let (columns, rows) = Self::to_rows(&values, table_columns);
+                Self::write_table(&mut stdout, &columns, &rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a single record. The table shows one `field value` line per field.
+    pub fn record<T: Serialize>(&self, record: &T) -> Result<()> {
+        let value = serde_json::to_value(record).context("cannot serialize output")?;
+        let mut stdout = io::stdout().lock();
+        match self.format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut stdout, &value)?;
+                writeln!(stdout)?;
+            }
+            OutputFormat::Ndjson | OutputFormat::Csv => {
+                drop(stdout);
+                self.records(&[value])?;
+            }
+            OutputFormat::Table => {
+                let mut fields = vec![];
+                Self::flatten("", &value, &mut fields);
+                let rows = fields
+                    .into_iter()
+                    .map(|(key, value)| vec![key, value])
+                    .collect::<Vec<_>>();
+                Self::write_table(
+                    &mut stdout,
+                    &["field".to_owned(), "value".to_owned()],
+                    &rows,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Blank line between two tables. The other formats print nothing, they would no
+    /// longer parse.
+    pub fn separator(&self) -> Result<()> {
+        if self.format == OutputFormat::Table {
+            writeln!(io::stdout())?;
+        }
+        Ok(())
+    }
+
+    /// Flatten the records to rows of cells. Columns are in order of first appearance,
+    /// or the `only` columns when given.
+    fn to_rows(values: &[Value], only: &[&str]) -> (Vec<String>, Vec<Vec<String>>) {
+        let flat = values
+            .iter()
+            .map(|value| {
+                let mut fields = vec![];
+                Self::flatten("", value, &mut fields);
+                fields
+            })
+            .collect::<Vec<_>>();
+        let mut columns: Vec<String> = only.iter().map(|c| c.to_string()).collect();
+        if columns.is_empty() {
+            for (key, _) in flat.iter().flatten() {
+                if !columns.contains(key) {
+                    columns.push(key.to_owned());
+                }
+            }
+        }
+        let rows = flat
+            .iter()
+            .map(|fields| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        fields
+                            .iter()
+                            .find(|(key, _)| key == column)
+                            .map(|(_, value)| value.to_owned())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        (columns, rows)
+    }
+
+    fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key = match prefix {
+                        "" => key.to_owned(),
+                        _ => format!("{prefix}.{key}"),
+                    };
+                    Self::flatten(&key, value, out);
+                }
+            }
+            Value::Null => out.push((prefix.to_owned(), "".to_owned())),
+            Value::String(s) => out.push((prefix.to_owned(), s.to_owned())),
+            _ => out.push((prefix.to_owned(), value.to_string())),
+        }
+    }
+
+    fn write_table(out: &mut impl Write, columns: &[String], rows: &[Vec<String>]) -> Result<()> {
+        let widths = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.chars().count()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        };
+        writeln!(out, "{}", line(columns))?;
+        let separator = widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>();
+        writeln!(out, "{}", separator.join("  "))?;
+        for row in rows {
+            writeln!(out, "{}", line(row))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use chrono::Utc;
+use clap::{CommandFactory, Parser, Subcommand};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+
+use crate::{
+    application::command::{Command, CommandHandler},
+    infrastructure::binance_stream::{BinanceStream, BinanceStreamEvent},
+};
+
+/// Set while `watch` runs, Ctrl-C then stops the watch instead of the shell
+static WATCHING: AtomicBool = AtomicBool::new(false);
+static STOP_WATCH: AtomicBool = AtomicBool::new(false);
+
+/// A line of the interactive shell: any [Command] plus the shell helpers
+#[derive(Parser, Debug)]
+#[clap(name = "", no_binary_name = true, disable_version_flag = true)]
+struct ReplArgs {
+    /// Validate orders locally and with Binance's test order endpoint without placing them
+    #[clap(long, global = true)]
+    dry_run: bool,
+
+    #[clap(subcommand)]
+    command: ReplCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ReplCommand {
+    #[clap(flatten)]
+    Command(Command),
+    /// Print the last trade of the symbol every interval until Ctrl-C
+    Watch {
+        symbol: Option<String>,
+
+        /// Seconds between two lines
+        #[clap(long, default_value_t = 1.0)]
+        interval: f64,
+    },
+    /// Show the last trade price of the symbols
+    Last { symbols: Vec<String> },
+    /// Leave the shell
+    #[clap(alias = "quit")]
+    Exit,
+}
+
+/// Completion of the commands, their flags and the symbols
+struct ReplHelper {
+    commands: clap::Command,
+    symbols: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let start = before.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let word = &before[start..];
+
+        // Follow the subcommands already typed, e.g. `bot arm`
+        let mut command = &self.commands;
+        for typed in before[..start].split_whitespace() {
+            if let Some(sub) = command.find_subcommand(typed) {
+                command = sub;
+            }
+        }
+
+        let candidates = if word.starts_with('-') {
+            command
+                .get_arguments()
+                .chain(self.commands.get_arguments())
+                .filter_map(|arg| arg.get_long())
+                .map(|long| format!("--{long}"))
+                .filter(|flag| flag.starts_with(word))
+                .collect()
+        } else if command.has_subcommands() {
+            command
+                .get_subcommands()
+                .map(|sub| sub.get_name().to_owned())
+                .filter(|name| name.starts_with(word))
+                .collect()
+        } else {
+            let upper = word.to_uppercase();
+            self.symbols
+                .iter()
+                .filter(|symbol| symbol.starts_with(&upper))
+                .cloned()
+                .collect()
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Interactive shell running the commands with the session of one [CommandHandler],
+/// so the config and the HTTP client are set up once.
+///
+/// ```no_run
+/// use binance_rs::application::{command::CommandHandler, repl::Repl};
+/// # fn example(handler: &CommandHandler) -> anyhow::Result<()> {
+/// Repl::new(handler)?.run()?;
+/// // binance:default> get-price btcusdt
+/// // binance:default> watch BTCUSDT --interval 5
+/// # Ok(())
+/// # }
+/// ```
+pub struct Repl<'a> {
+    handler: &'a CommandHandler,
+    editor: Editor<ReplHelper, DefaultHistory>,
+    history: PathBuf,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(handler: &'a CommandHandler) -> Result<Self> {
+        let symbols = match handler.binance().get_all_symbol_info() {
+            Ok(symbols) => symbols.into_iter().map(|info| info.symbol).collect(),
+            Err(err) => {
+                tracing::warn!("cannot read the symbols to complete: {:#}", err);
+                vec![]
+            }
+        };
+        let mut editor = Editor::new().context("cannot create the line editor")?;
+        editor.set_helper(Some(ReplHelper {
+            commands: ReplArgs::command(),
+            symbols,
+        }));
+        let history = history_path();
+        if let Err(err) = editor.load_history(&history) {
+            tracing::debug!("no history loaded from {}: {}", history.display(), err);
+        }
+        Ok(Self {
+            handler,
+            editor,
+            history,
+        })
+    }
+
+    /// Read and run lines until `exit` or Ctrl-D. A failing command only prints its error.
+    pub fn run(&mut self) -> Result<()> {
+        ctrlc::set_handler(|| {
+            if WATCHING.load(Ordering::SeqCst) {
+                STOP_WATCH.store(true, Ordering::SeqCst);
+            } else {
+                std::process::exit(130);
+            }
+        })
+        .context("cannot handle Ctrl-C")?;
+
+        let prompt = format!("binance:{}> ", self.handler.profile().name);
+        loop {
+            let line = match self.editor.readline(&prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err).context("cannot read the line"),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Err(err) = self.editor.add_history_entry(line) {
+                tracing::debug!("cannot add to history: {}", err);
+            }
+            match self.handle_line(line) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(err) => eprintln!("Error: {err:#}"),
+            }
+        }
+        if let Some(dir) = self.history.parent() {
+            std::fs::create_dir_all(dir).context("cannot create history directory")?;
+        }
+        self.editor
+            .save_history(&self.history)
+            .with_context(|| format!("cannot save history {}", self.history.display()))
+    }
+
+    /// Run the line, return whether the shell should stop
+    fn handle_line(&self, line: &str) -> Result<bool> {
+        let words = shlex::split(line).ok_or_else(|| anyhow!("unbalanced quotes"))?;
+        let args = match ReplArgs::try_parse_from(words) {
+            Ok(args) => args,
+            Err(err) => {
+                // Help and usage errors are printed the same way as on the command line
+                err.print()?;
+                return Ok(false);
+            }
+        };
+        match args.command {
+            ReplCommand::Command(Command::Interactive) => bail!("already in the interactive shell"),
+            ReplCommand::Command(command) => self.handler.handle_command(command, args.dry_run)?,
+            ReplCommand::Watch { symbol, interval } => self.watch(symbol, interval)?,
+            ReplCommand::Last { symbols } => self.last(symbols)?,
+            ReplCommand::Exit => return Ok(true),
+        }
+        Ok(false)
+    }
+
+    fn watch(&self, symbol: Option<String>, interval: f64) -> Result<()> {
+        let symbol = self.symbol_or_default(symbol)?;
+        let interval = Duration::try_from_secs_f64(interval).context("invalid interval")?;
+        let endpoint = &self.handler.profile().binance.stream_endpoint;
+        let mut stream = BinanceStream::subscribe(endpoint, &[BinanceStream::agg_trade(&symbol)])?;
+        stream.set_read_timeout(Duration::from_millis(200))?;
+        eprintln!("watching {symbol}, Ctrl-C to stop");
+
+        STOP_WATCH.store(false, Ordering::SeqCst);
+        WATCHING.store(true, Ordering::SeqCst);
+        let mut last = None;
+        let mut printed_at = Instant::now() - interval;
+        let result = loop {
+            if STOP_WATCH.load(Ordering::SeqCst) {
+                break Ok(());
+            }
+            match stream.next_event() {
+                Ok(Some(BinanceStreamEvent::AggTrade(trade))) => last = Some(trade),
+                Ok(_) => {}
+                Err(err) => break Err(err),
+            }
+            if printed_at.elapsed() < interval {
+                continue;
+            }
+            if let Some(trade) = last.take() {
+                println!(
+                    "{}  {}  {}  {}",
+                    Utc::now().format("%H:%M:%S"),
+                    trade.symbol,
+                    trade.price,
+                    trade.quantity
+                );
+                printed_at = Instant::now();
+            }
+        };
+        WATCHING.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn last(&self, symbols: Vec<String>) -> Result<()> {
+        let symbols = match symbols.is_empty() {
+            true => vec![self.symbol_or_default(None)?],
+            false => symbols.iter().map(|s| s.to_uppercase()).collect(),
+        };
+        let prices = self
+            .handler
+            .binance()
+            .get_prices()?
+            .into_iter()
+            .filter(|price| symbols.contains(&price.symbol))
+            .collect::<Vec<_>>();
+        if prices.is_empty() {
+            bail!("no price for {}", symbols.join(", "));
+        }
+        self.handler.output().records(&prices)
+    }
+
+    fn symbol_or_default(&self, symbol: Option<String>) -> Result<String> {
+        symbol
+            .or_else(|| self.handler.profile().defaults.symbol.clone())
+            .map(|symbol| symbol.to_uppercase())
+            .ok_or_else(|| anyhow!("symbol is required, the profile has no default symbol"))
+    }
+}
+
+/// `<config dir>/binance-rs/history.txt`
+fn history_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("binance-rs")
+        .join("history.txt")
+}
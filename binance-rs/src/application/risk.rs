@@ -0,0 +1,552 @@
+use std::{collections::HashMap, slice};
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+
+use crate::{
+    domain::{
+        binance::{
+            convert_price, BinanceOrderList, BinanceOrderListRequest, BinanceSpotAccount,
+            BinanceSpotOrder, BinanceSpotOrderRequest,
+        },
+        futures::{BinanceFuturesOrder, BinanceFuturesOrderRequest},
+        margin::{BinanceMarginOrder, BinanceMarginOrderRequest},
+        risk::{RiskFacts, RiskLimits, RiskRejection},
+    },
+    infrastructure::{binance_repo::BinanceRepo, risk_store::RiskStore},
+};
+
+/// Check the [RiskLimits] of the profile before sending orders.
+/// Rejected orders fail with a [RiskRejection] listing every broken limit,
+/// and each decision is logged.
+///
+/// ```no_run
+/// use std::path::PathBuf;
+///
+/// use binance_rs::{
+///     app_config::Profile,
+///     application::risk::RiskControl,
+///     domain::binance::BinanceSpotOrderRequest,
+///     infrastructure::{binance_repo::BinanceRepo, risk_store::RiskStore},
+/// };
+/// # fn example(
+/// #     binance: &BinanceRepo,
+/// #     profile: &Profile,
+/// #     path: PathBuf,
+/// #     order: BinanceSpotOrderRequest,
+/// # ) -> anyhow::Result<()> {
+/// let risk = RiskControl::new(binance, &profile.risk, RiskStore::new(path));
+/// let order = risk.make_spot_order(order)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RiskControl<'a> {
+    binance: &'a BinanceRepo,
+    limits: &'a RiskLimits,
+    store: RiskStore,
+}
+
+impl<'a> RiskControl<'a> {
+    pub fn new(binance: &'a BinanceRepo, limits: &'a RiskLimits, store: RiskStore) -> Self {
+        Self {
+            binance,
+            limits,
+            store,
+        }
+    }
+
+    /// Check then place the order
+    pub fn make_spot_order(&self, order: BinanceSpotOrderRequest) -> Result<BinanceSpotOrder> {
+        self.approve(slice::from_ref(&order))?;
+        self.binance.make_spot_order(order)
+    }
+
+    /// Check then place the order on the margin account
+    pub fn make_margin_order(
+        &self,
+        order: BinanceMarginOrderRequest,
+    ) -> Result<BinanceMarginOrder> {
+        self.approve(slice::from_ref(&order.order))?;
+        self.binance.make_margin_order(order)
+    }
+
+    /// Check then place the USDⓈ-M futures order, see [RiskControl::approve_futures]
+    pub fn make_futures_order(
+        &self,
+        order: BinanceFuturesOrderRequest,
+    ) -> Result<BinanceFuturesOrder> {
+        self.approve_futures(&order)?;
+        self.binance.futures().make_order(order)
+    }
+
+    /// Check every order of the list then place it
+    pub fn make_order_list(&self, order_list: BinanceOrderListRequest) -> Result<BinanceOrderList> {
+        let legs = order_list
+            .legs()
+            .into_iter()
+            .map(|(_, order)| order)
+            .collect::<Vec<_>>();
+        self.approve(&legs)?;
+        self.binance.make_order_list(order_list)
+    }
+
+    /// Fail with a [RiskRejection] unless the orders may be sent, otherwise record them
+    /// for `max_orders_per_minute`, with the account value for `daily_loss_limit` when
+    /// they are the first orders of the UTC day. Rejected orders record nothing.
+    pub fn approve(&self, orders: &[BinanceSpotOrderRequest]) -> Result<()> {
+        if self.limits.is_empty() {
+            return Ok(());
+        }
+        let (reasons, account_value) = self.evaluate(orders)?;
+        let symbol = orders
+            .first()
+            .map(|order| order.symbol.to_owned())
+            .unwrap_or_default();
+        let orders = orders
+            .iter()
+            .map(|order| format!("{} {} {}", order.symbol, order.side, order.order_type))
+            .collect::<Vec<_>>();
+        self.settle(symbol, &orders, reasons, account_value)
+    }
+
+    /// Fail with a [RiskRejection] unless the futures order may be sent, otherwise record
+    /// it for `max_orders_per_minute` with the spot orders
+    pub fn approve_futures(&self, order: &BinanceFuturesOrderRequest) -> Result<()> {
+        if self.limits.is_empty() {
+            return Ok(());
+        }
+        let reasons = self.check_futures(order)?;
+        let description = format!(
+            "{} {} {} futures",
+            order.symbol,
+            order.side,
+            order.order_type.as_str()
+        );
+        self.settle(order.symbol.to_owned(), &[description], reasons, None)
+    }
+
+    /// Reasons the futures order breaks the limits, only reading the risk state.
+    /// The notional and the price deviation are computed at the mark price. The spot
+    /// balances say nothing of the futures positions, so `max_position` and
+    /// `daily_loss_limit` are left out.
+    pub fn check_futures(&self, order: &BinanceFuturesOrderRequest) -> Result<Vec<String>> {
+        if self.limits.is_empty() {
+            return Ok(vec![]);
+        }
+        let limits = self.limits;
+        let state = self.store.load()?;
+        let since = Utc::now() - Duration::minutes(1);
+        let mut facts = RiskFacts {
+            symbol: order.symbol.to_owned(),
+            side: Some(order.side.clone()),
+            order_price: order.price.or(order.stop_price).map(|p| p.value()),
+            recent_orders: state.orders.iter().filter(|at| **at > since).count(),
+            ..Default::default()
+        };
+        if limits.max_notional.is_some() || limits.max_price_deviation_bps.is_some() {
+            let positions = self.binance.futures().get_positions(Some(&order.symbol))?;
+            if let Some(position) = positions.first() {
+                facts.mid_price = Some(f64::try_from(position.mark_price)?);
+            }
+        }
+        // Orders closing the whole position have no quantity, hence no notional
+        if let Some(price) = facts.order_price.or(facts.mid_price) {
+            facts.quantity = order.quantity.map(|q| q.value());
+            facts.notional = facts.quantity.map(|quantity| quantity * price);
+        }
+        Ok(limits.check(&facts))
+    }
+
+    /// Record the accepted orders and the account value at the first of the day,
+    /// or fail with the reasons
+    fn settle(
+        &self,
+        symbol: String,
+        orders: &[String],
+        reasons: Vec<String>,
+        account_value: Option<f64>,
+    ) -> Result<()> {
+        if !reasons.is_empty() {
+            let rejection = RiskRejection { symbol, reasons };
+            tracing::warn!("{}", rejection);
+            return Err(rejection.into());
+        }
+        let now = Utc::now();
+        self.store.update(|state| {
+            let today = now.date_naive();
+            if let (Some(value), true) = (
+                account_value,
+                state.day != Some(today) || state.start_value.is_none(),
+            ) {
+                // The value itself stays out of the logs like the balances
+                tracing::info!(day = %today, "recorded the account value at the start of the day");
+                state.day = Some(today);
+                state.start_value = Some(value);
+            }
+            state.orders.retain(|at| *at > now - Duration::minutes(1));
+            state.orders.extend(orders.iter().map(|_| now));
+        })?;
+        for order in orders {
+            tracing::info!("{} order accepted by the risk limits", order);
+        }
+        Ok(())
+    }
+
+    /// Reasons the orders break the limits. Only reads the risk state, so a dry run
+    /// records neither the orders nor the account value of the day.
+    /// The orders count as sent together for `max_orders_per_minute`.
+    pub fn check(&self, orders: &[BinanceSpotOrderRequest]) -> Result<Vec<String>> {
+        if self.limits.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(self.evaluate(orders)?.0)
+    }
+
+    /// The reasons the orders break the limits and the account value when
+    /// `daily_loss_limit` needs it
+    fn evaluate(&self, orders: &[BinanceSpotOrderRequest]) -> Result<(Vec<String>, Option<f64>)> {
+        let limits = self.limits;
+        let state = self.store.load()?;
+        let since = Utc::now() - Duration::minutes(1);
+        let recent_orders = state.orders.iter().filter(|at| **at > since).count();
+
+        let needs_account = !limits.max_position.is_empty() || limits.daily_loss_limit.is_some();
+        let account = match needs_account {
+            true => Some(self.binance.get_account()?),
+            false => None,
+        };
+        let account_value = match (&account, limits.daily_loss_limit) {
+            (Some(account), Some(_)) => Some(self.account_value(account)?),
+            _ => None,
+        };
+        // Fall of the account value since the first accepted order of the UTC day, none before it
+        let today = Utc::now().date_naive();
+        let daily_loss = account_value.map(|value| match (state.day, state.start_value) {
+            (Some(day), Some(start)) if day == today => start - value,
+            _ => 0.0,
+        });
+
+        let mut reasons: Vec<String> = vec![];
+        for (index, order) in orders.iter().enumerate() {
+            let mut facts = RiskFacts {
+                symbol: order.symbol.to_owned(),
+                side: Some(order.side.clone()),
+                order_price: order.price.or(order.stop_price).map(|p| p.value()),
+                recent_orders: recent_orders + index,
+                daily_loss,
+                ..Default::default()
+            };
+            let needs_price = limits.max_notional.is_some()
+                || !limits.max_position.is_empty()
+                || limits.max_price_deviation_bps.is_some();
+            if needs_price {
+                facts.mid_price = Some(self.binance.get_book_ticker(&order.symbol)?.mid());
+            }
+            if let Some(price) = facts.order_price.or(facts.mid_price) {
+                let quote = order.quote_order_qty.map(|q| q.value());
+                facts.quantity = order
+                    .quantity
+                    .map(|q| q.value())
+                    .or_else(|| quote.map(|quote| quote / price));
+                facts.notional = facts.quantity.map(|quantity| quantity * price).or(quote);
+            }
+            if let Some(account) = &account {
+                if !limits.max_position.is_empty() {
+                    let base_asset = self.binance.get_symbol_info(&order.symbol)?.base_asset;
+                    facts.position = account
+                        .balances
+                        .iter()
+                        .find(|balance| balance.asset == base_asset)
+                        .map(|balance| balance.free.value() + balance.locked.value());
+                    facts.base_asset = Some(base_asset);
+                }
+            }
+            for reason in limits.check(&facts) {
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+        }
+        Ok((reasons, account_value))
+    }
+
+    /// Value of every balance in the valuation asset at the last prices.
+    /// Assets without a market with the valuation asset are left out.
+    fn account_value(&self, account: &BinanceSpotAccount) -> Result<f64> {
+        let valuation = self.limits.valuation_asset();
+        let prices = self
+            .binance
+            .get_prices()?
+            .into_iter()
+            .map(|price| (price.symbol, price.price.value()))
+            .collect::<HashMap<_, _>>();
+        let mut value = 0.0;
+        for balance in account.balances.iter() {
+            let total = balance.free.value() + balance.locked.value();
+            if total == 0.0 {
+                continue;
+            }
+            match convert_price(&prices, &balance.asset, valuation) {
+                Some(price) => value += total * price,
+                None => tracing::debug!("no {} price for {}, left out", valuation, balance.asset),
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::{
+        domain::{
+            binance::{BinanceOrderSide, BinanceOrderType, BinanceTimeInForce, F64String},
+            futures::BinanceFuturesOrderType,
+        },
+        infrastructure::transport::testing::replayed,
+    };
+
+    /// BTC 1.6 and USDT 10000 at a BTCUSDT price of 20000, a mid price of 20000
+    const SPOT: &str = "spot_order.json";
+    const FUTURES: &str = "futures_order.json";
+    const ACCOUNT_VALUE: f64 = 42000.0;
+
+    fn store(name: &str) -> RiskStore {
+        let path = std::env::temp_dir().join(format!(
+            "binance-rs-risk-{name}-{}.toml",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        RiskStore::new(path)
+    }
+
+    fn limits(config: &str) -> RiskLimits {
+        toml::from_str(config).unwrap()
+    }
+
+    /// The BUY LIMIT of 0.001 BTCUSDT at 20100 in the spot cassette
+    fn spot_order() -> BinanceSpotOrderRequest {
+        let mut order = BinanceSpotOrderRequest::new(
+            "BTCUSDT".to_owned(),
+            BinanceOrderSide::Buy,
+            BinanceOrderType::Limit,
+        );
+        order.quantity = Some(F64String::from(0.001));
+        order.price = Some(F64String::from(20100.0));
+        order.time_in_force = Some(BinanceTimeInForce::Gtc);
+        order
+    }
+
+    fn futures_order(symbol: &str, quantity: f64) -> BinanceFuturesOrderRequest {
+        let mut order = BinanceFuturesOrderRequest::new(
+            symbol.to_owned(),
+            BinanceOrderSide::Buy,
+            BinanceFuturesOrderType::Market,
+        );
+        order.quantity = Some(quantity.into());
+        order
+    }
+
+    fn seed_orders(store: &RiskStore, ages: &[i64]) {
+        let now = Utc::now();
+        store
+            .update(|state| {
+                state.orders = ages
+                    .iter()
+                    .map(|age| now - Duration::seconds(*age))
+                    .collect();
+            })
+            .unwrap();
+    }
+
+    fn seed_start_value(store: &RiskStore, day: DateTime<Utc>, value: f64) {
+        store
+            .update(|state| {
+                state.day = Some(day.date_naive());
+                state.start_value = Some(value);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn spot_limits_are_checked_at_the_market_prices() {
+        let binance = replayed(SPOT);
+        // Notional of 20.1 USDT
+        for (config, broken) in [
+            ("max_notional = 20.1", None),
+            ("max_notional = 20.0", Some("above max_notional 20")),
+            // 1.6 BTC held
+            ("max_position = { BTC = 1.601 }", None),
+            (
+                "max_position = { BTC = 1.6 }",
+                Some("above max_position 1.6"),
+            ),
+            // 20100 is 50 bps from the mid price
+            ("max_price_deviation_bps = 50.0", None),
+            (
+                "max_price_deviation_bps = 49.0",
+                Some("above max_price_deviation_bps 49"),
+            ),
+            ("allowed_symbols = [\"BTCUSDT\"]", None),
+            (
+                "allowed_symbols = [\"ETHUSDT\"]",
+                Some("BTCUSDT is not an allowed symbol"),
+            ),
+        ] {
+            let limits = limits(config);
+            let risk = RiskControl::new(&binance, &limits, store("spot"));
+            let reasons = risk.check(&[spot_order()]).unwrap();
+            match broken {
+                None => assert!(reasons.is_empty(), "{config}: {reasons:?}"),
+                Some(broken) => {
+                    assert_eq!(reasons.len(), 1, "{config}: {reasons:?}");
+                    assert!(reasons[0].ends_with(broken), "{config}: {}", reasons[0]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejected_orders_are_not_recorded() {
+        let binance = replayed(SPOT);
+        let limits = limits("max_notional = 10.0\ndaily_loss_limit = 100.0");
+        let store = store("rejected");
+        let risk = RiskControl::new(&binance, &limits, store.clone());
+
+        let err = risk.make_spot_order(spot_order()).unwrap_err();
+        assert!(err.downcast_ref::<RiskRejection>().is_some());
+        let state = store.load().unwrap();
+        assert!(state.orders.is_empty());
+        assert_eq!(state.start_value, None);
+    }
+
+    #[test]
+    fn checks_record_nothing() {
+        let binance = replayed(SPOT);
+        let limits = limits("max_orders_per_minute = 5\ndaily_loss_limit = 100.0");
+        let store = store("check");
+        let risk = RiskControl::new(&binance, &limits, store.clone());
+
+        assert!(risk.check(&[spot_order()]).unwrap().is_empty());
+        let state = store.load().unwrap();
+        assert!(state.orders.is_empty());
+        assert_eq!(state.start_value, None);
+    }
+
+    #[test]
+    fn max_orders_per_minute_at_the_window_edge() {
+        let binance = replayed(SPOT);
+        let limits = limits("max_orders_per_minute = 2");
+        let store = store("window");
+        let risk = RiskControl::new(&binance, &limits, store.clone());
+
+        // The order of 61s ago left the window
+        seed_orders(&store, &[61, 30]);
+        risk.approve(&[spot_order()]).unwrap();
+        assert_eq!(store.load().unwrap().orders.len(), 2);
+
+        // The order of 59s ago is still in it
+        seed_orders(&store, &[59, 30]);
+        let err = risk.approve(&[spot_order()]).unwrap_err();
+        assert!(format!("{err:#}").contains("2 orders sent in the last minute"));
+        assert_eq!(store.load().unwrap().orders.len(), 2);
+
+        // Orders sent together count one after the other
+        seed_orders(&store, &[30]);
+        let reasons = risk.check(&[spot_order(), spot_order()]).unwrap();
+        assert_eq!(
+            reasons,
+            vec!["2 orders sent in the last minute, max_orders_per_minute is 2"]
+        );
+    }
+
+    #[test]
+    fn daily_loss_counts_from_the_first_accepted_order_of_the_day() {
+        let binance = replayed(SPOT);
+        let limits = limits("daily_loss_limit = 100.0");
+        let store = store("daily");
+        let risk = RiskControl::new(&binance, &limits, store.clone());
+
+        risk.approve(&[spot_order()]).unwrap();
+        let state = store.load().unwrap();
+        assert_eq!(state.day, Some(Utc::now().date_naive()));
+        assert_eq!(state.start_value, Some(ACCOUNT_VALUE));
+
+        seed_start_value(&store, Utc::now(), ACCOUNT_VALUE + 99.0);
+        risk.approve(&[spot_order()]).unwrap();
+        // The start value stays the one of the first order
+        assert_eq!(
+            store.load().unwrap().start_value,
+            Some(ACCOUNT_VALUE + 99.0)
+        );
+
+        seed_start_value(&store, Utc::now(), ACCOUNT_VALUE + 100.0);
+        assert_eq!(
+            risk.check(&[spot_order()]).unwrap(),
+            vec!["daily loss 100 USDT reached daily_loss_limit 100"]
+        );
+
+        // The value of a previous day is replaced
+        seed_start_value(
+            &store,
+            Utc::now() - Duration::days(1),
+            ACCOUNT_VALUE + 500.0,
+        );
+        assert!(risk.check(&[spot_order()]).unwrap().is_empty());
+        risk.approve(&[spot_order()]).unwrap();
+        assert_eq!(store.load().unwrap().start_value, Some(ACCOUNT_VALUE));
+    }
+
+    #[test]
+    fn futures_orders_are_checked_at_the_mark_price() {
+        let binance = replayed(FUTURES);
+        let limits = limits("max_notional = 1000.0\nallowed_symbols = [\"BTCUSDT\"]");
+        let risk = RiskControl::new(&binance, &limits, store("futures"));
+
+        assert_eq!(
+            risk.check_futures(&futures_order("ETHUSDT", 0.01)).unwrap(),
+            vec!["ETHUSDT is not an allowed symbol"]
+        );
+        let reasons = risk.check_futures(&futures_order("BTCUSDT", 1.0)).unwrap();
+        assert_eq!(reasons.len(), 1);
+        assert!(
+            reasons[0].ends_with("is above max_notional 1000"),
+            "{}",
+            reasons[0]
+        );
+
+        let err = risk
+            .make_futures_order(futures_order("BTCUSDT", 1.0))
+            .unwrap_err();
+        assert!(err.downcast_ref::<RiskRejection>().is_some());
+        let placed = risk
+            .make_futures_order(futures_order("BTCUSDT", 0.01))
+            .unwrap();
+        assert_eq!(placed.symbol, "BTCUSDT");
+    }
+
+    #[test]
+    fn futures_orders_count_for_max_orders_per_minute() {
+        let binance = replayed(FUTURES);
+        let limits = limits("max_orders_per_minute = 1");
+        let store = store("futures-rate");
+        let risk = RiskControl::new(&binance, &limits, store.clone());
+
+        assert!(risk
+            .check_futures(&futures_order("BTCUSDT", 0.01))
+            .unwrap()
+            .is_empty());
+        assert!(store.load().unwrap().orders.is_empty());
+
+        risk.make_futures_order(futures_order("BTCUSDT", 0.01))
+            .unwrap();
+        assert_eq!(store.load().unwrap().orders.len(), 1);
+        let err = risk
+            .make_futures_order(futures_order("BTCUSDT", 0.01))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("max_orders_per_minute is 1"));
+    }
+}
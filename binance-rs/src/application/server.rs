@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use prometheus::TEXT_FORMAT;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    application::command::CommandHandler,
+    domain::{binance::BinanceSpotOrderRequest, risk::RiskRejection, secret::SecretString},
+    infrastructure::{metrics::Metrics, reqwest_facade::ResponseError},
+};
+
+/// Largest order body accepted
+const MAX_BODY: u64 = 64 * 1024;
+
+/// Open orders older than this are read again by a scrape. Reading them for every symbol
+/// costs 80 request weight, too much for each scrape.
+const OPEN_ORDERS_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// Failed request with the status it is answered with
+struct ApiError {
+    status: u16,
+    error: anyhow::Error,
+}
+
+impl ApiError {
+    fn new(status: u16, error: anyhow::Error) -> Self {
+        Self { status, error }
+    }
+
+    fn body(&self) -> Value {
+        match self.error.downcast_ref::<RiskRejection>() {
+            Some(rejection) => json!({
+                "error": format!("{:#}", self.error),
+                "reasons": rejection.reasons,
+            }),
+            None => json!({ "error": format!("{:#}", self.error) }),
+        }
+    }
+}
+
+/// Errors of the handler: risk rejections are the client's, Binance errors are upstream ones
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        let status = if error.downcast_ref::<RiskRejection>().is_some() {
+            422
+        } else if error.downcast_ref::<ResponseError>().is_some()
+            || error.downcast_ref::<reqwest::Error>().is_some()
+        {
+            502
+        } else {
+            500
+        };
+        Self { status, error }
+    }
+}
+
+/// Status and body of a request, JSON unless it is the metrics text
+type ApiResult = Result<(u16, ApiBody), ApiError>;
+
+enum ApiBody {
+    Json(Value),
+    Metrics(String),
+}
+
+/// REST API over the [CommandHandler] of one profile, so other services get the balances
+/// and place orders without holding the API keys. Orders go through the same checks and
+/// risk limits as the command line. Requests are answered one at a time.
+///
+/// Every route but the probes requires `Authorization: Bearer <token>`:
+/// - `GET /healthz`: the process is up
+/// - `GET /readyz`: Binance answers
+/// - `GET /account`: balances and permissions
+/// - `GET /price?symbol=BTCUSDT`: last price, of every symbol without `symbol`
+/// - `GET /orders?symbol=BTCUSDT`: open orders, of every symbol without `symbol`
+/// - `POST /orders`: place the JSON order, or only check it with `--dry-run`
+/// - `GET /metrics`: Prometheus [Metrics], the open orders read again when older than 5 minutes
+///
+/// ```no_run
+/// use binance_rs::{
+///     application::{command::CommandHandler, server::ApiServer},
+///     domain::secret::SecretString,
+/// };
+/// # fn example(handler: &CommandHandler, token: SecretString) -> anyhow::Result<()> {
+/// ApiServer::new(handler, token, false).run("127.0.0.1:8000")?;
+/// // curl -H "Authorization: Bearer $TOKEN" localhost:8000/price?symbol=BTCUSDT
+/// # Ok(())
+/// # }
+/// ```
+pub struct ApiServer<'a> {
+    handler: &'a CommandHandler,
+    token: SecretString,
+    dry_run: bool,
+}
+
+impl<'a> ApiServer<'a> {
+    pub fn new(handler: &'a CommandHandler, token: SecretString, dry_run: bool) -> Self {
+        Self {
+            handler,
+            token,
+            dry_run,
+        }
+    }
+
+    pub fn run(&self, listen: &str) -> Result<()> {
+        let server =
+            Server::http(listen).map_err(|err| anyhow!("cannot listen on {listen}: {err}"))?;
+        tracing::info!(
+            "serving profile {} on http://{}{}",
+            self.handler.profile().name,
+            listen,
+            if self.dry_run { " in dry run" } else { "" }
+        );
+        self.serve(&server);
+        Ok(())
+    }
+
+    /// Answer the requests of the server until it is unblocked
+    fn serve(&self, server: &Server) {
+        let mut next_id: u64 = 0;
+        for mut request in server.incoming_requests() {
+            // Keep the id of the caller so its logs and ours can be joined
+            let request_id =
+                match header(&request, "X-Request-Id").filter(|id| valid_request_id(id)) {
+                    Some(id) => id.to_owned(),
+                    None => {
+                        next_id += 1;
+                        format!("serve-{next_id}")
+                    }
+                };
+            let span = tracing::info_span!(
+                "api_request",
+                request_id = %request_id,
+                method = %request.method(),
+                path = %request.url().split('?').next().unwrap_or_default(),
+            );
+            let _entered = span.enter();
+            let started = Instant::now();
+            let (status, body) = match self.handle(&mut request) {
+                Ok(reply) => reply,
+                Err(err) => {
+                    if err.status >= 500 {
+                        tracing::error!("{:#}", err.error);
+                    }
+                    (err.status, ApiBody::Json(err.body()))
+                }
+            };
+            tracing::info!(
+                status,
+                latency_ms = started.elapsed().as_millis() as u64,
+                remote = %request
+                    .remote_addr()
+                    .map(|addr| addr.ip().to_string())
+                    .unwrap_or_default(),
+                "served"
+            );
+            let response = match body {
+                ApiBody::Json(body) => Response::from_string(body.to_string())
+                    .with_header(content_type("application/json")),
+                ApiBody::Metrics(text) => {
+                    Response::from_string(text).with_header(content_type(TEXT_FORMAT))
+                }
+            }
+            .with_status_code(status)
+            .with_header(
+                Header::from_bytes("X-Request-Id", request_id.as_bytes()).expect("valid header"),
+            );
+            if let Err(err) = request.respond(response) {
+                tracing::warn!("cannot send the response: {}", err);
+            }
+        }
+    }
+
+    fn handle(&self, request: &mut Request) -> ApiResult {
+        let url = request.url().to_owned();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let method = request.method().clone();
+
+        // The probes stay open for the orchestrator
+        match (&method, path) {
+            (Method::Get, "/healthz") => return reply(&json!({ "status": "ok" })),
+            (Method::Get, "/readyz") => {
+                return match self.handler.binance().ping() {
+                    Ok(()) => reply(&json!({ "status": "ready" })),
+                    Err(err) => Err(ApiError::new(503, err.context("Binance is unreachable"))),
+                }
+            }
+            _ => {}
+        }
+        if !self.authorized(request) {
+            return Err(ApiError::new(
+                401,
+                anyhow!("missing or invalid bearer token"),
+            ));
+        }
+        let query = serde_urlencoded::from_str::<HashMap<String, String>>(query)
+            .map_err(|err| ApiError::new(400, anyhow!("invalid query: {err}")))?;
+        let symbol = query.get("symbol").map(|symbol| symbol.to_uppercase());
+        let binance = self.handler.binance();
+
+        match (&method, path) {
+            (Method::Get, "/account") => reply(&binance.get_account()?),
+            (Method::Get, "/price") => {
+                let prices = binance.get_prices()?;
+                match symbol {
+                    None => reply(&prices),
+                    Some(symbol) => match prices.iter().find(|price| price.symbol == symbol) {
+                        Some(price) => reply(price),
+                        None => Err(ApiError::new(404, anyhow!("unknown symbol {symbol}"))),
+                    },
+                }
+            }
+            (Method::Get, "/orders") => {
+                let orders = binance
+                    .get_open_orders()?
+                    .into_iter()
+                    .filter(|order| symbol.is_none() || order.symbol == symbol)
+                    .collect::<Vec<_>>();
+                reply(&orders)
+            }
+            (Method::Post, "/orders") => self.place_order(request),
+            (Method::Get, "/metrics") => {
+                // `GET /orders` keeps the gauges current too, a failed read leaves them as they are
+                let stale = Metrics::global()
+                    .open_orders_age()
+                    .is_none_or(|age| age > OPEN_ORDERS_MAX_AGE);
+                if stale {
+                    if let Err(err) = binance.get_open_orders() {
+                        tracing::warn!("cannot read the open orders for the metrics: {:#}", err);
+                    }
+                }
+                Ok((200, ApiBody::Metrics(Metrics::global().encode()?)))
+            }
+            (_, "/account" | "/price" | "/orders" | "/metrics") => Err(ApiError::new(
+                405,
+                anyhow!("method {method} not allowed on {path}"),
+            )),
+            _ => Err(ApiError::new(404, anyhow!("no route {path}"))),
+        }
+    }
+
+    /// Place the order of the body, or only report on it in dry run
+    fn place_order(&self, request: &mut Request) -> ApiResult {
+        if request.body_length().unwrap_or_default() as u64 > MAX_BODY {
+            return Err(ApiError::new(
+                413,
+                anyhow!("order larger than {MAX_BODY} bytes"),
+            ));
+        }
+        let mut body = String::new();
+        request
+            .as_reader()
+            .take(MAX_BODY)
+            .read_to_string(&mut body)
+            .context("cannot read the body")?;
+        let mut order = serde_json::from_str::<BinanceSpotOrderRequest>(&body)
+            .map_err(|err| ApiError::new(400, anyhow!("invalid order: {err}")))?;
+        order.symbol = order.symbol.to_uppercase();
+
+        if self.dry_run {
+            let report = self.handler.dry_runner().check(&order)?;
+            let status = if report.valid { 200 } else { 422 };
+            return Ok((status, ApiBody::Json(to_value(&report)?)));
+        }
+        let errors = order.validate();
+        if !errors.is_empty() {
+            return Err(ApiError::new(
+                422,
+                anyhow!("invalid order: {}", errors.join("; ")),
+            ));
+        }
+        let placed = self.handler.place_order(order)?;
+        Ok((201, ApiBody::Json(to_value(&placed)?)))
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        header(request, "Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| constant_time_eq(token.trim().as_bytes(), self.token.expose().as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+fn reply<T: Serialize>(value: &T) -> ApiResult {
+    Ok((200, ApiBody::Json(to_value(value)?)))
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).context("cannot serialize the response")
+}
+
+fn header<'r>(request: &'r Request, name: &'static str) -> Option<&'r str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Ids safe to log and send back
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("valid header")
+}
+
+/// Compare without returning early, so the time taken tells nothing about the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use reqwest::blocking::{Client, RequestBuilder};
+
+    use super::*;
+    use crate::{
+        app_config::AppConfig,
+        application::output::{Output, OutputFormat},
+        infrastructure::transport::testing::{cassette, API_KEY, SECRET_KEY},
+    };
+
+    const TOKEN: &str = "servetoken-abcdefgh";
+
+    /// Serve the profile of the config, answered by the serve cassette, returning its url
+    fn start(config: &str) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        let config = format!(
+            "api_key = \"{API_KEY}\"\nsecret_key = \"{SECRET_KEY}\"\nenvironment = \"mock\"\n{config}"
+        );
+        thread::spawn(move || {
+            let cfg = toml::from_str::<AppConfig>(&config).unwrap();
+            let handler = CommandHandler::new(cfg, None, Output::new(OutputFormat::Json))
+                .unwrap()
+                .with_cassette(None, Some(cassette("serve.json")))
+                .unwrap();
+            ApiServer::new(&handler, SecretString::new(TOKEN.to_owned()), false).serve(&server);
+        });
+        format!("http://{addr}")
+    }
+
+    fn authorized(request: RequestBuilder) -> RequestBuilder {
+        request.header("Authorization", format!("Bearer {TOKEN}"))
+    }
+
+    fn order(body: &str) -> RequestBuilder {
+        Client::new()
+            .post(format!("{}/orders", start("")))
+            .header("Content-Type", "application/json")
+            .body(body.to_owned())
+    }
+
+    const LIMIT_ORDER: &str = r#"{"symbol":"btcusdt","side":"BUY","type":"LIMIT","timeInForce":"GTC","quantity":"0.001","price":"20100"}"#;
+
+    #[test]
+    fn healthz_needs_no_token() {
+        let response = Client::new()
+            .get(format!("{}/healthz", start("")))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().unwrap(), r#"{"status":"ok"}"#);
+    }
+
+    #[test]
+    fn routes_need_the_bearer_token() {
+        let url = format!("{}/account", start(""));
+        let client = Client::new();
+        let missing = client.get(&url).send().unwrap();
+        assert_eq!(missing.status(), 401);
+        let wrong = client
+            .get(&url)
+            .header("Authorization", "Bearer servetoken-abcdefgX")
+            .send()
+            .unwrap();
+        assert_eq!(wrong.status(), 401);
+        let basic = client
+            .get(&url)
+            .header("Authorization", format!("Basic {TOKEN}"))
+            .send()
+            .unwrap();
+        assert_eq!(basic.status(), 401);
+
+        let response = authorized(client.get(&url)).send().unwrap();
+        assert_eq!(response.status(), 200);
+        let account = response.json::<Value>().unwrap();
+        assert_eq!(account["balances"][0]["asset"], "BTC");
+    }
+
+    #[test]
+    fn order_is_placed() {
+        let response = authorized(order(LIMIT_ORDER)).send().unwrap();
+        assert_eq!(response.status(), 201);
+        let placed = response.json::<Value>().unwrap();
+        assert_eq!(placed["orderId"], 2);
+    }
+
+    #[test]
+    fn order_body_over_max_body_is_refused() {
+        let body = format!(
+            r#"{{"symbol":"BTCUSDT","padding":"{}"}}"#,
+            "x".repeat(MAX_BODY as usize)
+        );
+        let response = authorized(order(&body)).send().unwrap();
+        assert_eq!(response.status(), 413);
+    }
+
+    #[test]
+    fn invalid_order_is_unprocessable() {
+        let response = authorized(order(
+            r#"{"symbol":"BTCUSDT","side":"BUY","type":"LIMIT","quantity":"0.001"}"#,
+        ))
+        .send()
+        .unwrap();
+        assert_eq!(response.status(), 422);
+        let error = response.json::<Value>().unwrap()["error"].to_string();
+        assert!(error.contains("invalid order"), "{error}");
+    }
+
+    #[test]
+    fn order_rejected_by_the_risk_limits_is_unprocessable() {
+        let url = start("[risk]\nallowed_symbols = [\"ETHUSDT\"]");
+        let response = authorized(Client::new().post(format!("{url}/orders")))
+            .body(LIMIT_ORDER)
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 422);
+        let body = response.json::<Value>().unwrap();
+        assert_eq!(body["reasons"], json!(["BTCUSDT is not an allowed symbol"]));
+    }
+
+    #[test]
+    fn metrics_leave_the_balances_out() {
+        let url = start("");
+        let client = Client::new();
+        authorized(client.get(format!("{url}/account")))
+            .send()
+            .unwrap();
+        let response = authorized(client.get(format!("{url}/metrics")))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let text = response.text().unwrap();
+        assert!(
+            text.contains("binance_open_orders{symbol=\"BTCUSDT\"}"),
+            "{text}"
+        );
+        assert!(!text.contains("balance"), "{text}");
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A condition on the live prices of a symbol, notified once met.
+///
+/// ```toml
+/// [[alerts]]
+/// name = "btc-above-30k"
+/// symbol = "BTCUSDT"
+/// type = "price_cross"
+/// level = 30000.0
+/// direction = "up"
+///
+/// [[alerts]]
+/// name = "eth-moves"
+/// symbol = "ETHUSDT"
+/// type = "percent_move"
+/// percent = 3.0
+/// window_secs = 900
+/// cooldown_secs = 600
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub symbol: String,
+    /// Don't notify the rule again for this long after a notification
+    #[serde(default)]
+    pub cooldown_secs: u64,
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The last trade price goes through the level
+    PriceCross {
+        level: f64,
+        #[serde(default)]
+        direction: CrossDirection,
+    },
+
+    /// The last trade price moved by `percent` or more within the window, either way
+    PercentMove { percent: f64, window_secs: u64 },
+
+    /// The spread between the best bid and ask is wider than `max_bps` basis points of the mid price
+    Spread { max_bps: f64 },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossDirection {
+    Up,
+    Down,
+    #[default]
+    Any,
+}
+
+impl AlertCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PriceCross { .. } => "price_cross",
+            Self::PercentMove { .. } => "percent_move",
+            Self::Spread { .. } => "spread",
+        }
+    }
+
+    /// Whether the condition follows the best bid and ask instead of the trades
+    pub fn needs_book(&self) -> bool {
+        matches!(self, Self::Spread { .. })
+    }
+}
+
+/// Live market data the rules are evaluated against
+#[derive(Debug, Clone, Copy)]
+pub enum MarketUpdate {
+    Trade { price: f64 },
+    Book { bid: f64, ask: f64 },
+}
+
+/// A notification of a rule whose condition was met
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub name: String,
+    pub symbol: String,
+    pub condition: &'static str,
+    pub message: String,
+    /// Last trade price, or mid price for a spread
+    pub price: f64,
+    pub time: DateTime<Utc>,
+}
+
+/// An [AlertRule] and what it has seen of the market so far
+///
+/// ```
+/// use binance_rs::domain::alert::*;
+/// use chrono::Utc;
+///
+/// let rule = AlertRule {
+///     name: "btc-above-30k".to_owned(),
+///     symbol: "BTCUSDT".to_owned(),
+///     cooldown_secs: 0,
+///     condition: AlertCondition::PriceCross {
+///         level: 30000.0,
+///         direction: CrossDirection::Up,
+///     },
+/// };
+/// let mut watch = AlertWatch::new(rule);
+/// assert!(watch.update(&MarketUpdate::Trade { price: 29990.0 }, Utc::now()).is_none());
+/// let alert = watch.update(&MarketUpdate::Trade { price: 30010.0 }, Utc::now());
+/// assert_eq!(alert.unwrap().price, 30010.0);
+/// ```
+#[derive(Debug)]
+pub struct AlertWatch {
+    pub rule: AlertRule,
+    last_price: Option<f64>,
+    history: VecDeque<(DateTime<Utc>, f64)>,
+    wide: bool,
+    notified_at: Option<DateTime<Utc>>,
+}
+
+impl AlertWatch {
+    pub fn new(rule: AlertRule) -> Self {
+        Self {
+            rule,
+            last_price: None,
+            history: VecDeque::new(),
+            wide: false,
+            notified_at: None,
+        }
+    }
+
+    /// Evaluate the rule with a market update of its symbol, return the alert to notify
+    pub fn update(&mut self, update: &MarketUpdate, now: DateTime<Utc>) -> Option<Alert> {
+        let (price, message) = match (&self.rule.condition, *update) {
+            (AlertCondition::PriceCross { level, direction }, MarketUpdate::Trade { price }) => {
+                let previous = self.last_price.replace(price);
+                let up = matches!(previous, Some(p) if p < *level) && price >= *level;
+                let down = matches!(previous, Some(p) if p > *level) && price <= *level;
+                let crossed = match direction {
+                    CrossDirection::Up => up,
+                    CrossDirection::Down => down,
+                    CrossDirection::Any => up || down,
+                };
+                if !crossed {
+                    return None;
+                }
+                let way = if up { "above" } else { "below" };
+                (
+                    price,
+                    format!("{} crossed {} {}", self.rule.symbol, way, level),
+                )
+            }
+            (
+                AlertCondition::PercentMove {
+                    percent,
+                    window_secs,
+                },
+                MarketUpdate::Trade { price },
+            ) => {
+                let start = now - Duration::seconds(*window_secs as i64);
+                while matches!(self.history.front(), Some((time, _)) if *time < start) {
+                    self.history.pop_front();
+                }
+                self.history.push_back((now, price));
+                let (_, first) = self.history.front()?;
+                let change = (price - first) / first * 100.0;
+                if change.abs() < *percent {
+                    return None;
+                }
+                let message = format!(
+                    "{} moved {:+.2}% in {}s, from {} to {}",
+                    self.rule.symbol, change, window_secs, first, price
+                );
+                // Measure the next move from here
+                self.history.clear();
+                self.history.push_back((now, price));
+                (price, message)
+            }
+            (AlertCondition::Spread { max_bps }, MarketUpdate::Book { bid, ask }) => {
+                let mid = (bid + ask) / 2.0;
+                let spread_bps = (ask - bid) / mid * 10_000.0;
+                let was_wide = self.wide;
+                self.wide = spread_bps > *max_bps;
+                if !self.wide || was_wide {
+                    return None;
+                }
+                let message = format!(
+                    "{} spread is {:.1} bps, bid {} ask {}",
+                    self.rule.symbol, spread_bps, bid, ask
+                );
+                (mid, message)
+            }
+            _ => return None,
+        };
+
+        let cooldown = Duration::seconds(self.rule.cooldown_secs as i64);
+        if matches!(self.notified_at, Some(at) if now < at + cooldown) {
+            return None;
+        }
+        self.notified_at = Some(now);
+        Some(Alert {
+            name: self.rule.name.to_owned(),
+            symbol: self.rule.symbol.to_owned(),
+            condition: self.rule.condition.name(),
+            message,
+            price,
+            time: now,
+        })
+    }
+}
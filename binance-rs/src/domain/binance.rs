@@ -1,13 +1,12 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, TimeZone, Utc,
+};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
-pub struct BinanceKeypair {
-    pub key: String,
-    pub secret: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BinanceSpotAccount {
     #[serde(rename = "makerCommission")]
@@ -86,10 +85,64 @@ pub struct Price {
     pub price: F64String,
 }
 
+/// Last price of a symbol, from `/api/v3/ticker/price`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymbolPrice {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "price")]
+    pub price: F64String,
+}
+
+/// Price of one `asset` in `quote` from the last prices by symbol, through the
+/// `<asset><quote>` market or the inverse of the `<quote><asset>` one
+pub fn convert_price(prices: &HashMap<String, f64>, asset: &str, quote: &str) -> Option<f64> {
+    if asset == quote {
+        return Some(1.0);
+    }
+    prices.get(&format!("{asset}{quote}")).copied().or_else(|| {
+        prices
+            .get(&format!("{quote}{asset}"))
+            .map(|price| 1.0 / price)
+    })
+}
+
+/// Best bid and ask of a symbol, from `/api/v3/ticker/bookTicker`
 #[derive(Debug, Serialize, Deserialize)]
+pub struct BookTicker {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "bidPrice")]
+    pub bid_price: F64String,
+
+    #[serde(rename = "bidQty")]
+    pub bid_qty: F64String,
+
+    #[serde(rename = "askPrice")]
+    pub ask_price: F64String,
+
+    #[serde(rename = "askQty")]
+    pub ask_qty: F64String,
+}
+
+impl BookTicker {
+    pub fn mid(&self) -> f64 {
+        (self.bid_price.value() + self.ask_price.value()) / 2.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(try_from = "String")]
 pub struct F64String(f64);
 
+impl F64String {
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
 impl From<f64> for F64String {
     fn from(value: f64) -> Self {
         Self(value)
@@ -102,7 +155,7 @@ impl TryFrom<String> for F64String {
     fn try_from(v: String) -> Result<Self, Self::Error> {
         let value = v
             .parse::<f64>()
-            .map_err(|e| format!("cannot parse {} to f64. {:?}", v, e))?;
+            .map_err(|e| format!("cannot parse {v} to f64. {e:?}"))?;
         Ok(Self(value))
     }
 }
@@ -113,25 +166,191 @@ impl Display for F64String {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum BinanceOrderSide {
-    #[serde(rename = "BUY")]
-    Buy,
+/// Enum of the string values of a Binance field. Values unknown to this version are kept
+/// in `Unknown` instead of failing the whole response.
+///
+/// ```
+/// // binance_enum! { BinanceOrderSide { Buy => "BUY", Sell => "SELL", } }
+/// use binance_rs::domain::binance::BinanceOrderSide;
+///
+/// assert_eq!(BinanceOrderSide::from("SELL".to_owned()), BinanceOrderSide::Sell);
+/// assert_eq!(BinanceOrderSide::Buy.as_str(), "BUY");
+/// let unknown = BinanceOrderSide::from("HOLD".to_owned());
+/// assert_eq!(unknown, BinanceOrderSide::Unknown("HOLD".to_owned()));
+/// assert_eq!(unknown.as_str(), "HOLD");
+/// ```
+macro_rules! binance_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            /// Value of the field in the Binance API
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value),
+                }
+            }
+        }
 
-    #[serde(rename = "SELL")]
-    Sell,
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.as_str().to_owned()
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum BinanceOrderType {
-    #[serde(rename = "LIMIT")]
-    Limit,
+binance_enum! {
+    BinanceOrderSide {
+        Buy => "BUY",
+        Sell => "SELL",
+    }
+}
 
-    #[serde(rename = "MARKET")]
-    Market,
+binance_enum! {
+    BinanceOrderType {
+        Limit => "LIMIT",
+        Market => "MARKET",
+        StopLoss => "STOP_LOSS",
+        StopLossLimit => "STOP_LOSS_LIMIT",
+        TakeProfit => "TAKE_PROFIT",
+        TakeProfitLimit => "TAKE_PROFIT_LIMIT",
+        LimitMaker => "LIMIT_MAKER",
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+binance_enum! {
+    BinanceOrderStatus {
+        New => "NEW",
+        PendingNew => "PENDING_NEW",
+        PartiallyFilled => "PARTIALLY_FILLED",
+        Filled => "FILLED",
+        Canceled => "CANCELED",
+        PendingCancel => "PENDING_CANCEL",
+        Rejected => "REJECTED",
+        Expired => "EXPIRED",
+        ExpiredInMatch => "EXPIRED_IN_MATCH",
+    }
+}
+
+binance_enum! {
+    BinanceTimeInForce {
+        Gtc => "GTC",
+        Ioc => "IOC",
+        Fok => "FOK",
+    }
+}
+
+impl BinanceOrderType {
+    /// The order rests in the book at its `price` once placed or triggered
+    pub fn has_limit_price(&self) -> bool {
+        matches!(
+            self,
+            Self::Limit | Self::StopLossLimit | Self::TakeProfitLimit | Self::LimitMaker
+        )
+    }
+
+    /// The order waits for `stopPrice` or `trailingDelta` to trigger
+    pub fn is_conditional(&self) -> bool {
+        matches!(
+            self,
+            Self::StopLoss | Self::StopLossLimit | Self::TakeProfit | Self::TakeProfitLimit
+        )
+    }
+
+    pub fn requires_time_in_force(&self) -> bool {
+        matches!(
+            self,
+            Self::Limit | Self::StopLossLimit | Self::TakeProfitLimit
+        )
+    }
+}
+
+impl FromStr for BinanceOrderSide {
+    type Err = String;
+
+    /// Case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::from(s.to_uppercase()) {
+            Self::Unknown(_) => Err(format!("unknown side {s}")),
+            side => Ok(side),
+        }
+    }
+}
+
+impl FromStr for BinanceOrderType {
+    type Err = String;
+
+    /// Case insensitive, `-` can be used instead of `_`, e.g. `stop-loss-limit`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::from(s.to_uppercase().replace('-', "_")) {
+            Self::Unknown(_) => Err(format!("unknown order type {s}")),
+            order_type => Ok(order_type),
+        }
+    }
+}
+
+impl BinanceOrderStatus {
+    /// The order can still trade
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            Self::New | Self::PendingNew | Self::PartiallyFilled | Self::PendingCancel
+        )
+    }
+}
+
+/// Level of detail of the new order response, FULL includes the fills
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceOrderResponseType {
+    #[serde(rename = "ACK")]
+    Ack,
+
+    #[serde(rename = "RESULT")]
+    Result,
+
+    #[serde(rename = "FULL")]
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceSelfTradePreventionMode {
+    #[serde(rename = "NONE")]
+    None,
+
+    #[serde(rename = "EXPIRE_TAKER")]
+    ExpireTaker,
+
+    #[serde(rename = "EXPIRE_MAKER")]
+    ExpireMaker,
+
+    #[serde(rename = "EXPIRE_BOTH")]
+    ExpireBoth,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BinanceSpotOrderRequest {
     #[serde(rename = "symbol")]
     pub symbol: String,
@@ -143,13 +362,161 @@ pub struct BinanceSpotOrderRequest {
     pub order_type: BinanceOrderType,
 
     #[serde(rename = "quantity")]
-    pub quantity: F64String,
+    pub quantity: Option<F64String>,
+
+    /// Amount of the quote asset to spend (BUY) or receive (SELL) for MARKET orders
+    #[serde(rename = "quoteOrderQty")]
+    pub quote_order_qty: Option<F64String>,
 
     #[serde(rename = "price")]
     pub price: Option<F64String>,
 
     #[serde(rename = "timeInForce")]
-    pub time_in_force: Option<String>,
+    pub time_in_force: Option<BinanceTimeInForce>,
+
+    /// Trigger price of STOP_LOSS, STOP_LOSS_LIMIT, TAKE_PROFIT and TAKE_PROFIT_LIMIT orders
+    #[serde(default, rename = "stopPrice")]
+    pub stop_price: Option<F64String>,
+
+    /// Trailing stop distance in basis points, instead of or along with `stopPrice`
+    #[serde(default, rename = "trailingDelta")]
+    pub trailing_delta: Option<u32>,
+
+    /// Visible quantity of an iceberg order, limit price orders only
+    #[serde(default, rename = "icebergQty")]
+    pub iceberg_qty: Option<F64String>,
+
+    #[serde(default, rename = "newClientOrderId")]
+    pub new_client_order_id: Option<String>,
+
+    #[serde(default, rename = "newOrderRespType")]
+    pub new_order_resp_type: Option<BinanceOrderResponseType>,
+
+    #[serde(default, rename = "selfTradePreventionMode")]
+    pub self_trade_prevention_mode: Option<BinanceSelfTradePreventionMode>,
+}
+
+impl BinanceSpotOrderRequest {
+    /// A new order of the given type with every optional field unset
+    pub fn new(symbol: String, side: BinanceOrderSide, order_type: BinanceOrderType) -> Self {
+        Self {
+            symbol,
+            side,
+            order_type,
+            quantity: None,
+            quote_order_qty: None,
+            price: None,
+            time_in_force: None,
+            stop_price: None,
+            trailing_delta: None,
+            iceberg_qty: None,
+            new_client_order_id: None,
+            new_order_resp_type: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    /// Check the fields required, or not allowed, by the order type, returning every problem found
+    ///
+    /// | type              | required                                           |
+    /// |-------------------|----------------------------------------------------|
+    /// | LIMIT             | timeInForce, quantity, price                       |
+    /// | MARKET            | quantity or quoteOrderQty                          |
+    /// | STOP_LOSS         | quantity, stopPrice or trailingDelta               |
+    /// | STOP_LOSS_LIMIT   | timeInForce, quantity, price, stopPrice or trailingDelta |
+    /// | TAKE_PROFIT       | quantity, stopPrice or trailingDelta               |
+    /// | TAKE_PROFIT_LIMIT | timeInForce, quantity, price, stopPrice or trailingDelta |
+    /// | LIMIT_MAKER       | quantity, price                                    |
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if let BinanceOrderSide::Unknown(side) = &self.side {
+            errors.push(format!("unknown side {side}"));
+        }
+        if let Some(BinanceTimeInForce::Unknown(time_in_force)) = &self.time_in_force {
+            errors.push(format!("unknown timeInForce {time_in_force}"));
+        }
+        if let BinanceOrderType::Unknown(order_type) = &self.order_type {
+            errors.push(format!("unknown order type {order_type}"));
+            return errors;
+        }
+        let positive = |name: &str, value: Option<F64String>, errors: &mut Vec<String>| {
+            if let Some(value) = value {
+                if value.value() <= 0.0 {
+                    errors.push(format!("{name} must be positive"));
+                }
+            }
+        };
+        positive("quantity", self.quantity, &mut errors);
+        positive("quoteOrderQty", self.quote_order_qty, &mut errors);
+        positive("price", self.price, &mut errors);
+        positive("stopPrice", self.stop_price, &mut errors);
+        positive("icebergQty", self.iceberg_qty, &mut errors);
+        if self.trailing_delta == Some(0) {
+            errors.push("trailingDelta must be positive".to_owned());
+        }
+
+        let order_type = &self.order_type;
+        let check = |name: &str, present: bool, required: bool, errors: &mut Vec<String>| {
+            if required && !present {
+                errors.push(format!("{order_type} order requires {name}"));
+            }
+            if !required && present {
+                errors.push(format!("{order_type} order does not support {name}"));
+            }
+        };
+        if *order_type == BinanceOrderType::Market {
+            if self.quantity.is_some() == self.quote_order_qty.is_some() {
+                errors.push("MARKET order requires either quantity or quoteOrderQty".to_owned());
+            }
+        } else {
+            check("quantity", self.quantity.is_some(), true, &mut errors);
+            check(
+                "quoteOrderQty",
+                self.quote_order_qty.is_some(),
+                false,
+                &mut errors,
+            );
+        }
+        check(
+            "price",
+            self.price.is_some(),
+            order_type.has_limit_price(),
+            &mut errors,
+        );
+        check(
+            "timeInForce",
+            self.time_in_force.is_some(),
+            order_type.requires_time_in_force(),
+            &mut errors,
+        );
+        if order_type.is_conditional() {
+            if self.stop_price.is_none() && self.trailing_delta.is_none() {
+                errors.push(format!(
+                    "{order_type} order requires stopPrice or trailingDelta"
+                ));
+            }
+        } else {
+            check("stopPrice", self.stop_price.is_some(), false, &mut errors);
+            check(
+                "trailingDelta",
+                self.trailing_delta.is_some(),
+                false,
+                &mut errors,
+            );
+        }
+        if self.iceberg_qty.is_some() {
+            check(
+                "icebergQty",
+                true,
+                order_type.has_limit_price(),
+                &mut errors,
+            );
+            if matches!(&self.time_in_force, Some(tif) if *tif != BinanceTimeInForce::Gtc) {
+                errors.push("icebergQty requires timeInForce GTC".to_owned());
+            }
+        }
+        errors
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -167,40 +534,40 @@ pub struct BinanceSpotOrder {
     pub client_order_id: Option<String>,
 
     #[serde(rename = "price")]
-    pub price: Option<String>,
+    pub price: Option<Decimal>,
 
     #[serde(rename = "origQty")]
-    pub orig_qty: Option<String>,
+    pub orig_qty: Option<Decimal>,
 
     #[serde(rename = "executedQty")]
-    pub executed_qty: Option<String>,
+    pub executed_qty: Option<Decimal>,
 
     #[serde(rename = "cummulativeQuoteQty")]
-    pub cummulative_quote_qty: Option<String>,
+    pub cummulative_quote_qty: Option<Decimal>,
 
     #[serde(rename = "status")]
-    pub status: Option<String>,
+    pub status: Option<BinanceOrderStatus>,
 
     #[serde(rename = "timeInForce")]
-    pub time_in_force: Option<String>,
+    pub time_in_force: Option<BinanceTimeInForce>,
 
     #[serde(rename = "type")]
-    pub binance_spot_order_type: Option<String>,
+    pub binance_spot_order_type: Option<BinanceOrderType>,
 
     #[serde(rename = "side")]
-    pub side: Option<String>,
+    pub side: Option<BinanceOrderSide>,
 
     #[serde(rename = "stopPrice")]
-    pub stop_price: Option<String>,
+    pub stop_price: Option<Decimal>,
 
     #[serde(rename = "icebergQty")]
-    pub iceberg_qty: Option<String>,
+    pub iceberg_qty: Option<Decimal>,
 
-    #[serde(rename = "time")]
-    pub time: Option<i64>,
+    #[serde(default, rename = "time", with = "ts_milliseconds_option")]
+    pub time: Option<DateTime<Utc>>,
 
-    #[serde(rename = "updateTime")]
-    pub update_time: Option<i64>,
+    #[serde(default, rename = "updateTime", with = "ts_milliseconds_option")]
+    pub update_time: Option<DateTime<Utc>>,
 
     #[serde(rename = "isWorking")]
     pub is_working: Option<bool>,
@@ -209,8 +576,873 @@ pub struct BinanceSpotOrder {
     pub working_time: Option<i64>,
 
     #[serde(rename = "origQuoteOrderQty")]
-    pub orig_quote_order_qty: Option<String>,
+    pub orig_quote_order_qty: Option<Decimal>,
 
     #[serde(rename = "selfTradePreventionMode")]
     pub self_trade_prevention_mode: Option<String>,
+
+    #[serde(default, rename = "transactTime", with = "ts_milliseconds_option")]
+    pub transact_time: Option<DateTime<Utc>>,
+
+    #[serde(default, rename = "trailingDelta")]
+    pub trailing_delta: Option<i64>,
+
+    #[serde(default, rename = "trailingTime")]
+    pub trailing_time: Option<i64>,
+
+    /// Trades of the order, only in the FULL new order response
+    #[serde(default, rename = "fills")]
+    pub fills: Option<Vec<BinanceOrderFill>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceOrderFill {
+    #[serde(rename = "price")]
+    pub price: Decimal,
+
+    #[serde(rename = "qty")]
+    pub qty: Decimal,
+
+    #[serde(rename = "commission")]
+    pub commission: Decimal,
+
+    #[serde(rename = "commissionAsset")]
+    pub commission_asset: String,
+
+    #[serde(rename = "tradeId")]
+    pub trade_id: i64,
+}
+
+/// A trade of the account, from `/api/v3/myTrades`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceTrade {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "id")]
+    pub id: i64,
+
+    #[serde(rename = "orderId")]
+    pub order_id: i64,
+
+    #[serde(rename = "price")]
+    pub price: Decimal,
+
+    #[serde(rename = "qty")]
+    pub qty: Decimal,
+
+    #[serde(rename = "quoteQty")]
+    pub quote_qty: Decimal,
+
+    #[serde(rename = "commission")]
+    pub commission: Decimal,
+
+    #[serde(rename = "commissionAsset")]
+    pub commission_asset: String,
+
+    #[serde(rename = "time", with = "ts_milliseconds")]
+    pub time: DateTime<Utc>,
+
+    #[serde(rename = "isBuyer")]
+    pub is_buyer: bool,
+
+    #[serde(rename = "isMaker")]
+    pub is_maker: bool,
+}
+
+/// A candlestick from `/api/v3/klines`, sent as an array of values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<serde_json::Value>")]
+pub struct BinanceKline {
+    pub open_time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl TryFrom<Vec<serde_json::Value>> for BinanceKline {
+    type Error = String;
+
+    fn try_from(values: Vec<serde_json::Value>) -> Result<Self, Self::Error> {
+        let number = |index: usize| -> Result<f64, String> {
+            let value = values
+                .get(index)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("kline value {index} is not a string"))?;
+            value
+                .parse::<f64>()
+                .map_err(|e| format!("cannot parse {value} to f64. {e:?}"))
+        };
+        let open_time = values
+            .first()
+            .and_then(|v| v.as_i64())
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+            .ok_or_else(|| "kline open time is not a timestamp".to_owned())?;
+        Ok(Self {
+            open_time,
+            open: number(1)?,
+            high: number(2)?,
+            low: number(3)?,
+            close: number(4)?,
+            volume: number(5)?,
+        })
+    }
+}
+
+/// An order list of contingent orders, read from a toml file with its `type`
+///
+/// ```toml
+/// # OCO: a take profit above and a stop loss below the price, one cancels the other
+/// type = "OCO"
+/// symbol = "BTCUSDT"
+/// side = "SELL"
+/// quantity = "0.001"
+///
+/// [above]
+/// type = "LIMIT_MAKER"
+/// price = "22000"
+///
+/// [below]
+/// type = "STOP_LOSS_LIMIT"
+/// stopPrice = "19000"
+/// price = "18950"
+/// timeInForce = "GTC"
+/// ```
+///
+/// OTO places the `[pending]` order once the `[working]` order fills, both with their own
+/// `side` and `quantity`. OTOCO places the OCO of `[pending]` with `side`, `quantity`,
+/// `[pending.above]` and an optional `[pending.below]` once `[working]` fills.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BinanceOrderListRequest {
+    #[serde(rename = "OCO")]
+    Oco(BinanceOcoRequest),
+
+    #[serde(rename = "OTO")]
+    Oto(BinanceOtoRequest),
+
+    #[serde(rename = "OTOCO")]
+    Otoco(BinanceOtocoRequest),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceOcoRequest {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(default, rename = "listClientOrderId")]
+    pub list_client_order_id: Option<String>,
+
+    #[serde(rename = "side")]
+    pub side: BinanceOrderSide,
+
+    #[serde(rename = "quantity")]
+    pub quantity: F64String,
+
+    #[serde(rename = "above")]
+    pub above: BinanceOrderListLeg,
+
+    #[serde(rename = "below")]
+    pub below: BinanceOrderListLeg,
+
+    #[serde(default, rename = "newOrderRespType")]
+    pub new_order_resp_type: Option<BinanceOrderResponseType>,
+
+    #[serde(default, rename = "selfTradePreventionMode")]
+    pub self_trade_prevention_mode: Option<BinanceSelfTradePreventionMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceOtoRequest {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(default, rename = "listClientOrderId")]
+    pub list_client_order_id: Option<String>,
+
+    #[serde(rename = "working")]
+    pub working: BinanceOrderListLeg,
+
+    #[serde(rename = "pending")]
+    pub pending: BinanceOrderListLeg,
+
+    #[serde(default, rename = "newOrderRespType")]
+    pub new_order_resp_type: Option<BinanceOrderResponseType>,
+
+    #[serde(default, rename = "selfTradePreventionMode")]
+    pub self_trade_prevention_mode: Option<BinanceSelfTradePreventionMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceOtocoRequest {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(default, rename = "listClientOrderId")]
+    pub list_client_order_id: Option<String>,
+
+    #[serde(rename = "working")]
+    pub working: BinanceOrderListLeg,
+
+    #[serde(rename = "pending")]
+    pub pending: BinanceOtocoPending,
+
+    #[serde(default, rename = "newOrderRespType")]
+    pub new_order_resp_type: Option<BinanceOrderResponseType>,
+
+    #[serde(default, rename = "selfTradePreventionMode")]
+    pub self_trade_prevention_mode: Option<BinanceSelfTradePreventionMode>,
+}
+
+/// The OCO placed once the working order of an OTOCO fills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceOtocoPending {
+    #[serde(rename = "side")]
+    pub side: BinanceOrderSide,
+
+    #[serde(rename = "quantity")]
+    pub quantity: F64String,
+
+    #[serde(rename = "above")]
+    pub above: BinanceOrderListLeg,
+
+    #[serde(default, rename = "below")]
+    pub below: Option<BinanceOrderListLeg>,
+}
+
+/// One order of an order list. `side` and `quantity` are only set on OTO orders,
+/// the legs of an OCO share the ones of the list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceOrderListLeg {
+    #[serde(rename = "type")]
+    pub order_type: BinanceOrderType,
+
+    #[serde(default, rename = "side")]
+    pub side: Option<BinanceOrderSide>,
+
+    #[serde(default, rename = "quantity")]
+    pub quantity: Option<F64String>,
+
+    #[serde(default, rename = "price")]
+    pub price: Option<F64String>,
+
+    #[serde(default, rename = "stopPrice")]
+    pub stop_price: Option<F64String>,
+
+    #[serde(default, rename = "trailingDelta")]
+    pub trailing_delta: Option<u32>,
+
+    #[serde(default, rename = "icebergQty")]
+    pub iceberg_qty: Option<F64String>,
+
+    #[serde(default, rename = "timeInForce")]
+    pub time_in_force: Option<BinanceTimeInForce>,
+
+    #[serde(default, rename = "clientOrderId")]
+    pub client_order_id: Option<String>,
+}
+
+impl BinanceOrderListLeg {
+    /// The leg as a standalone order, with the side and quantity of the list when it has none
+    pub fn to_order(
+        &self,
+        symbol: &str,
+        side: Option<BinanceOrderSide>,
+        quantity: Option<F64String>,
+    ) -> Option<BinanceSpotOrderRequest> {
+        let mut order = BinanceSpotOrderRequest::new(
+            symbol.to_owned(),
+            self.side.clone().or(side)?,
+            self.order_type.clone(),
+        );
+        order.quantity = self.quantity.or(quantity);
+        order.price = self.price;
+        order.stop_price = self.stop_price;
+        order.trailing_delta = self.trailing_delta;
+        order.iceberg_qty = self.iceberg_qty;
+        order.time_in_force = self.time_in_force.clone();
+        order.new_client_order_id = self.client_order_id.clone();
+        Some(order)
+    }
+}
+
+impl BinanceOrderListRequest {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Oco(oco) => &oco.symbol,
+            Self::Oto(oto) => &oto.symbol,
+            Self::Otoco(otoco) => &otoco.symbol,
+        }
+    }
+
+    /// Name and content of every leg, as standalone orders.
+    /// Legs missing a side are left out, [BinanceOrderListRequest::validate] reports them.
+    pub fn legs(&self) -> Vec<(&'static str, BinanceSpotOrderRequest)> {
+        let symbol = self.symbol();
+        let legs = match self {
+            Self::Oco(oco) => vec![
+                (
+                    "above",
+                    oco.above
+                        .to_order(symbol, Some(oco.side.clone()), Some(oco.quantity)),
+                ),
+                (
+                    "below",
+                    oco.below
+                        .to_order(symbol, Some(oco.side.clone()), Some(oco.quantity)),
+                ),
+            ],
+            Self::Oto(oto) => vec![
+                ("working", oto.working.to_order(symbol, None, None)),
+                ("pending", oto.pending.to_order(symbol, None, None)),
+            ],
+            Self::Otoco(otoco) => {
+                let pending = &otoco.pending;
+                let (side, quantity) = (Some(pending.side.clone()), Some(pending.quantity));
+                let mut legs = vec![
+                    ("working", otoco.working.to_order(symbol, None, None)),
+                    (
+                        "pending.above",
+                        pending.above.to_order(symbol, side.clone(), quantity),
+                    ),
+                ];
+                if let Some(below) = &pending.below {
+                    legs.push(("pending.below", below.to_order(symbol, side, quantity)));
+                }
+                legs
+            }
+        };
+        legs.into_iter()
+            .filter_map(|(name, order)| Some((name, order?)))
+            .collect()
+    }
+
+    /// Check the order types allowed for each leg and the fields of every leg,
+    /// returning every problem found
+    pub fn validate(&self) -> Vec<String> {
+        use BinanceOrderType::*;
+        let above = [
+            StopLoss,
+            StopLossLimit,
+            TakeProfit,
+            TakeProfitLimit,
+            LimitMaker,
+        ];
+        let below = [StopLoss, StopLossLimit, TakeProfit, TakeProfitLimit];
+        let working = [Limit, LimitMaker];
+
+        let mut errors = vec![];
+        let mut check = |name: &str, leg: &BinanceOrderListLeg, allowed: &[BinanceOrderType]| {
+            if !allowed.contains(&leg.order_type) {
+                errors.push(format!("{} order cannot be {}", name, leg.order_type));
+            }
+        };
+        let mut shared = vec![];
+        let mut own = vec![];
+        match self {
+            Self::Oco(oco) => {
+                check("above", &oco.above, &above);
+                check("below", &oco.below, &below);
+                shared.extend([("above", &oco.above), ("below", &oco.below)]);
+            }
+            Self::Oto(oto) => {
+                check("working", &oto.working, &working);
+                own.extend([("working", &oto.working), ("pending", &oto.pending)]);
+            }
+            Self::Otoco(otoco) => {
+                check("working", &otoco.working, &working);
+                check("pending.above", &otoco.pending.above, &above);
+                shared.push(("pending.above", &otoco.pending.above));
+                if let Some(pending_below) = &otoco.pending.below {
+                    check("pending.below", pending_below, &below);
+                    shared.push(("pending.below", pending_below));
+                }
+                own.push(("working", &otoco.working));
+            }
+        }
+        for (name, leg) in shared {
+            if leg.side.is_some() || leg.quantity.is_some() {
+                errors.push(format!(
+                    "{name} order uses the side and quantity of the list"
+                ));
+            }
+        }
+        for (name, leg) in own {
+            if leg.side.is_none() {
+                errors.push(format!("{name} order requires side"));
+            }
+        }
+        for (name, order) in self.legs() {
+            errors.extend(
+                order
+                    .validate()
+                    .into_iter()
+                    .map(|error| format!("{name}: {error}")),
+            );
+        }
+        errors
+    }
+}
+
+/// Entry of the response of cancelling the open orders of a symbol,
+/// the orders of an order list are reported together
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BinanceCanceledOrder {
+    OrderList(BinanceOrderList),
+    Order(Box<BinanceSpotOrder>),
+}
+
+/// Response of placing, querying or cancelling an order list.
+/// The order reports are only in the responses of placing and cancelling.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceOrderList {
+    #[serde(rename = "orderListId")]
+    pub order_list_id: i64,
+
+    #[serde(rename = "contingencyType")]
+    pub contingency_type: String,
+
+    #[serde(rename = "listStatusType")]
+    pub list_status_type: String,
+
+    #[serde(rename = "listOrderStatus")]
+    pub list_order_status: String,
+
+    #[serde(rename = "listClientOrderId")]
+    pub list_client_order_id: String,
+
+    #[serde(rename = "transactionTime")]
+    pub transaction_time: i64,
+
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "orders")]
+    pub orders: Vec<BinanceOrderListOrder>,
+
+    #[serde(default, rename = "orderReports")]
+    pub order_reports: Vec<BinanceSpotOrder>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceOrderListOrder {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "orderId")]
+    pub order_id: i64,
+
+    #[serde(rename = "clientOrderId")]
+    pub client_order_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceExchangeInfo {
+    #[serde(rename = "symbols")]
+    pub symbols: Vec<BinanceSymbolInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinanceSymbolInfo {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "status")]
+    pub status: String,
+
+    #[serde(rename = "baseAsset")]
+    pub base_asset: String,
+
+    #[serde(rename = "quoteAsset")]
+    pub quote_asset: String,
+
+    #[serde(rename = "filters")]
+    pub filters: Vec<BinanceSymbolFilter>,
+}
+
+impl BinanceSymbolInfo {
+    /// Step size of the quantity from the `LOT_SIZE` filter
+    pub fn step_size(&self) -> Option<f64> {
+        self.filters.iter().find_map(|filter| match filter {
+            BinanceSymbolFilter::LotSize { step_size, .. } => Some(step_size.value()),
+            _ => None,
+        })
+    }
+
+    /// Check the order against the symbol status and filters.
+    /// `price` is the order price, or the current price for MARKET orders.
+    pub fn check_order(&self, order: &BinanceSpotOrderRequest, price: f64) -> Vec<String> {
+        let mut errors = vec![];
+        if self.status != "TRADING" {
+            errors.push(format!("symbol {} is {}", self.symbol, self.status));
+        }
+        let quantity = order.quantity.map(|q| q.value());
+        let notional = match (quantity, order.quote_order_qty) {
+            (Some(quantity), _) => quantity * price,
+            (None, Some(quote)) => quote.value(),
+            (None, None) => 0.0,
+        };
+        for filter in self.filters.iter() {
+            match filter {
+                BinanceSymbolFilter::PriceFilter {
+                    min_price,
+                    max_price,
+                    tick_size,
+                } => {
+                    let prices = [("price", order.price), ("stopPrice", order.stop_price)];
+                    for (name, price) in prices {
+                        if let Some(price) = price.map(|p| p.value()) {
+                            check_range(name, price, min_price, max_price, &mut errors);
+                            check_step(name, price, tick_size.value(), &mut errors);
+                        }
+                    }
+                }
+                BinanceSymbolFilter::LotSize {
+                    min_qty,
+                    max_qty,
+                    step_size,
+                } => {
+                    if let Some(quantity) = quantity {
+                        check_range("quantity", quantity, min_qty, max_qty, &mut errors);
+                        check_step("quantity", quantity, step_size.value(), &mut errors);
+                    }
+                    if let Some(iceberg_qty) = order.iceberg_qty.map(|q| q.value()) {
+                        check_step("icebergQty", iceberg_qty, step_size.value(), &mut errors);
+                    }
+                }
+                BinanceSymbolFilter::Notional {
+                    min_notional,
+                    apply_min_to_market,
+                    max_notional,
+                } => {
+                    let is_market = !order.order_type.has_limit_price();
+                    if (!is_market || *apply_min_to_market) && notional < min_notional.value() {
+                        errors.push(format!(
+                            "notional {notional} is below the minimum {min_notional}"
+                        ));
+                    }
+                    if notional > max_notional.value() {
+                        errors.push(format!(
+                            "notional {notional} is above the maximum {max_notional}"
+                        ));
+                    }
+                }
+                BinanceSymbolFilter::MinNotional { min_notional } => {
+                    if notional < min_notional.value() {
+                        errors.push(format!(
+                            "notional {notional} is below the minimum {min_notional}"
+                        ));
+                    }
+                }
+                BinanceSymbolFilter::Other => {}
+            }
+        }
+        errors
+    }
+
+    /// Tick size of the price from the `PRICE_FILTER` filter
+    pub fn tick_size(&self) -> Option<f64> {
+        self.filters.iter().find_map(|filter| match filter {
+            BinanceSymbolFilter::PriceFilter { tick_size, .. } => Some(tick_size.value()),
+            _ => None,
+        })
+    }
+}
+
+/// Response of the test order endpoint with `computeCommissionRates=true`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceTestOrderCommission {
+    #[serde(rename = "standardCommissionForOrder")]
+    pub standard_commission_for_order: Option<BinanceCommissionRate>,
+
+    #[serde(rename = "taxCommissionForOrder")]
+    pub tax_commission_for_order: Option<BinanceCommissionRate>,
+
+    #[serde(rename = "discount")]
+    pub discount: Option<BinanceCommissionDiscount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceCommissionRate {
+    #[serde(rename = "maker")]
+    pub maker: F64String,
+
+    #[serde(rename = "taker")]
+    pub taker: F64String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceCommissionDiscount {
+    #[serde(rename = "enabledForAccount")]
+    pub enabled_for_account: bool,
+
+    #[serde(rename = "enabledForSymbol")]
+    pub enabled_for_symbol: bool,
+
+    #[serde(rename = "discountAsset")]
+    pub discount_asset: String,
+
+    #[serde(rename = "discount")]
+    pub discount: F64String,
+}
+
+fn check_range(name: &str, value: f64, min: &F64String, max: &F64String, errors: &mut Vec<String>) {
+    // Binance uses 0 to disable the min or max of a filter
+    if min.value() > 0.0 && value < min.value() {
+        errors.push(format!("{name} {value} is below the minimum {min}"));
+    }
+    if max.value() > 0.0 && value > max.value() {
+        errors.push(format!("{name} {value} is above the maximum {max}"));
+    }
+}
+
+fn check_step(name: &str, value: f64, step: f64, errors: &mut Vec<String>) {
+    if step <= 0.0 {
+        return;
+    }
+    let steps = value / step;
+    if (steps - steps.round()).abs() > 1e-6 {
+        errors.push(format!("{name} {value} is not a multiple of {step}"));
+    }
+}
+
+/// The symbol filters used to validate orders, other filters are kept as [BinanceSymbolFilter::Other]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "filterType")]
+pub enum BinanceSymbolFilter {
+    #[serde(rename = "PRICE_FILTER")]
+    PriceFilter {
+        #[serde(rename = "minPrice")]
+        min_price: F64String,
+
+        #[serde(rename = "maxPrice")]
+        max_price: F64String,
+
+        #[serde(rename = "tickSize")]
+        tick_size: F64String,
+    },
+
+    #[serde(rename = "LOT_SIZE")]
+    LotSize {
+        #[serde(rename = "minQty")]
+        min_qty: F64String,
+
+        #[serde(rename = "maxQty")]
+        max_qty: F64String,
+
+        #[serde(rename = "stepSize")]
+        step_size: F64String,
+    },
+
+    #[serde(rename = "NOTIONAL")]
+    Notional {
+        #[serde(rename = "minNotional")]
+        min_notional: F64String,
+
+        #[serde(rename = "applyMinToMarket")]
+        apply_min_to_market: bool,
+
+        #[serde(rename = "maxNotional")]
+        max_notional: F64String,
+    },
+
+    #[serde(rename = "MIN_NOTIONAL")]
+    MinNotional {
+        #[serde(rename = "minNotional")]
+        min_notional: F64String,
+    },
+
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(order_type: &str) -> BinanceSpotOrderRequest {
+        BinanceSpotOrderRequest::new(
+            "BTCUSDT".to_owned(),
+            BinanceOrderSide::Buy,
+            order_type
+                .parse()
+                .unwrap_or_else(|_| BinanceOrderType::Unknown(order_type.to_owned())),
+        )
+    }
+
+    fn limit() -> BinanceSpotOrderRequest {
+        BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            price: Some(20000.0.into()),
+            time_in_force: Some(BinanceTimeInForce::Gtc),
+            ..order("LIMIT")
+        }
+    }
+
+    #[test]
+    fn limit_requires_price_and_time_in_force() {
+        assert!(limit().validate().is_empty());
+        let order = BinanceSpotOrderRequest {
+            price: None,
+            time_in_force: None,
+            ..limit()
+        };
+        assert_eq!(
+            order.validate(),
+            vec![
+                "LIMIT order requires price",
+                "LIMIT order requires timeInForce"
+            ]
+        );
+        let order = BinanceSpotOrderRequest {
+            quantity: None,
+            ..limit()
+        };
+        assert_eq!(order.validate(), vec!["LIMIT order requires quantity"]);
+    }
+
+    #[test]
+    fn market_requires_either_quantity_or_quote_order_qty() {
+        let quantity = BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            ..order("MARKET")
+        };
+        assert!(quantity.validate().is_empty());
+        let quote = BinanceSpotOrderRequest {
+            quote_order_qty: Some(20.0.into()),
+            ..order("MARKET")
+        };
+        assert!(quote.validate().is_empty());
+        let both = BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            quote_order_qty: Some(20.0.into()),
+            ..order("MARKET")
+        };
+        assert_eq!(
+            both.validate(),
+            vec!["MARKET order requires either quantity or quoteOrderQty"]
+        );
+        assert_eq!(
+            order("MARKET").validate(),
+            vec!["MARKET order requires either quantity or quoteOrderQty"]
+        );
+        let priced = BinanceSpotOrderRequest {
+            price: Some(20000.0.into()),
+            time_in_force: Some(BinanceTimeInForce::Gtc),
+            ..quantity
+        };
+        assert_eq!(
+            priced.validate(),
+            vec![
+                "MARKET order does not support price",
+                "MARKET order does not support timeInForce"
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_loss_requires_stop_price_or_trailing_delta() {
+        let stop_loss = BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            ..order("STOP_LOSS")
+        };
+        assert_eq!(
+            stop_loss.validate(),
+            vec!["STOP_LOSS order requires stopPrice or trailingDelta"]
+        );
+        let stop_price = BinanceSpotOrderRequest {
+            stop_price: Some(19000.0.into()),
+            ..order("STOP_LOSS")
+        };
+        assert_eq!(
+            stop_price.validate(),
+            vec!["STOP_LOSS order requires quantity"]
+        );
+        let trailing = BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            trailing_delta: Some(100),
+            ..order("STOP_LOSS")
+        };
+        assert!(trailing.validate().is_empty());
+        // Only the limit variants have a price
+        let priced = BinanceSpotOrderRequest {
+            price: Some(19000.0.into()),
+            stop_price: Some(19000.0.into()),
+            ..trailing
+        };
+        assert_eq!(
+            priced.validate(),
+            vec!["STOP_LOSS order does not support price"]
+        );
+    }
+
+    #[test]
+    fn stop_price_only_for_conditional_orders() {
+        let order = BinanceSpotOrderRequest {
+            stop_price: Some(19000.0.into()),
+            trailing_delta: Some(100),
+            ..limit()
+        };
+        assert_eq!(
+            order.validate(),
+            vec![
+                "LIMIT order does not support stopPrice",
+                "LIMIT order does not support trailingDelta"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_values_are_rejected() {
+        // The fields of an unknown type cannot be checked
+        assert_eq!(order("OTO").validate(), vec!["unknown order type OTO"]);
+        let order = BinanceSpotOrderRequest {
+            side: BinanceOrderSide::Unknown("HOLD".to_owned()),
+            time_in_force: Some(BinanceTimeInForce::Unknown("GTD".to_owned())),
+            ..limit()
+        };
+        assert_eq!(
+            order.validate(),
+            vec!["unknown side HOLD", "unknown timeInForce GTD"]
+        );
+    }
+
+    #[test]
+    fn amounts_must_be_positive() {
+        let order = BinanceSpotOrderRequest {
+            quantity: Some(0.0.into()),
+            price: Some((-1.0).into()),
+            ..limit()
+        };
+        assert_eq!(
+            order.validate(),
+            vec!["quantity must be positive", "price must be positive"]
+        );
+    }
+
+    #[test]
+    fn iceberg_of_gtc_limit_orders_only() {
+        let iceberg = BinanceSpotOrderRequest {
+            iceberg_qty: Some(0.0005.into()),
+            ..limit()
+        };
+        assert!(iceberg.validate().is_empty());
+        let ioc = BinanceSpotOrderRequest {
+            time_in_force: Some(BinanceTimeInForce::Ioc),
+            ..iceberg
+        };
+        assert_eq!(ioc.validate(), vec!["icebergQty requires timeInForce GTC"]);
+        let market = BinanceSpotOrderRequest {
+            quantity: Some(0.001.into()),
+            iceberg_qty: Some(0.0005.into()),
+            ..order("MARKET")
+        };
+        assert_eq!(
+            market.validate(),
+            vec!["MARKET order does not support icebergQty"]
+        );
+    }
 }
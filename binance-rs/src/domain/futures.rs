@@ -0,0 +1,523 @@
+use std::str::FromStr;
+
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::binance::{BinanceOrderSide, BinanceTimeInForce, F64String};
+
+/// Position of a USDⓈ-M futures symbol, from `/fapi/v2/positionRisk`.
+/// In hedge mode each symbol has a LONG and a SHORT position, BOTH otherwise.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceFuturesPosition {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    /// BOTH, LONG or SHORT
+    #[serde(rename = "positionSide")]
+    pub position_side: String,
+
+    /// Negative for a short position
+    #[serde(rename = "positionAmt")]
+    pub position_amt: Decimal,
+
+    #[serde(rename = "entryPrice")]
+    pub entry_price: Decimal,
+
+    #[serde(default, rename = "breakEvenPrice")]
+    pub break_even_price: Option<Decimal>,
+
+    #[serde(rename = "markPrice")]
+    pub mark_price: Decimal,
+
+    #[serde(rename = "unRealizedProfit")]
+    pub un_realized_profit: Decimal,
+
+    #[serde(rename = "liquidationPrice")]
+    pub liquidation_price: Decimal,
+
+    #[serde(rename = "leverage")]
+    pub leverage: F64String,
+
+    /// `cross` or `isolated`
+    #[serde(rename = "marginType")]
+    pub margin_type: String,
+
+    #[serde(rename = "isolatedMargin")]
+    pub isolated_margin: Decimal,
+
+    #[serde(default, rename = "notional")]
+    pub notional: Option<Decimal>,
+
+    #[serde(default, rename = "maxNotionalValue")]
+    pub max_notional_value: Option<Decimal>,
+
+    #[serde(default, rename = "updateTime", with = "ts_milliseconds_option")]
+    pub update_time: Option<DateTime<Utc>>,
+}
+
+/// Leverage of a symbol once changed, from `/fapi/v1/leverage`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceFuturesLeverage {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "leverage")]
+    pub leverage: u32,
+
+    /// Largest position allowed at this leverage
+    #[serde(rename = "maxNotionalValue")]
+    pub max_notional_value: F64String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceFuturesMarginType {
+    #[serde(rename = "ISOLATED")]
+    Isolated,
+
+    #[serde(rename = "CROSSED")]
+    Crossed,
+}
+
+impl BinanceFuturesMarginType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Isolated => "ISOLATED",
+            Self::Crossed => "CROSSED",
+        }
+    }
+}
+
+impl FromStr for BinanceFuturesMarginType {
+    type Err = String;
+
+    /// Case insensitive, `cross` is accepted for CROSSED
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "ISOLATED" => Ok(Self::Isolated),
+            "CROSS" | "CROSSED" => Ok(Self::Crossed),
+            _ => Err(format!("unknown margin type {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceFuturesOrderType {
+    #[serde(rename = "LIMIT")]
+    Limit,
+
+    #[serde(rename = "MARKET")]
+    Market,
+
+    #[serde(rename = "STOP")]
+    Stop,
+
+    #[serde(rename = "STOP_MARKET")]
+    StopMarket,
+
+    #[serde(rename = "TAKE_PROFIT")]
+    TakeProfit,
+
+    #[serde(rename = "TAKE_PROFIT_MARKET")]
+    TakeProfitMarket,
+
+    #[serde(rename = "TRAILING_STOP_MARKET")]
+    TrailingStopMarket,
+}
+
+impl BinanceFuturesOrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Limit => "LIMIT",
+            Self::Market => "MARKET",
+            Self::Stop => "STOP",
+            Self::StopMarket => "STOP_MARKET",
+            Self::TakeProfit => "TAKE_PROFIT",
+            Self::TakeProfitMarket => "TAKE_PROFIT_MARKET",
+            Self::TrailingStopMarket => "TRAILING_STOP_MARKET",
+        }
+    }
+
+    /// The order rests in the book at its `price` once placed or triggered
+    pub fn has_limit_price(&self) -> bool {
+        matches!(self, Self::Limit | Self::Stop | Self::TakeProfit)
+    }
+
+    /// The order waits for `stopPrice` to trigger
+    pub fn has_stop_price(&self) -> bool {
+        matches!(
+            self,
+            Self::Stop | Self::StopMarket | Self::TakeProfit | Self::TakeProfitMarket
+        )
+    }
+
+    /// The order can close the whole position instead of a quantity
+    pub fn can_close_position(&self) -> bool {
+        matches!(self, Self::StopMarket | Self::TakeProfitMarket)
+    }
+}
+
+impl FromStr for BinanceFuturesOrderType {
+    type Err = String;
+
+    /// Case insensitive, `-` can be used instead of `_`, e.g. `stop-market`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.to_uppercase().replace('-', "_");
+        [
+            Self::Limit,
+            Self::Market,
+            Self::Stop,
+            Self::StopMarket,
+            Self::TakeProfit,
+            Self::TakeProfitMarket,
+            Self::TrailingStopMarket,
+        ]
+        .into_iter()
+        .find(|order_type| order_type.as_str() == value)
+        .ok_or_else(|| format!("unknown futures order type {s}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinancePositionSide {
+    #[serde(rename = "BOTH")]
+    Both,
+
+    #[serde(rename = "LONG")]
+    Long,
+
+    #[serde(rename = "SHORT")]
+    Short,
+}
+
+impl FromStr for BinancePositionSide {
+    type Err = String;
+
+    /// Case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "BOTH" => Ok(Self::Both),
+            "LONG" => Ok(Self::Long),
+            "SHORT" => Ok(Self::Short),
+            _ => Err(format!("unknown position side {s}")),
+        }
+    }
+}
+
+/// Price triggering the `stopPrice` of conditional orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceWorkingType {
+    #[serde(rename = "MARK_PRICE")]
+    MarkPrice,
+
+    #[serde(rename = "CONTRACT_PRICE")]
+    ContractPrice,
+}
+
+/// New order of `/fapi/v1/order`
+///
+/// ```no_run
+/// use binance_rs::{
+///     domain::{
+///         binance::{BinanceOrderSide, F64String},
+///         futures::{BinanceFuturesOrderRequest, BinanceFuturesOrderType},
+///     },
+///     infrastructure::binance_repo::BinanceRepo,
+/// };
+/// # fn example(binance: &BinanceRepo) -> anyhow::Result<()> {
+/// let mut order = BinanceFuturesOrderRequest::new(
+///     "BTCUSDT".to_owned(),
+///     BinanceOrderSide::Sell,
+///     BinanceFuturesOrderType::StopMarket,
+/// );
+/// order.stop_price = Some(F64String::from(19000.0));
+/// order.close_position = Some(true);
+/// assert!(order.validate().is_empty());
+/// binance.futures().make_order(order)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct BinanceFuturesOrderRequest {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "side")]
+    pub side: BinanceOrderSide,
+
+    /// LONG or SHORT in hedge mode, BOTH by default
+    #[serde(rename = "positionSide")]
+    pub position_side: Option<BinancePositionSide>,
+
+    #[serde(rename = "type")]
+    pub order_type: BinanceFuturesOrderType,
+
+    #[serde(rename = "timeInForce")]
+    pub time_in_force: Option<BinanceTimeInForce>,
+
+    #[serde(rename = "quantity")]
+    pub quantity: Option<F64String>,
+
+    /// Only reduce the position, never open or increase one. One-way mode only.
+    #[serde(rename = "reduceOnly")]
+    pub reduce_only: Option<bool>,
+
+    #[serde(rename = "price")]
+    pub price: Option<F64String>,
+
+    #[serde(rename = "stopPrice")]
+    pub stop_price: Option<F64String>,
+
+    /// Close the whole position once triggered, STOP_MARKET and TAKE_PROFIT_MARKET only
+    #[serde(rename = "closePosition")]
+    pub close_position: Option<bool>,
+
+    /// Price activating a TRAILING_STOP_MARKET order, the current price by default
+    #[serde(rename = "activationPrice")]
+    pub activation_price: Option<F64String>,
+
+    /// Trailing distance of a TRAILING_STOP_MARKET order in percent, from 0.1 to 10
+    #[serde(rename = "callbackRate")]
+    pub callback_rate: Option<F64String>,
+
+    #[serde(rename = "workingType")]
+    pub working_type: Option<BinanceWorkingType>,
+
+    #[serde(rename = "newClientOrderId")]
+    pub new_client_order_id: Option<String>,
+}
+
+impl BinanceFuturesOrderRequest {
+    /// A new order of the given type with every optional field unset
+    pub fn new(
+        symbol: String,
+        side: BinanceOrderSide,
+        order_type: BinanceFuturesOrderType,
+    ) -> Self {
+        Self {
+            symbol,
+            side,
+            position_side: None,
+            order_type,
+            time_in_force: None,
+            quantity: None,
+            reduce_only: None,
+            price: None,
+            stop_price: None,
+            close_position: None,
+            activation_price: None,
+            callback_rate: None,
+            working_type: None,
+            new_client_order_id: None,
+        }
+    }
+
+    /// Check the fields required, or not allowed, by the order type, returning every problem found
+    ///
+    /// | type                 | required                                      |
+    /// |----------------------|-----------------------------------------------|
+    /// | LIMIT                | timeInForce, quantity, price                  |
+    /// | MARKET               | quantity                                      |
+    /// | STOP, TAKE_PROFIT    | quantity, price, stopPrice                    |
+    /// | STOP_MARKET, TAKE_PROFIT_MARKET | stopPrice, quantity or closePosition |
+    /// | TRAILING_STOP_MARKET | quantity, callbackRate                        |
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if let BinanceOrderSide::Unknown(side) = &self.side {
+            errors.push(format!("unknown side {side}"));
+        }
+        let positive = |name: &str, value: Option<F64String>, errors: &mut Vec<String>| {
+            if let Some(value) = value {
+                if value.value() <= 0.0 {
+                    errors.push(format!("{name} must be positive"));
+                }
+            }
+        };
+        positive("quantity", self.quantity, &mut errors);
+        positive("price", self.price, &mut errors);
+        positive("stopPrice", self.stop_price, &mut errors);
+        positive("activationPrice", self.activation_price, &mut errors);
+
+        let order_type = self.order_type.as_str();
+        let check = |name: &str, present: bool, required: bool, errors: &mut Vec<String>| {
+            if required && !present {
+                errors.push(format!("{order_type} order requires {name}"));
+            }
+            if !required && present {
+                errors.push(format!("{order_type} order does not support {name}"));
+            }
+        };
+        let close_position = self.close_position == Some(true);
+        if close_position {
+            if !self.order_type.can_close_position() {
+                errors.push(format!("{order_type} order does not support closePosition"));
+            }
+            if self.quantity.is_some() {
+                errors.push("closePosition cannot be sent with quantity".to_owned());
+            }
+            if self.reduce_only.is_some() {
+                errors.push("closePosition cannot be sent with reduceOnly".to_owned());
+            }
+        } else {
+            check("quantity", self.quantity.is_some(), true, &mut errors);
+        }
+        check(
+            "price",
+            self.price.is_some(),
+            self.order_type.has_limit_price(),
+            &mut errors,
+        );
+        if self.order_type == BinanceFuturesOrderType::Limit {
+            check(
+                "timeInForce",
+                self.time_in_force.is_some(),
+                true,
+                &mut errors,
+            );
+        }
+        check(
+            "stopPrice",
+            self.stop_price.is_some(),
+            self.order_type.has_stop_price(),
+            &mut errors,
+        );
+        let trailing = self.order_type == BinanceFuturesOrderType::TrailingStopMarket;
+        check(
+            "callbackRate",
+            self.callback_rate.is_some(),
+            trailing,
+            &mut errors,
+        );
+        if !trailing {
+            check(
+                "activationPrice",
+                self.activation_price.is_some(),
+                false,
+                &mut errors,
+            );
+        }
+        if let Some(rate) = self.callback_rate {
+            if !(0.1..=10.0).contains(&rate.value()) {
+                errors.push("callbackRate must be between 0.1 and 10".to_owned());
+            }
+        }
+        if self.reduce_only.is_some()
+            && matches!(
+                self.position_side,
+                Some(BinancePositionSide::Long | BinancePositionSide::Short)
+            )
+        {
+            errors.push("reduceOnly cannot be sent in hedge mode".to_owned());
+        }
+        errors
+    }
+}
+
+/// Order of the futures account, from `/fapi/v1/order`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceFuturesOrder {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "orderId")]
+    pub order_id: i64,
+
+    #[serde(rename = "clientOrderId")]
+    pub client_order_id: String,
+
+    #[serde(rename = "side")]
+    pub side: BinanceOrderSide,
+
+    #[serde(rename = "positionSide")]
+    pub position_side: String,
+
+    #[serde(rename = "type")]
+    pub order_type: String,
+
+    #[serde(rename = "status")]
+    pub status: String,
+
+    #[serde(default, rename = "price")]
+    pub price: Option<Decimal>,
+
+    #[serde(default, rename = "avgPrice")]
+    pub avg_price: Option<Decimal>,
+
+    #[serde(default, rename = "origQty")]
+    pub orig_qty: Option<Decimal>,
+
+    #[serde(default, rename = "executedQty")]
+    pub executed_qty: Option<Decimal>,
+
+    #[serde(default, rename = "cumQuote")]
+    pub cum_quote: Option<Decimal>,
+
+    #[serde(default, rename = "stopPrice")]
+    pub stop_price: Option<Decimal>,
+
+    #[serde(default, rename = "timeInForce")]
+    pub time_in_force: Option<String>,
+
+    #[serde(default, rename = "reduceOnly")]
+    pub reduce_only: bool,
+
+    #[serde(default, rename = "closePosition")]
+    pub close_position: bool,
+
+    #[serde(default, rename = "workingType")]
+    pub working_type: Option<String>,
+
+    #[serde(default, rename = "updateTime", with = "ts_milliseconds_option")]
+    pub update_time: Option<DateTime<Utc>>,
+}
+
+/// Funding rate paid between longs and shorts, from `/fapi/v1/fundingRate`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceFundingRate {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    /// Paid by the longs to the shorts when positive
+    #[serde(rename = "fundingRate")]
+    pub funding_rate: Decimal,
+
+    #[serde(rename = "fundingTime", with = "ts_milliseconds")]
+    pub funding_time: DateTime<Utc>,
+
+    #[serde(default, rename = "markPrice")]
+    pub mark_price: Option<Decimal>,
+}
+
+/// Change of the futures wallet, from `/fapi/v1/income`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceFuturesIncome {
+    /// Empty for the transfers
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    /// TRANSFER, REALIZED_PNL, FUNDING_FEE, COMMISSION, INSURANCE_CLEAR, ...
+    #[serde(rename = "incomeType")]
+    pub income_type: String,
+
+    /// Negative when paid
+    #[serde(rename = "income")]
+    pub income: Decimal,
+
+    #[serde(rename = "asset")]
+    pub asset: String,
+
+    #[serde(default, rename = "info")]
+    pub info: String,
+
+    #[serde(rename = "time", with = "ts_milliseconds")]
+    pub time: DateTime<Utc>,
+
+    #[serde(rename = "tranId")]
+    pub tran_id: i64,
+
+    #[serde(default, rename = "tradeId")]
+    pub trade_id: String,
+}
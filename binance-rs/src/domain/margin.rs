@@ -0,0 +1,370 @@
+use std::str::FromStr;
+
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::domain::binance::{BinanceSpotOrder, BinanceSpotOrderRequest, F64String};
+
+/// Cross margin account, from `/sapi/v1/margin/account`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMarginAccount {
+    #[serde(rename = "borrowEnabled")]
+    pub borrow_enabled: bool,
+
+    #[serde(rename = "tradeEnabled")]
+    pub trade_enabled: bool,
+
+    #[serde(rename = "transferEnabled")]
+    pub transfer_enabled: bool,
+
+    /// Total assets over total liabilities, liquidation happens at 1.1
+    #[serde(rename = "marginLevel")]
+    pub margin_level: F64String,
+
+    #[serde(rename = "totalAssetOfBtc")]
+    pub total_asset_of_btc: F64String,
+
+    #[serde(rename = "totalLiabilityOfBtc")]
+    pub total_liability_of_btc: F64String,
+
+    #[serde(rename = "totalNetAssetOfBtc")]
+    pub total_net_asset_of_btc: F64String,
+
+    #[serde(rename = "userAssets")]
+    pub user_assets: Vec<MarginAsset>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarginAsset {
+    #[serde(rename = "asset")]
+    pub asset: String,
+
+    #[serde(rename = "free")]
+    pub free: F64String,
+
+    #[serde(rename = "locked")]
+    pub locked: F64String,
+
+    #[serde(rename = "borrowed")]
+    pub borrowed: F64String,
+
+    #[serde(rename = "interest")]
+    pub interest: F64String,
+
+    /// free + locked - borrowed - interest
+    #[serde(rename = "netAsset")]
+    pub net_asset: F64String,
+}
+
+/// Isolated margin pairs, from `/sapi/v1/margin/isolated/account`.
+/// The totals are only sent when every pair is requested.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceIsolatedMarginAccount {
+    #[serde(rename = "assets")]
+    pub assets: Vec<IsolatedMarginPair>,
+
+    #[serde(default, rename = "totalAssetOfBtc")]
+    pub total_asset_of_btc: Option<F64String>,
+
+    #[serde(default, rename = "totalLiabilityOfBtc")]
+    pub total_liability_of_btc: Option<F64String>,
+
+    #[serde(default, rename = "totalNetAssetOfBtc")]
+    pub total_net_asset_of_btc: Option<F64String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IsolatedMarginPair {
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+
+    #[serde(rename = "baseAsset")]
+    pub base_asset: IsolatedMarginAsset,
+
+    #[serde(rename = "quoteAsset")]
+    pub quote_asset: IsolatedMarginAsset,
+
+    #[serde(rename = "isolatedCreated")]
+    pub isolated_created: bool,
+
+    #[serde(default, rename = "enabled")]
+    pub enabled: bool,
+
+    #[serde(rename = "tradeEnabled")]
+    pub trade_enabled: bool,
+
+    #[serde(rename = "marginLevel")]
+    pub margin_level: F64String,
+
+    /// EXCESSIVE, NORMAL, MARGIN_CALL, PRE_LIQUIDATION or FORCE_LIQUIDATION
+    #[serde(rename = "marginLevelStatus")]
+    pub margin_level_status: String,
+
+    #[serde(rename = "marginRatio")]
+    pub margin_ratio: F64String,
+
+    #[serde(rename = "indexPrice")]
+    pub index_price: F64String,
+
+    #[serde(rename = "liquidatePrice")]
+    pub liquidate_price: F64String,
+
+    #[serde(rename = "liquidateRate")]
+    pub liquidate_rate: F64String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IsolatedMarginAsset {
+    #[serde(rename = "asset")]
+    pub asset: String,
+
+    #[serde(rename = "borrowEnabled")]
+    pub borrow_enabled: bool,
+
+    #[serde(rename = "repayEnabled")]
+    pub repay_enabled: bool,
+
+    #[serde(rename = "free")]
+    pub free: F64String,
+
+    #[serde(rename = "locked")]
+    pub locked: F64String,
+
+    #[serde(rename = "borrowed")]
+    pub borrowed: F64String,
+
+    #[serde(rename = "interest")]
+    pub interest: F64String,
+
+    #[serde(rename = "netAsset")]
+    pub net_asset: F64String,
+
+    #[serde(rename = "netAssetOfBtc")]
+    pub net_asset_of_btc: F64String,
+
+    #[serde(rename = "totalAsset")]
+    pub total_asset: F64String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceBorrowRepayType {
+    #[serde(rename = "BORROW")]
+    Borrow,
+
+    #[serde(rename = "REPAY")]
+    Repay,
+}
+
+/// Borrow or repay an asset of the cross margin account, or of the isolated pair `symbol`
+#[derive(Debug, Clone, Serialize)]
+pub struct BinanceBorrowRepayRequest {
+    #[serde(rename = "asset")]
+    pub asset: String,
+
+    #[serde(rename = "isIsolated", serialize_with = "upper_bool")]
+    pub is_isolated: bool,
+
+    #[serde(rename = "symbol")]
+    pub symbol: Option<String>,
+
+    #[serde(rename = "amount")]
+    pub amount: F64String,
+
+    #[serde(rename = "type")]
+    pub kind: BinanceBorrowRepayType,
+}
+
+impl BinanceBorrowRepayRequest {
+    /// Isolated when the symbol of the pair is given
+    pub fn new(
+        kind: BinanceBorrowRepayType,
+        asset: String,
+        amount: f64,
+        isolated_symbol: Option<String>,
+    ) -> Self {
+        Self {
+            asset,
+            is_isolated: isolated_symbol.is_some(),
+            symbol: isolated_symbol,
+            amount: F64String::from(amount),
+            kind,
+        }
+    }
+}
+
+/// Id of a borrow or repay, from `/sapi/v1/margin/borrow-repay`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMarginTransaction {
+    #[serde(rename = "tranId")]
+    pub tran_id: i64,
+}
+
+/// How a margin order borrows the missing assets and repays the debt with what it gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinanceSideEffectType {
+    #[serde(rename = "NO_SIDE_EFFECT")]
+    NoSideEffect,
+
+    /// Borrow what the order needs
+    #[serde(rename = "MARGIN_BUY")]
+    MarginBuy,
+
+    /// Repay the debt with what the order gets
+    #[serde(rename = "AUTO_REPAY")]
+    AutoRepay,
+
+    #[serde(rename = "AUTO_BORROW_REPAY")]
+    AutoBorrowRepay,
+}
+
+impl FromStr for BinanceSideEffectType {
+    type Err = String;
+
+    /// Case insensitive, `-` can be used instead of `_`, e.g. `margin-buy`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().replace('-', "_").as_str() {
+            "NO_SIDE_EFFECT" => Ok(Self::NoSideEffect),
+            "MARGIN_BUY" => Ok(Self::MarginBuy),
+            "AUTO_REPAY" => Ok(Self::AutoRepay),
+            "AUTO_BORROW_REPAY" => Ok(Self::AutoBorrowRepay),
+            _ => Err(format!("unknown side effect type {s}")),
+        }
+    }
+}
+
+/// A spot order sent to the cross margin account, or to the isolated pair of its symbol
+///
+/// ```no_run
+/// use binance_rs::{
+///     domain::{
+///         binance::BinanceSpotOrderRequest,
+///         margin::{BinanceMarginOrderRequest, BinanceSideEffectType},
+///     },
+///     infrastructure::binance_repo::BinanceRepo,
+/// };
+/// # fn example(binance: &BinanceRepo, spot_order: BinanceSpotOrderRequest) -> anyhow::Result<()> {
+/// let mut order = BinanceMarginOrderRequest::new(spot_order);
+/// order.side_effect_type = Some(BinanceSideEffectType::MarginBuy);
+/// binance.make_margin_order(order)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct BinanceMarginOrderRequest {
+    #[serde(flatten)]
+    pub order: BinanceSpotOrderRequest,
+
+    #[serde(rename = "isIsolated", serialize_with = "upper_bool")]
+    pub is_isolated: bool,
+
+    #[serde(rename = "sideEffectType")]
+    pub side_effect_type: Option<BinanceSideEffectType>,
+
+    /// Repay the debt of a MARGIN_BUY or AUTO_BORROW_REPAY order once it is canceled
+    #[serde(rename = "autoRepayAtCancel")]
+    pub auto_repay_at_cancel: Option<bool>,
+}
+
+impl BinanceMarginOrderRequest {
+    /// A cross margin order without side effect
+    pub fn new(order: BinanceSpotOrderRequest) -> Self {
+        Self {
+            order,
+            is_isolated: false,
+            side_effect_type: None,
+            auto_repay_at_cancel: None,
+        }
+    }
+
+    /// The problems of the spot order, plus the fields margin orders do not support
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = self.order.validate();
+        if self.order.trailing_delta.is_some() {
+            errors.push("margin orders do not support trailingDelta".to_owned());
+        }
+        if self.auto_repay_at_cancel.is_some()
+            && !matches!(
+                self.side_effect_type,
+                Some(BinanceSideEffectType::MarginBuy | BinanceSideEffectType::AutoBorrowRepay)
+            )
+        {
+            errors.push(
+                "autoRepayAtCancel requires sideEffectType MARGIN_BUY or AUTO_BORROW_REPAY"
+                    .to_owned(),
+            );
+        }
+        errors
+    }
+}
+
+/// Order of the margin account, the spot fields and what was borrowed for it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMarginOrder {
+    #[serde(flatten)]
+    pub order: BinanceSpotOrder,
+
+    #[serde(default, rename = "isIsolated")]
+    pub is_isolated: Option<bool>,
+
+    #[serde(default, rename = "marginBuyBorrowAmount")]
+    pub margin_buy_borrow_amount: Option<Decimal>,
+
+    #[serde(default, rename = "marginBuyBorrowAsset")]
+    pub margin_buy_borrow_asset: Option<String>,
+}
+
+/// Page of `/sapi/v1/margin/interestHistory`, the most recent first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMarginInterestHistory {
+    #[serde(rename = "rows")]
+    pub rows: Vec<BinanceMarginInterest>,
+
+    #[serde(rename = "total")]
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMarginInterest {
+    #[serde(rename = "txId")]
+    pub tx_id: i64,
+
+    #[serde(rename = "interestAccuredTime", with = "ts_milliseconds")]
+    pub interest_accured_time: DateTime<Utc>,
+
+    #[serde(rename = "asset")]
+    pub asset: String,
+
+    #[serde(rename = "principal")]
+    pub principal: Decimal,
+
+    #[serde(rename = "interest")]
+    pub interest: Decimal,
+
+    /// Daily rate
+    #[serde(rename = "interestRate")]
+    pub interest_rate: Decimal,
+
+    /// PERIODIC, ON_BORROW, PERIODIC_CONVERTED, ON_BORROW_CONVERTED or PORTFOLIO
+    #[serde(rename = "type")]
+    pub interest_type: String,
+
+    #[serde(default, rename = "isolatedSymbol")]
+    pub isolated_symbol: Option<String>,
+}
+
+/// How much of an asset can still be borrowed, from `/sapi/v1/margin/maxBorrowable`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceMaxBorrowable {
+    #[serde(rename = "amount")]
+    pub amount: F64String,
+
+    /// Limit of the VIP level of the account
+    #[serde(rename = "borrowLimit")]
+    pub borrow_limit: F64String,
+}
+
+/// Binance expects `isIsolated` as `TRUE` or `FALSE`
+fn upper_bool<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if *value { "TRUE" } else { "FALSE" })
+}
@@ -1 +1,7 @@
+pub mod alert;
 pub mod binance;
+pub mod futures;
+pub mod margin;
+pub mod risk;
+pub mod secret;
+pub mod trigger;
@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, path::PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::binance::BinanceOrderSide;
+
+/// Limits checked before any order is sent. Every limit is optional.
+///
+/// ```toml
+/// [risk]
+/// max_notional = 1000.0
+/// max_position = { BTC = 0.5, ETH = 5.0 }
+/// daily_loss_limit = 200.0
+/// valuation_asset = "USDT"
+/// allowed_symbols = ["BTCUSDT", "ETHUSDT"]
+/// max_orders_per_minute = 10
+/// max_price_deviation_bps = 100.0
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RiskLimits {
+    /// Largest value of an order, in the quote asset of its symbol
+    pub max_notional: Option<f64>,
+
+    /// Largest balance of an asset a BUY may lead to
+    #[serde(default)]
+    pub max_position: BTreeMap<String, f64>,
+
+    /// Stop trading for the rest of the UTC day once the account value fell this much,
+    /// in the `valuation_asset`. The loss counts from the value at the first accepted
+    /// order of the UTC day, not at UTC midnight.
+    /// Deposits and withdrawals move the account value too.
+    pub daily_loss_limit: Option<f64>,
+
+    /// Asset the account value is measured in, USDT when not set
+    pub valuation_asset: Option<String>,
+
+    pub allowed_symbols: Option<Vec<String>>,
+
+    pub max_orders_per_minute: Option<u32>,
+
+    /// Largest distance of the order price from the mid price, in basis points
+    pub max_price_deviation_bps: Option<f64>,
+
+    /// File keeping the orders of the last minute and the account value at the start
+    /// of the day, defaults to `<config dir>/binance-rs/risk-<profile>.toml`
+    pub state: Option<PathBuf>,
+}
+
+/// What is known of an order and the account when checking the [RiskLimits].
+/// Only the facts needed by the configured limits are filled.
+#[derive(Debug, Default)]
+pub struct RiskFacts {
+    pub symbol: String,
+    pub side: Option<BinanceOrderSide>,
+    /// Limit or stop price of the order
+    pub order_price: Option<f64>,
+    pub mid_price: Option<f64>,
+    pub quantity: Option<f64>,
+    pub notional: Option<f64>,
+    pub base_asset: Option<String>,
+    /// Balance of the base asset before the order
+    pub position: Option<f64>,
+    /// Orders sent during the last minute, this one excluded
+    pub recent_orders: usize,
+    /// Fall of the account value since the first accepted order of the UTC day,
+    /// zero before it
+    pub daily_loss: Option<f64>,
+}
+
+impl RiskLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_notional.is_none()
+            && self.max_position.is_empty()
+            && self.daily_loss_limit.is_none()
+            && self.allowed_symbols.is_none()
+            && self.max_orders_per_minute.is_none()
+            && self.max_price_deviation_bps.is_none()
+    }
+
+    pub fn valuation_asset(&self) -> &str {
+        self.valuation_asset.as_deref().unwrap_or("USDT")
+    }
+
+    /// Reasons the order breaks the limits, empty when it may be sent
+    pub fn check(&self, facts: &RiskFacts) -> Vec<String> {
+        let mut reasons = vec![];
+        if let Some(allowed) = &self.allowed_symbols {
+            if !allowed
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(&facts.symbol))
+            {
+                reasons.push(format!("{} is not an allowed symbol", facts.symbol));
+            }
+        }
+        if let (Some(max), Some(notional)) = (self.max_notional, facts.notional) {
+            if notional > max {
+                reasons.push(format!("notional {notional} is above max_notional {max}"));
+            }
+        }
+        if let (Some(max_bps), Some(price), Some(mid)) = (
+            self.max_price_deviation_bps,
+            facts.order_price,
+            facts.mid_price,
+        ) {
+            let deviation = (price - mid).abs() / mid * 10_000.0;
+            if deviation > max_bps {
+                reasons.push(format!(
+                    "price {price} is {deviation:.1} bps from the mid price {mid}, above max_price_deviation_bps {max_bps}"
+                ));
+            }
+        }
+        if let (Some(BinanceOrderSide::Buy), Some(asset), Some(quantity)) =
+            (&facts.side, &facts.base_asset, facts.quantity)
+        {
+            if let Some(max) = self.max_position.get(asset) {
+                let position = facts.position.unwrap_or_default() + quantity;
+                if position > *max {
+                    reasons.push(format!(
+                        "{asset} position would be {position}, above max_position {max}"
+                    ));
+                }
+            }
+        }
+        if let Some(max) = self.max_orders_per_minute {
+            if facts.recent_orders >= max as usize {
+                reasons.push(format!(
+                    "{} orders sent in the last minute, max_orders_per_minute is {}",
+                    facts.recent_orders, max
+                ));
+            }
+        }
+        if let (Some(max), Some(loss)) = (self.daily_loss_limit, facts.daily_loss) {
+            if loss >= max {
+                reasons.push(format!(
+                    "daily loss {} {} reached daily_loss_limit {}",
+                    loss,
+                    self.valuation_asset(),
+                    max
+                ));
+            }
+        }
+        reasons
+    }
+}
+
+/// Orders sent and account value at the start of the day, kept between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RiskState {
+    pub day: Option<NaiveDate>,
+    /// Account value in the valuation asset at the first accepted order of the day
+    pub start_value: Option<f64>,
+    #[serde(default)]
+    pub orders: Vec<DateTime<Utc>>,
+}
+
+/// An order refused by the [RiskLimits]
+#[derive(Debug)]
+pub struct RiskRejection {
+    pub symbol: String,
+    pub reasons: Vec<String>,
+}
+
+impl Error for RiskRejection {}
+
+impl Display for RiskRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} order rejected by the risk limits: {}",
+            self.symbol,
+            self.reasons.join("; ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(config: &str) -> RiskLimits {
+        toml::from_str(config).unwrap()
+    }
+
+    fn buy(notional: f64) -> RiskFacts {
+        RiskFacts {
+            symbol: "BTCUSDT".to_owned(),
+            side: Some(BinanceOrderSide::Buy),
+            order_price: Some(20000.0),
+            mid_price: Some(20000.0),
+            quantity: Some(notional / 20000.0),
+            notional: Some(notional),
+            base_asset: Some("BTC".to_owned()),
+            position: Some(1.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_limit_accepts_anything() {
+        let limits = limits("");
+        assert!(limits.is_empty());
+        assert!(limits.check(&buy(1e9)).is_empty());
+    }
+
+    #[test]
+    fn max_notional() {
+        let limits = limits("max_notional = 1000.0");
+        assert!(limits.check(&buy(1000.0)).is_empty());
+        assert_eq!(
+            limits.check(&buy(1000.5)),
+            vec!["notional 1000.5 is above max_notional 1000"]
+        );
+    }
+
+    #[test]
+    fn max_position_of_buys() {
+        let limits = limits("max_position = { BTC = 1.5 }");
+        // 1.0 held plus 0.5 bought
+        assert!(limits.check(&buy(10000.0)).is_empty());
+        assert_eq!(
+            limits.check(&buy(12000.0)),
+            vec!["BTC position would be 1.6, above max_position 1.5"]
+        );
+        // Sells only reduce the position
+        let sell = RiskFacts {
+            side: Some(BinanceOrderSide::Sell),
+            ..buy(12000.0)
+        };
+        assert!(limits.check(&sell).is_empty());
+        // Assets without a limit
+        let eth = RiskFacts {
+            base_asset: Some("ETH".to_owned()),
+            ..buy(12000.0)
+        };
+        assert!(limits.check(&eth).is_empty());
+    }
+
+    #[test]
+    fn daily_loss_limit() {
+        let limits = limits("daily_loss_limit = 200.0");
+        let facts = |loss| RiskFacts {
+            daily_loss: Some(loss),
+            ..buy(100.0)
+        };
+        assert!(limits.check(&facts(199.9)).is_empty());
+        assert!(limits.check(&facts(-500.0)).is_empty());
+        assert_eq!(
+            limits.check(&facts(200.0)),
+            vec!["daily loss 200 USDT reached daily_loss_limit 200"]
+        );
+    }
+
+    #[test]
+    fn allowed_symbols_ignore_case() {
+        let limits = limits("allowed_symbols = [\"btcusdt\", \"ETHUSDT\"]");
+        assert!(limits.check(&buy(100.0)).is_empty());
+        let facts = RiskFacts {
+            symbol: "BNBUSDT".to_owned(),
+            ..buy(100.0)
+        };
+        assert_eq!(
+            limits.check(&facts),
+            vec!["BNBUSDT is not an allowed symbol"]
+        );
+    }
+
+    #[test]
+    fn max_orders_per_minute_excludes_the_order() {
+        let limits = limits("max_orders_per_minute = 3");
+        let facts = |recent_orders| RiskFacts {
+            recent_orders,
+            ..buy(100.0)
+        };
+        assert!(limits.check(&facts(2)).is_empty());
+        assert_eq!(
+            limits.check(&facts(3)),
+            vec!["3 orders sent in the last minute, max_orders_per_minute is 3"]
+        );
+    }
+
+    #[test]
+    fn max_price_deviation_from_the_mid_price() {
+        let limits = limits("max_price_deviation_bps = 100.0");
+        let facts = |order_price| RiskFacts {
+            order_price: Some(order_price),
+            ..buy(100.0)
+        };
+        assert!(limits.check(&facts(20200.0)).is_empty());
+        assert!(limits.check(&facts(19800.0)).is_empty());
+        assert_eq!(
+            limits.check(&facts(19790.0)),
+            vec![
+                "price 19790 is 105.0 bps from the mid price 20000, above max_price_deviation_bps 100"
+            ]
+        );
+        // Market orders have no price to compare
+        let market = RiskFacts {
+            order_price: None,
+            ..buy(100.0)
+        };
+        assert!(limits.check(&market).is_empty());
+    }
+
+    #[test]
+    fn every_broken_limit_is_reported() {
+        let limits = limits("max_notional = 10.0\nallowed_symbols = [\"ETHUSDT\"]");
+        assert_eq!(limits.check(&buy(100.0)).len(), 2);
+    }
+}
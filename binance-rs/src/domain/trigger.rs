@@ -0,0 +1,313 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_config::DEFAULT_PROFILE,
+    domain::binance::{
+        BinanceOrderSide, BinanceOrderType, BinanceSpotOrderRequest, BinanceTimeInForce, F64String,
+    },
+};
+
+/// A stop loss, take profit or trailing stop emulated client side.
+/// The order is placed by the emulator once the condition is met by the last trade price.
+///
+/// ```toml
+/// [[triggers]]
+/// id = "t1675659775601-3f2a"
+/// profile = "default"
+/// symbol = "BTCUSDT"
+/// side = "SELL"
+/// quantity = 0.01
+/// created_at = "2023-02-06T05:02:55.601Z"
+///
+/// [triggers.condition]
+/// type = "trailing_stop"
+/// trailing_delta = 200
+/// extreme = 23500.0
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub id: String,
+    /// Profile that armed the trigger, only `bot run` of this profile places its order.
+    /// Triggers stored without a profile belong to the default profile.
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    pub symbol: String,
+    pub side: BinanceOrderSide,
+    pub quantity: f64,
+    /// Place a LIMIT GTC order at this price instead of a MARKET order
+    pub limit_price: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub condition: TriggerCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerCondition {
+    /// SELL when the price falls to the stop price, BUY when it rises to it
+    StopLoss { stop_price: f64 },
+
+    /// SELL when the price rises to the stop price, BUY when it falls to it
+    TakeProfit { stop_price: f64 },
+
+    /// SELL when the price falls `trailing_delta` basis points below the highest price
+    /// since armed, BUY when it rises as much above the lowest price
+    TrailingStop {
+        trailing_delta: u32,
+        /// Highest (SELL) or lowest (BUY) price seen, kept across restarts
+        extreme: Option<f64>,
+    },
+}
+
+impl TriggerCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::StopLoss { .. } => "stop_loss",
+            Self::TakeProfit { .. } => "take_profit",
+            Self::TrailingStop { .. } => "trailing_stop",
+        }
+    }
+}
+
+impl Trigger {
+    pub fn new(
+        profile: String,
+        symbol: String,
+        side: BinanceOrderSide,
+        quantity: f64,
+        condition: TriggerCondition,
+    ) -> Self {
+        static BASE: OnceLock<u64> = OnceLock::new();
+        static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+        let created_at = Utc::now();
+        // Counted from a random start so the triggers armed in the same millisecond,
+        // in this process or another one, don't share the id disarming them
+        let base = *BASE.get_or_init(|| RandomState::new().hash_one(std::process::id()));
+        let suffix = base.wrapping_add(SEQUENCE.fetch_add(1, Ordering::Relaxed)) & 0xffff;
+        Self {
+            id: format!("t{}-{suffix:04x}", created_at.timestamp_millis()),
+            profile,
+            symbol,
+            side,
+            quantity,
+            limit_price: None,
+            created_at,
+            condition,
+        }
+    }
+
+    /// Update the condition with the last trade price, returning true when the order must be placed
+    pub fn update(&mut self, price: f64) -> bool {
+        let sell = self.side == BinanceOrderSide::Sell;
+        match &mut self.condition {
+            TriggerCondition::StopLoss { stop_price } => match sell {
+                true => price <= *stop_price,
+                false => price >= *stop_price,
+            },
+            TriggerCondition::TakeProfit { stop_price } => match sell {
+                true => price >= *stop_price,
+                false => price <= *stop_price,
+            },
+            TriggerCondition::TrailingStop {
+                trailing_delta,
+                extreme,
+            } => {
+                let delta = *trailing_delta as f64 / 10_000.0;
+                let best = match (*extreme, sell) {
+                    (Some(extreme), true) => extreme.max(price),
+                    (Some(extreme), false) => extreme.min(price),
+                    (None, _) => price,
+                };
+                *extreme = Some(best);
+                match sell {
+                    true => price <= best * (1.0 - delta),
+                    false => price >= best * (1.0 + delta),
+                }
+            }
+        }
+    }
+
+    /// The order placed when the trigger fires, tagged with the trigger id as client order id
+    pub fn order(&self) -> BinanceSpotOrderRequest {
+        let order_type = match self.limit_price {
+            Some(_) => BinanceOrderType::Limit,
+            None => BinanceOrderType::Market,
+        };
+        let mut order =
+            BinanceSpotOrderRequest::new(self.symbol.to_owned(), self.side.clone(), order_type);
+        order.quantity = Some(F64String::from(self.quantity));
+        order.price = self.limit_price.map(F64String::from);
+        order.time_in_force = self.limit_price.map(|_| BinanceTimeInForce::Gtc);
+        order.new_client_order_id = Some(format!("emu-{}", self.id));
+        order
+    }
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(side: BinanceOrderSide, condition: TriggerCondition) -> Trigger {
+        Trigger::new(
+            DEFAULT_PROFILE.to_owned(),
+            "BTCUSDT".to_owned(),
+            side,
+            0.01,
+            condition,
+        )
+    }
+
+    /// Prices at which the trigger fires, feeding them in order
+    fn fired(trigger: &mut Trigger, prices: &[f64]) -> Vec<bool> {
+        prices.iter().map(|price| trigger.update(*price)).collect()
+    }
+
+    #[test]
+    fn stop_loss_fires_against_the_position() {
+        let condition = TriggerCondition::StopLoss {
+            stop_price: 20000.0,
+        };
+        let mut sell = trigger(BinanceOrderSide::Sell, condition.clone());
+        assert_eq!(
+            fired(&mut sell, &[20100.0, 20000.01, 20000.0, 19900.0]),
+            vec![false, false, true, true]
+        );
+        let mut buy = trigger(BinanceOrderSide::Buy, condition);
+        assert_eq!(
+            fired(&mut buy, &[19900.0, 19999.99, 20000.0, 20100.0]),
+            vec![false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn take_profit_fires_with_the_position() {
+        let condition = TriggerCondition::TakeProfit {
+            stop_price: 20000.0,
+        };
+        let mut sell = trigger(BinanceOrderSide::Sell, condition.clone());
+        assert_eq!(
+            fired(&mut sell, &[19900.0, 19999.99, 20000.0, 20100.0]),
+            vec![false, false, true, true]
+        );
+        let mut buy = trigger(BinanceOrderSide::Buy, condition);
+        assert_eq!(
+            fired(&mut buy, &[20100.0, 20000.01, 20000.0, 19900.0]),
+            vec![false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn trailing_sell_follows_the_highest_price() {
+        let mut sell = trigger(
+            BinanceOrderSide::Sell,
+            TriggerCondition::TrailingStop {
+                trailing_delta: 100,
+                extreme: None,
+            },
+        );
+        // Armed at 20000, rises to 21000, then 1% below it is 20790
+        assert_eq!(
+            fired(
+                &mut sell,
+                &[20000.0, 20500.0, 21000.0, 20800.0, 20900.0, 20790.0]
+            ),
+            vec![false, false, false, false, false, true]
+        );
+        assert_eq!(
+            sell.condition,
+            TriggerCondition::TrailingStop {
+                trailing_delta: 100,
+                extreme: Some(21000.0),
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_buy_follows_the_lowest_price() {
+        let mut buy = trigger(
+            BinanceOrderSide::Buy,
+            TriggerCondition::TrailingStop {
+                trailing_delta: 200,
+                extreme: None,
+            },
+        );
+        // Falls to 19000, then 2% above it is 19380
+        assert_eq!(
+            fired(&mut buy, &[20000.0, 19000.0, 19300.0, 19100.0, 19380.0]),
+            vec![false, false, false, false, true]
+        );
+        assert_eq!(
+            buy.condition,
+            TriggerCondition::TrailingStop {
+                trailing_delta: 200,
+                extreme: Some(19000.0),
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_extreme_only_ratchets_and_survives_restarts() {
+        let mut sell = trigger(
+            BinanceOrderSide::Sell,
+            TriggerCondition::TrailingStop {
+                trailing_delta: 100,
+                extreme: Some(21000.0),
+            },
+        );
+        // A lower price than the stored extreme neither moves it nor fires above 20790
+        assert!(!sell.update(20800.0));
+        assert!(matches!(
+            sell.condition,
+            TriggerCondition::TrailingStop { extreme: Some(extreme), .. } if extreme == 21000.0
+        ));
+        assert!(sell.update(20790.0));
+    }
+
+    #[test]
+    fn ids_are_unique_within_a_millisecond() {
+        let ids = (0..100)
+            .map(|_| {
+                trigger(
+                    BinanceOrderSide::Sell,
+                    TriggerCondition::StopLoss { stop_price: 1.0 },
+                )
+                .id
+            })
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(ids.len(), 100);
+    }
+
+    #[test]
+    fn order_is_tagged_with_the_trigger_id() {
+        let mut trigger = trigger(
+            BinanceOrderSide::Sell,
+            TriggerCondition::StopLoss { stop_price: 1.0 },
+        );
+        let order = trigger.order();
+        assert_eq!(order.order_type, BinanceOrderType::Market);
+        assert!(order.price.is_none());
+        assert_eq!(
+            order.new_client_order_id,
+            Some(format!("emu-{}", trigger.id))
+        );
+
+        trigger.limit_price = Some(19000.0);
+        let order = trigger.order();
+        assert_eq!(order.order_type, BinanceOrderType::Limit);
+        assert_eq!(order.price.map(|price| price.value()), Some(19000.0));
+        assert_eq!(order.time_in_force, Some(BinanceTimeInForce::Gtc));
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+
+use crate::{
+    domain::futures::{
+        BinanceFundingRate, BinanceFuturesIncome, BinanceFuturesLeverage, BinanceFuturesMarginType,
+        BinanceFuturesOrder, BinanceFuturesOrderRequest, BinanceFuturesPosition,
+    },
+    infrastructure::{binance_repo::BinanceRepo, reqwest_facade::ReqwestFacade},
+};
+
+/// USDⓈ-M futures endpoints of the `futures_endpoint` of the profile, sent with the
+/// client, transport and signer of the [BinanceRepo]
+///
+/// ```no_run
+/// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+/// let positions = binance.futures().get_positions(Some("BTCUSDT"))?;
+/// binance.futures().change_leverage("BTCUSDT", 3)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BinanceFuturesRepo<'a> {
+    binance: &'a BinanceRepo,
+}
+
+impl<'a> BinanceFuturesRepo<'a> {
+    pub fn new(binance: &'a BinanceRepo) -> Self {
+        Self { binance }
+    }
+
+    fn make_url(&self, path: &str) -> String {
+        self.binance.cfg.futures_endpoint.to_owned() + path
+    }
+
+    /// Get the positions of the symbol, or of every symbol, the empty ones included
+    pub fn get_positions(&self, symbol: Option<&str>) -> Result<Vec<BinanceFuturesPosition>> {
+        let mut query = vec![];
+        if let Some(symbol) = symbol {
+            query.push(("symbol".to_owned(), symbol.to_owned()));
+        }
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .get(self.make_url(&self.binance.cfg.get_futures_position_risk))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceFuturesPosition>>(resp)
+    }
+
+    /// Change the initial leverage of the symbol, from 1 to 125 depending on the symbol
+    pub fn change_leverage(&self, symbol: &str, leverage: u32) -> Result<BinanceFuturesLeverage> {
+        if !(1..=125).contains(&leverage) {
+            bail!("leverage must be between 1 and 125");
+        }
+        let query = vec![
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("leverage".to_owned(), leverage.to_string()),
+        ];
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .post(self.make_url(&self.binance.cfg.post_futures_leverage))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceFuturesLeverage>(resp)
+    }
+
+    /// Switch the symbol between isolated and cross margin, refused by Binance while
+    /// the symbol has a position or open orders
+    pub fn change_margin_type(
+        &self,
+        symbol: &str,
+        margin_type: BinanceFuturesMarginType,
+    ) -> Result<()> {
+        let query = vec![
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("marginType".to_owned(), margin_type.as_str().to_owned()),
+        ];
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .post(self.make_url(&self.binance.cfg.post_futures_margin_type))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<serde_json::Value>(resp)?;
+        Ok(())
+    }
+
+    /// Place the order
+    pub fn make_order(&self, order: BinanceFuturesOrderRequest) -> Result<BinanceFuturesOrder> {
+        let query = ReqwestFacade::object_to_query(&order)?;
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .post(self.make_url(&self.binance.cfg.post_futures_order))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceFuturesOrder>(resp)
+    }
+
+    /// Validate the order with Binance without sending it to the matching engine
+    pub fn test_order(&self, order: &BinanceFuturesOrderRequest) -> Result<()> {
+        let query = ReqwestFacade::object_to_query(order)?;
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .post(self.make_url(&self.binance.cfg.post_futures_test_order))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<serde_json::Value>(resp)?;
+        Ok(())
+    }
+
+    /// Get the last funding rates of the symbol, oldest first, up to 1000
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let rates = binance.futures().get_funding_rates("BTCUSDT", 30)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_funding_rates(&self, symbol: &str, limit: u32) -> Result<Vec<BinanceFundingRate>> {
+        let query = [
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("limit".to_owned(), limit.to_string()),
+        ];
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .get(self.make_url(&self.binance.cfg.get_futures_funding_rate))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceFundingRate>>(resp)
+    }
+
+    /// Get the last changes of the futures wallet, oldest first, up to 1000,
+    /// of one symbol or income type when given
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let income = binance.futures().get_income(Some("BTCUSDT"), Some("FUNDING_FEE"), 100)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_income(
+        &self,
+        symbol: Option<&str>,
+        income_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<BinanceFuturesIncome>> {
+        let mut query = vec![];
+        if let Some(symbol) = symbol {
+            query.push(("symbol".to_owned(), symbol.to_owned()));
+        }
+        if let Some(income_type) = income_type {
+            query.push(("incomeType".to_owned(), income_type.to_owned()));
+        }
+        query.push(("limit".to_owned(), limit.to_string()));
+        let query = self.binance.compose_query(query)?;
+        let resp = self.binance.send(
+            self.binance
+                .client
+                .get(self.make_url(&self.binance.cfg.get_futures_income))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceFuturesIncome>>(resp)
+    }
+}
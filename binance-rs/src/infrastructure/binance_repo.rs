@@ -1,78 +1,235 @@
-use std::{fs, path::PathBuf, time::Duration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use crate::{
-    app_config::AppConfigBinance,
+    app_config::{AppConfigBinance, BinanceEnvironment},
     domain::binance::{
-        BinanceKeypair, BinanceSpotAccount, BinanceSpotOrder, BinanceSpotOrderRequest, Price,
+        BinanceCanceledOrder, BinanceExchangeInfo, BinanceKline, BinanceOrderList,
+        BinanceOrderListRequest, BinanceOrderResponseType, BinanceSpotAccount, BinanceSpotOrder,
+        BinanceSpotOrderRequest, BinanceSymbolInfo, BinanceTestOrderCommission, BinanceTrade,
+        BookTicker, Price, SymbolPrice,
+    },
+    domain::margin::{
+        BinanceBorrowRepayRequest, BinanceIsolatedMarginAccount, BinanceMarginAccount,
+        BinanceMarginInterestHistory, BinanceMarginOrder, BinanceMarginOrderRequest,
+        BinanceMarginTransaction, BinanceMaxBorrowable,
+    },
+    infrastructure::{
+        binance_futures::BinanceFuturesRepo,
+        metrics::Metrics,
+        reqwest_facade::ReqwestFacade,
+        signer::{ApiCredentials, RequestSigner},
+        transport::{HttpTransport, RecordingTransport, ReqwestTransport},
     },
-    infrastructure::reqwest_facade::ReqwestFacade,
 };
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
-use hmac::{Hmac, Mac};
-use reqwest::header;
-use sha2::Sha256;
+use reqwest::{
+    blocking::{RequestBuilder, Response},
+    header,
+};
 
-type HmacSha256 = Hmac<Sha256>;
+/// Timeout of the requests unless [BinanceRepoBuilder::timeout] sets another one
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct BinanceRepo {
+    pub(crate) cfg: AppConfigBinance,
+    /// Builds the requests, the transport sends them
+    pub(crate) client: reqwest::blocking::Client,
+    transport: Box<dyn HttpTransport>,
+    api_key: header::HeaderValue,
+    signer: Box<dyn RequestSigner>,
+}
+
+/// Options of the HTTP client of a [BinanceRepo], created by [BinanceRepo::builder]
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use binance_rs::{
+///     app_config::Profile,
+///     infrastructure::{binance_repo::BinanceRepo, signer::ApiCredentials},
+/// };
+/// # fn example(profile: &Profile, credentials: ApiCredentials) -> anyhow::Result<()> {
+/// let binance = BinanceRepo::builder(profile.binance.clone(), credentials)
+///     .timeout(Duration::from_secs(3))
+///     .proxy(reqwest::Proxy::https("http://proxy.local:3128")?)
+///     .user_agent("my-bot/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BinanceRepoBuilder {
     cfg: AppConfigBinance,
-    client: reqwest::blocking::Client,
-    keypair: BinanceKeypair,
+    credentials: ApiCredentials,
+    client: Option<reqwest::blocking::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    user_agent: Option<String>,
+    pool_max_idle_per_host: Option<usize>,
+    https_only: Option<bool>,
+}
+
+impl BinanceRepoBuilder {
+    /// Send the requests with this client, the other client options are then ignored.
+    /// The API key is added to each request, not to the default headers of the client.
+    pub fn client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Total timeout of a request, 10 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Idle connections kept open to Binance
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Refuse plain HTTP urls, the default except for the mock environment
+    pub fn https_only(mut self, https_only: bool) -> Self {
+        self.https_only = Some(https_only);
+        self
+    }
+
+    /// Base url of the REST API instead of the one of the environment, e.g. a local proxy
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.cfg.endpoint = endpoint.into();
+        self
+    }
+
+    /// Base url of the USDⓈ-M futures API instead of the one of the environment
+    pub fn futures_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.cfg.futures_endpoint = endpoint.into();
+        self
+    }
+
+    pub fn build(self) -> Result<BinanceRepo> {
+        let mut api_key = header::HeaderValue::from_str(self.credentials.api_key.expose())
+            .context("the API key is not a valid header value")?;
+        api_key.set_sensitive(true);
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let https_only = self
+                    .https_only
+                    .unwrap_or(self.cfg.environment != BinanceEnvironment::Mock);
+                let mut builder = reqwest::blocking::Client::builder()
+                    .https_only(https_only)
+                    .timeout(self.timeout.unwrap_or(DEFAULT_TIMEOUT));
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                if let Some(user_agent) = self.user_agent {
+                    builder = builder.user_agent(user_agent);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                builder.build().context("cannot create the HTTP client")?
+            }
+        };
+        Ok(BinanceRepo {
+            cfg: self.cfg,
+            transport: Box::new(ReqwestTransport::new(client.clone())),
+            client,
+            api_key,
+            signer: self.credentials.signer,
+        })
+    }
 }
 
 impl BinanceRepo {
-    pub fn new(cfg: AppConfigBinance, keypair: BinanceKeypair) -> Self {
-        let api_key_header =
-            header::HeaderValue::from_str(&keypair.key).expect("cannot create header from api_key");
-        let mut headers = header::HeaderMap::new();
-        headers.insert("X-MBX-APIKEY", api_key_header);
-        let client = reqwest::blocking::Client::builder()
-            .https_only(true)
-            .default_headers(headers)
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("cannot create reqwest client");
-        Self {
+    /// Repo with the default HTTP client, see [BinanceRepo::builder] to configure it
+    pub fn new(cfg: AppConfigBinance, credentials: ApiCredentials) -> Result<Self> {
+        Self::builder(cfg, credentials).build()
+    }
+
+    pub fn builder(cfg: AppConfigBinance, credentials: ApiCredentials) -> BinanceRepoBuilder {
+        BinanceRepoBuilder {
             cfg,
-            client,
-            keypair,
+            credentials,
+            client: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            user_agent: None,
+            pool_max_idle_per_host: None,
+            https_only: None,
         }
     }
 
-    /// Create a HMAC SHA256 signature given a secret and raw message
-    ///
-    /// ```
-    /// create_signature("my secret and secure key", "input message")
-    /// // 97d2a569059bbcd8ead4444ff99071f4c01d005bcefe0d3567e1be628e5fdcd9
-    /// ```
-    fn create_signature(&self, raw: &str) -> String {
-        let mut mac = HmacSha256::new_from_slice(self.keypair.secret.as_bytes())
-            .expect("HMAC can't take key of any size");
-        mac.update(raw.as_bytes());
-        let signature = mac.finalize().into_bytes();
-        hex::encode(signature)
+    /// The USDⓈ-M futures endpoints, signed and sent like the spot ones
+    pub fn futures(&self) -> BinanceFuturesRepo<'_> {
+        BinanceFuturesRepo::new(self)
+    }
+
+    /// Send the requests with another transport, e.g. a [ReplayTransport](crate::infrastructure::transport::ReplayTransport) in tests
+    pub fn with_transport(mut self, transport: Box<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Record every exchange with the current transport to the cassette at `path`
+    pub fn recording(mut self, path: PathBuf) -> Self {
+        let inner = std::mem::replace(
+            &mut self.transport,
+            Box::new(ReqwestTransport::new(self.client.clone())),
+        );
+        self.transport = Box::new(RecordingTransport::new(inner, path));
+        self
     }
 
     /// Compose the query for the request in the form of vector (key, value) pair.
-    /// Add a timestamp field and produce a signature for the request.
+    /// Add a timestamp field and produce a signature for the request with the [RequestSigner].
+    /// The signed payload is percent-encoded like reqwest encodes the query it sends.
     ///
+    /// ```text
+    /// [("symbol", "BTCUSDT")]
+    ///   => symbol=BTCUSDT&timestamp=1675659775601&signature=0f1234abcdef
     /// ```
-    /// let query = compose_query("secret", vec![("symbol", "BTCUSDT")]);
-    /// assert!(query, vec![("symbol", "BTCUSDT"), ("timestamp", "1675659775601"), ("signature", "0f1234abcdef")]);
-    /// ```
-    fn compose_query(&self, query: Vec<(String, String)>) -> Vec<(String, String)> {
+    pub(crate) fn compose_query(
+        &self,
+        query: Vec<(String, String)>,
+    ) -> Result<Vec<(String, String)>> {
         let timestamp = Utc::now().timestamp_millis();
         let mut query: Vec<(String, String)> = query;
         query.push(("timestamp".to_owned(), timestamp.to_string()));
-        let raw_query: Vec<String> = query
-            .iter()
-            .map(|(key, value)| key.to_string() + "=" + value)
-            .collect();
-        let raw_query = raw_query.join("&");
-        let signature = self.create_signature(&raw_query);
+        let raw_query = serde_urlencoded::to_string(&query).context("cannot encode query")?;
+        let signature = self.signer.sign(&raw_query)?;
         query.push(("signature".to_owned(), signature));
-        query
+        Ok(query)
+    }
+
+    pub(crate) fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.header("X-MBX-APIKEY", self.api_key.clone());
+        ReqwestFacade::send(self.transport.as_ref(), request)
     }
 
     fn make_url(&self, path: &str) -> String {
@@ -81,58 +238,443 @@ impl BinanceRepo {
 
     /// Get the account information of binance SPOT Account
     pub fn get_account(&self) -> Result<BinanceSpotAccount> {
-        let query = self.compose_query(vec![]);
-        let resp = self
-            .client
-            .get(self.make_url(&self.cfg.get_account))
-            .query(&query)
-            .send()?;
+        let query = self.compose_query(vec![])?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_account))
+                .query(&query),
+        )?;
         ReqwestFacade::handle_response_json::<BinanceSpotAccount>(resp)
     }
 
+    /// Check that the API is reachable
+    pub fn ping(&self) -> Result<()> {
+        let resp = self.send(self.client.get(self.make_url(&self.cfg.get_ping)))?;
+        ReqwestFacade::handle_response_json::<serde_json::Value>(resp)?;
+        Ok(())
+    }
+
     /// Get price of given symbol.
     ///
-    /// ```
-    /// get_price("BTCUSDT")
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let price = binance.get_price("BTCUSDT")?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn get_price(&self, symbol: &str) -> Result<Price> {
-        let resp = self
-            .client
-            .get(self.make_url(&self.cfg.get_avg_price))
-            .query(&[("symbol".to_owned(), symbol)])
-            .send()?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_avg_price))
+                .query(&[("symbol".to_owned(), symbol)]),
+        )?;
         ReqwestFacade::handle_response_json::<Price>(resp)
     }
 
-    /// Get orders
+    /// Get the last price of every symbol
+    pub fn get_prices(&self) -> Result<Vec<SymbolPrice>> {
+        let resp = self.send(self.client.get(self.make_url(&self.cfg.get_ticker_price)))?;
+        ReqwestFacade::handle_response_json::<Vec<SymbolPrice>>(resp)
+    }
+
+    /// Get the best bid and ask of the symbol
     ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let mid = binance.get_book_ticker("BTCUSDT")?.mid();
+    /// # Ok(())
+    /// # }
     /// ```
-    /// get_orders("BTCUSDT")
+    pub fn get_book_ticker(&self, symbol: &str) -> Result<BookTicker> {
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_book_ticker))
+                .query(&[("symbol".to_owned(), symbol)]),
+        )?;
+        ReqwestFacade::handle_response_json::<BookTicker>(resp)
+    }
+
+    /// Get orders
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let orders = binance.get_orders("BTCUSDT")?;
+    /// # Ok(())
+    /// # }
     /// ```
     pub fn get_orders(&self, symbol: &str) -> Result<Vec<BinanceSpotOrder>> {
         let query = vec![("symbol".to_owned(), symbol.to_owned())];
-        let query = self.compose_query(query);
-        let resp = self
-            .client
-            .get(self.make_url(&self.cfg.get_all_orders))
-            .query(&query)
-            .send()?;
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_all_orders))
+                .query(&query),
+        )?;
         ReqwestFacade::handle_response_json::<Vec<BinanceSpotOrder>>(resp)
     }
 
+    /// Get the last trades of the account on the symbol, oldest first
+    pub fn get_my_trades(&self, symbol: &str, limit: u32) -> Result<Vec<BinanceTrade>> {
+        let query = vec![
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("limit".to_owned(), limit.to_string()),
+        ];
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_my_trades))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceTrade>>(resp)
+    }
+
+    /// Get the last candlesticks of the symbol, oldest first
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let klines = binance.get_klines("BTCUSDT", "1m", 120)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u32,
+    ) -> Result<Vec<BinanceKline>> {
+        let query = [
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("interval".to_owned(), interval.to_owned()),
+            ("limit".to_owned(), limit.to_string()),
+        ];
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_klines))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceKline>>(resp)
+    }
+
+    /// Get the trading rules of the symbol
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let info = binance.get_symbol_info("BTCUSDT")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_symbol_info(&self, symbol: &str) -> Result<BinanceSymbolInfo> {
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_exchange_info))
+                .query(&[("symbol".to_owned(), symbol)]),
+        )?;
+        let info = ReqwestFacade::handle_response_json::<BinanceExchangeInfo>(resp)?;
+        info.symbols
+            .into_iter()
+            .find(|info| info.symbol == symbol)
+            .ok_or_else(|| anyhow!("symbol {symbol} not found in exchange info"))
+    }
+
+    /// Get the trading rules of every symbol
+    pub fn get_all_symbol_info(&self) -> Result<Vec<BinanceSymbolInfo>> {
+        let resp = self.send(self.client.get(self.make_url(&self.cfg.get_exchange_info)))?;
+        let info = ReqwestFacade::handle_response_json::<BinanceExchangeInfo>(resp)?;
+        Ok(info.symbols)
+    }
+
     /// Request Binance API to create the order
-    /// Use [read_order_from_file] to construct the order request
-    pub fn make_spot_order(&self, order: BinanceSpotOrderRequest) -> Result<BinanceSpotOrder> {
-        let query = ReqwestFacade::object_to_query::<BinanceSpotOrderRequest>(order);
-        let query = self.compose_query(query);
-        let resp = self
-            .client
-            .post(self.make_url(&self.cfg.post_new_order))
-            .query(&query)
-            .send()?;
+    /// Use [read_order_from_file] to construct the order request.
+    /// The FULL response with the fills is requested unless the order sets `newOrderRespType`.
+    pub fn make_spot_order(&self, mut order: BinanceSpotOrderRequest) -> Result<BinanceSpotOrder> {
+        order
+            .new_order_resp_type
+            .get_or_insert(BinanceOrderResponseType::Full);
+        let query = ReqwestFacade::object_to_query(&order)?;
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .post(self.make_url(&self.cfg.post_new_order))
+                .query(&query),
+        )?;
         ReqwestFacade::handle_response_json::<BinanceSpotOrder>(resp)
     }
 
+    /// Validate the order with Binance without sending it to the matching engine,
+    /// returning the commission rates that would apply to it
+    pub fn test_spot_order(
+        &self,
+        order: BinanceSpotOrderRequest,
+    ) -> Result<BinanceTestOrderCommission> {
+        let mut query = ReqwestFacade::object_to_query(&order)?;
+        query.push(("computeCommissionRates".to_owned(), "true".to_owned()));
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .post(self.make_url(&self.cfg.post_test_order))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceTestOrderCommission>(resp)
+    }
+
+    /// Place an OCO, OTO or OTOCO order list.
+    /// The FULL response with the fills is requested unless the list sets `newOrderRespType`.
+    pub fn make_order_list(&self, order_list: BinanceOrderListRequest) -> Result<BinanceOrderList> {
+        let full = Some(BinanceOrderResponseType::Full);
+        let (path, query) = match order_list {
+            BinanceOrderListRequest::Oco(mut oco) => {
+                oco.new_order_resp_type = oco.new_order_resp_type.or(full);
+                let query = ReqwestFacade::object_to_query(&oco)?;
+                (&self.cfg.post_order_list_oco, query)
+            }
+            BinanceOrderListRequest::Oto(mut oto) => {
+                oto.new_order_resp_type = oto.new_order_resp_type.or(full);
+                let query = ReqwestFacade::object_to_query(&oto)?;
+                (&self.cfg.post_order_list_oto, query)
+            }
+            BinanceOrderListRequest::Otoco(mut otoco) => {
+                otoco.new_order_resp_type = otoco.new_order_resp_type.or(full);
+                let query = ReqwestFacade::object_to_query(&otoco)?;
+                (&self.cfg.post_order_list_otoco, query)
+            }
+        };
+        let query = self.compose_query(query)?;
+        let resp = self.send(self.client.post(self.make_url(path)).query(&query))?;
+        ReqwestFacade::handle_response_json::<BinanceOrderList>(resp)
+    }
+
+    /// Get an order list and the ids of its orders
+    pub fn get_order_list(&self, order_list_id: i64) -> Result<BinanceOrderList> {
+        let query = vec![("orderListId".to_owned(), order_list_id.to_string())];
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_order_list))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceOrderList>(resp)
+    }
+
+    /// Get the open order lists of every symbol
+    pub fn get_open_order_lists(&self) -> Result<Vec<BinanceOrderList>> {
+        let query = self.compose_query(vec![])?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_open_order_lists))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceOrderList>>(resp)
+    }
+
+    /// Cancel every order of an order list
+    pub fn cancel_order_list(&self, symbol: &str, order_list_id: i64) -> Result<BinanceOrderList> {
+        let query = vec![
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("orderListId".to_owned(), order_list_id.to_string()),
+        ];
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .delete(self.make_url(&self.cfg.delete_order_list))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceOrderList>(resp)
+    }
+
+    /// Get the open orders of every symbol, kept in the open order [Metrics]
+    pub fn get_open_orders(&self) -> Result<Vec<BinanceSpotOrder>> {
+        let query = self.compose_query(vec![])?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_open_orders))
+                .query(&query),
+        )?;
+        let orders = ReqwestFacade::handle_response_json::<Vec<BinanceSpotOrder>>(resp)?;
+        Metrics::global().set_open_orders(&orders);
+        Ok(orders)
+    }
+
+    /// Cancel every open order of the symbol, order lists included
+    pub fn cancel_open_orders(&self, symbol: &str) -> Result<Vec<BinanceCanceledOrder>> {
+        let query = vec![("symbol".to_owned(), symbol.to_owned())];
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .delete(self.make_url(&self.cfg.delete_open_orders))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceCanceledOrder>>(resp)
+    }
+
+    /// Get the cross margin account and its assets
+    pub fn get_margin_account(&self) -> Result<BinanceMarginAccount> {
+        let query = self.compose_query(vec![])?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_margin_account))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMarginAccount>(resp)
+    }
+
+    /// Get the isolated margin pairs, every pair when `symbols` is empty, up to 5 otherwise
+    ///
+    /// ```no_run
+    /// # fn example(binance: &binance_rs::infrastructure::binance_repo::BinanceRepo) -> anyhow::Result<()> {
+    /// let account = binance.get_isolated_margin_account(&["BTCUSDT".to_owned()])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_isolated_margin_account(
+        &self,
+        symbols: &[String],
+    ) -> Result<BinanceIsolatedMarginAccount> {
+        let mut query = vec![];
+        if !symbols.is_empty() {
+            query.push(("symbols".to_owned(), symbols.join(",")));
+        }
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_isolated_margin_account))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceIsolatedMarginAccount>(resp)
+    }
+
+    /// Borrow or repay an asset of the cross margin account or of an isolated pair
+    pub fn margin_borrow_repay(
+        &self,
+        request: &BinanceBorrowRepayRequest,
+    ) -> Result<BinanceMarginTransaction> {
+        let query = ReqwestFacade::object_to_query(request)?;
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .post(self.make_url(&self.cfg.post_margin_borrow_repay))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMarginTransaction>(resp)
+    }
+
+    /// Place an order on the margin account.
+    /// The FULL response with the fills is requested unless the order sets `newOrderRespType`.
+    pub fn make_margin_order(
+        &self,
+        mut order: BinanceMarginOrderRequest,
+    ) -> Result<BinanceMarginOrder> {
+        order
+            .order
+            .new_order_resp_type
+            .get_or_insert(BinanceOrderResponseType::Full);
+        let query = ReqwestFacade::object_to_query(&order)?;
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .post(self.make_url(&self.cfg.post_margin_order))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMarginOrder>(resp)
+    }
+
+    /// Get the open margin orders, of every symbol of the cross margin account when
+    /// `symbol` is not given. Isolated orders require the symbol.
+    pub fn get_margin_open_orders(
+        &self,
+        symbol: Option<&str>,
+        is_isolated: bool,
+    ) -> Result<Vec<BinanceMarginOrder>> {
+        let mut query = vec![];
+        if let Some(symbol) = symbol {
+            query.push(("symbol".to_owned(), symbol.to_owned()));
+        }
+        if is_isolated {
+            query.push(("isIsolated".to_owned(), "TRUE".to_owned()));
+        }
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_margin_open_orders))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<Vec<BinanceMarginOrder>>(resp)
+    }
+
+    /// Cancel an open margin order
+    pub fn cancel_margin_order(
+        &self,
+        symbol: &str,
+        order_id: i64,
+        is_isolated: bool,
+    ) -> Result<BinanceMarginOrder> {
+        let mut query = vec![
+            ("symbol".to_owned(), symbol.to_owned()),
+            ("orderId".to_owned(), order_id.to_string()),
+        ];
+        if is_isolated {
+            query.push(("isIsolated".to_owned(), "TRUE".to_owned()));
+        }
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .delete(self.make_url(&self.cfg.delete_margin_order))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMarginOrder>(resp)
+    }
+
+    /// Get the last interests charged, most recent first, of one asset or every asset,
+    /// of the cross margin account or of the isolated pair `isolated_symbol`
+    pub fn get_margin_interest_history(
+        &self,
+        asset: Option<&str>,
+        isolated_symbol: Option<&str>,
+        size: u32,
+    ) -> Result<BinanceMarginInterestHistory> {
+        let mut query = vec![];
+        if let Some(asset) = asset {
+            query.push(("asset".to_owned(), asset.to_owned()));
+        }
+        if let Some(symbol) = isolated_symbol {
+            query.push(("isolatedSymbol".to_owned(), symbol.to_owned()));
+        }
+        query.push(("size".to_owned(), size.to_string()));
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_margin_interest_history))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMarginInterestHistory>(resp)
+    }
+
+    /// Get how much of the asset can still be borrowed
+    pub fn get_margin_max_borrowable(
+        &self,
+        asset: &str,
+        isolated_symbol: Option<&str>,
+    ) -> Result<BinanceMaxBorrowable> {
+        let mut query = vec![("asset".to_owned(), asset.to_owned())];
+        if let Some(symbol) = isolated_symbol {
+            query.push(("isolatedSymbol".to_owned(), symbol.to_owned()));
+        }
+        let query = self.compose_query(query)?;
+        let resp = self.send(
+            self.client
+                .get(self.make_url(&self.cfg.get_margin_max_borrowable))
+                .query(&query),
+        )?;
+        ReqwestFacade::handle_response_json::<BinanceMaxBorrowable>(resp)
+    }
+
+    /// Given a toml file_path read and parse the file to <BinanceOrderListRequest>
+    pub fn read_order_list_from_file(file_path: &Path) -> Result<BinanceOrderListRequest> {
+        let content = fs::read_to_string(file_path).context("cannot read order list from file")?;
+        let order_list = toml::from_str::<BinanceOrderListRequest>(&content)
+            .context("cannot parse order list from toml")?;
+        Ok(order_list)
+    }
+
     /// Given a toml file_path read and parse the file to <BinanceSpotOrderRequest>
     pub fn read_order_from_file(file_path: PathBuf) -> Result<BinanceSpotOrderRequest> {
         let content = fs::read_to_string(file_path).context("cannot read order from file")?;
@@ -0,0 +1,143 @@
+use std::{io::ErrorKind, net::TcpStream, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::domain::binance::F64String;
+
+/// Message of a combined stream, the data wrapped with the name of its stream
+#[derive(Debug, Deserialize)]
+struct BinanceStreamMessage {
+    #[serde(rename = "stream")]
+    stream: String,
+
+    #[serde(rename = "data")]
+    data: serde_json::Value,
+}
+
+#[derive(Debug)]
+pub enum BinanceStreamEvent {
+    AggTrade(BinanceAggTrade),
+    BookTicker(BinanceBookTicker),
+}
+
+/// Trades of a symbol aggregated by price and taker side, from the `<symbol>@aggTrade` stream
+#[derive(Debug, Deserialize)]
+pub struct BinanceAggTrade {
+    #[serde(rename = "s")]
+    pub symbol: String,
+
+    #[serde(rename = "p")]
+    pub price: F64String,
+
+    #[serde(rename = "q")]
+    pub quantity: F64String,
+
+    #[serde(rename = "T")]
+    pub trade_time: i64,
+}
+
+/// Best bid and ask of a symbol, from the `<symbol>@bookTicker` stream
+#[derive(Debug, Deserialize)]
+pub struct BinanceBookTicker {
+    #[serde(rename = "s")]
+    pub symbol: String,
+
+    #[serde(rename = "b")]
+    pub bid_price: F64String,
+
+    #[serde(rename = "B")]
+    pub bid_quantity: F64String,
+
+    #[serde(rename = "a")]
+    pub ask_price: F64String,
+
+    #[serde(rename = "A")]
+    pub ask_quantity: F64String,
+}
+
+/// Combined websocket market stream of several streams
+///
+/// ```no_run
+/// use binance_rs::infrastructure::binance_stream::BinanceStream;
+/// # fn main() -> anyhow::Result<()> {
+/// let streams = [BinanceStream::agg_trade("BTCUSDT"), BinanceStream::book_ticker("BTCUSDT")];
+/// let mut stream = BinanceStream::subscribe("wss://stream.binance.com:9443", &streams)?;
+/// while let Some(event) = stream.next_event()? {
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct BinanceStream {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl BinanceStream {
+    /// Name of the stream of the aggregated trades of the symbol
+    pub fn agg_trade(symbol: &str) -> String {
+        format!("{}@aggTrade", symbol.to_lowercase())
+    }
+
+    /// Name of the stream of the best bid and ask of the symbol
+    pub fn book_ticker(symbol: &str) -> String {
+        format!("{}@bookTicker", symbol.to_lowercase())
+    }
+
+    pub fn subscribe(stream_endpoint: &str, streams: &[String]) -> Result<Self> {
+        let url = format!("{}/stream?streams={}", stream_endpoint, streams.join("/"));
+        let (socket, _) =
+            tungstenite::connect(&url).with_context(|| format!("cannot connect to {url}"))?;
+        tracing::info!("connected to {}", url);
+        Ok(Self { socket })
+    }
+
+    /// Return from [BinanceStream::next_event] with `None` when no message comes in time,
+    /// so the caller can do other work between trades
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let tcp = match self.socket.get_mut() {
+            MaybeTlsStream::Plain(tcp) => tcp,
+            MaybeTlsStream::NativeTls(tls) => tls.get_mut(),
+            _ => return Ok(()),
+        };
+        tcp.set_read_timeout(Some(timeout))
+            .context("cannot set the stream read timeout")
+    }
+
+    /// Wait for the next event. Pings are answered while waiting, other messages,
+    /// read timeouts and reads interrupted by a signal return `None`.
+    pub fn next_event(&mut self) -> Result<Option<BinanceStreamEvent>> {
+        let message = match self.socket.read() {
+            Ok(message) => message,
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(err) => return Err(err).context("cannot read from stream"),
+        };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(frame) => bail!("stream closed: {frame:?}"),
+            _ => return Ok(None),
+        };
+        let message = serde_json::from_str::<BinanceStreamMessage>(&text)
+            .with_context(|| format!("cannot parse stream message {text}"))?;
+        let event = match message.stream.rsplit('@').next() {
+            Some("aggTrade") => {
+                serde_json::from_value(message.data).map(BinanceStreamEvent::AggTrade)
+            }
+            Some("bookTicker") => {
+                serde_json::from_value(message.data).map(BinanceStreamEvent::BookTicker)
+            }
+            _ => return Ok(None),
+        };
+        event
+            .map(Some)
+            .with_context(|| format!("cannot parse stream message {text}"))
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::secret::SecretString;
+
+/// Environment variable read before prompting for the keystore passphrase
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "BINANCE_RS_KEYSTORE_PASSPHRASE";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeystoreFile {
+    #[serde(default)]
+    entries: BTreeMap<String, KeystoreEntry>,
+}
+
+/// A secret encrypted with ChaCha20-Poly1305 using a key derived from the passphrase with Argon2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreEntry {
+    pub created_at: DateTime<Utc>,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Passphrase encrypted secrets stored in a local toml file
+///
+/// ```no_run
+/// use binance_rs::{domain::secret::SecretString, infrastructure::keystore::Keystore};
+/// # fn example(secret: &SecretString, passphrase: &SecretString) -> anyhow::Result<()> {
+/// let mut keystore = Keystore::open(Keystore::default_path())?;
+/// keystore.insert("main-secret", secret, passphrase)?;
+/// keystore.save()?;
+/// let secret = keystore.get("main-secret", passphrase)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Keystore {
+    path: PathBuf,
+    file: KeystoreFile,
+}
+
+impl Keystore {
+    /// `<config dir>/binance-rs/keystore.toml`, e.g. `~/.config/binance-rs/keystore.toml`
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("binance-rs")
+            .join("keystore.toml")
+    }
+
+    /// Open the keystore at the path. A missing file is an empty keystore.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str::<KeystoreFile>(&content)
+                .with_context(|| format!("cannot parse keystore {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => KeystoreFile::default(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("cannot read keystore {}", path.display()))
+            }
+        };
+        Ok(Self { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &KeystoreEntry)> {
+        self.file.entries.iter()
+    }
+
+    /// Write the keystore back to its path, only readable by the current user.
+    /// The file is replaced atomically so an interrupted write never loses the keys.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("cannot create keystore directory")?;
+        }
+        let content = toml::to_string(&self.file).context("cannot serialize keystore")?;
+        let tmp = self.path.with_extension("toml.tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&tmp)
+            .with_context(|| format!("cannot write keystore {}", tmp.display()))?;
+        // The mode only applies to new files, a leftover temporary file keeps its own
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("cannot restrict keystore {}", tmp.display()))?;
+        }
+        file.write_all(content.as_bytes())
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("cannot write keystore {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("cannot write keystore {}", self.path.display()))
+    }
+
+    /// Check the passphrase against an existing entry so all entries share one passphrase
+    pub fn verify(&self, passphrase: &SecretString) -> Result<()> {
+        match self.file.entries.keys().next() {
+            Some(name) => self.get(name, passphrase).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        name: &str,
+        secret: &SecretString,
+        passphrase: &SecretString,
+    ) -> Result<()> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, secret.expose().as_bytes())
+            .map_err(|_| anyhow!("cannot encrypt secret {name}"))?;
+        self.file.entries.insert(
+            name.to_owned(),
+            KeystoreEntry {
+                created_at: Utc::now(),
+                salt: hex::encode(salt),
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        self.file
+            .entries
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("key {name} not found in keystore"))
+    }
+
+    pub fn get(&self, name: &str, passphrase: &SecretString) -> Result<SecretString> {
+        let entry = self
+            .file
+            .entries
+            .get(name)
+            .ok_or_else(|| anyhow!("key {name} not found in keystore"))?;
+        let salt = hex::decode(&entry.salt).context("invalid keystore salt")?;
+        let nonce = hex::decode(&entry.nonce).context("invalid keystore nonce")?;
+        let ciphertext = hex::decode(&entry.ciphertext).context("invalid keystore ciphertext")?;
+        if nonce.len() != 12 {
+            bail!("invalid keystore nonce length for key {name}");
+        }
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow!("cannot decrypt key {name}, wrong passphrase?"))?;
+        let secret = String::from_utf8(plaintext).context("secret is not utf-8")?;
+        Ok(SecretString::new(secret))
+    }
+}
+
+/// Read the passphrase from [KEYSTORE_PASSPHRASE_ENV] or prompt for it on the terminal
+pub fn read_passphrase(confirm: bool) -> Result<SecretString> {
+    if let Ok(passphrase) = env::var(KEYSTORE_PASSPHRASE_ENV) {
+        return Ok(SecretString::new(passphrase));
+    }
+    let passphrase = rpassword::prompt_password("Keystore passphrase: ")?;
+    if confirm {
+        let again = rpassword::prompt_password("Confirm passphrase: ")?;
+        if passphrase != again {
+            bail!("passphrases do not match");
+        }
+    }
+    Ok(SecretString::new(passphrase))
+}
+
+fn derive_key(passphrase: &SecretString, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.expose().as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("cannot derive keystore key: {e}"))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app_config::SecretSource, infrastructure::secret_facade::SecretResolver};
+
+    fn path(name: &str) -> PathBuf {
+        let dir =
+            env::temp_dir().join(format!("binance-rs-keystore-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("keystore.toml")
+    }
+
+    fn secret(value: &str) -> SecretString {
+        SecretString::new(value.to_owned())
+    }
+
+    fn saved(path: &Path, passphrase: &str) {
+        let mut keystore = Keystore::open(path.to_owned()).unwrap();
+        keystore
+            .insert("main-secret", &secret("s3cr3t-value"), &secret(passphrase))
+            .unwrap();
+        keystore.save().unwrap();
+    }
+
+    #[test]
+    fn saved_secret_is_read_back() {
+        let path = path("roundtrip");
+        saved(&path, "correct horse");
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t-value"));
+        let keystore = Keystore::open(path.clone()).unwrap();
+        let secret = keystore
+            .get("main-secret", &secret("correct horse"))
+            .unwrap();
+        assert_eq!(secret.expose(), "s3cr3t-value");
+        assert!(!path.with_extension("toml.tmp").exists());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_refused() {
+        let path = path("passphrase");
+        saved(&path, "correct horse");
+
+        let keystore = Keystore::open(path).unwrap();
+        let err = keystore
+            .get("main-secret", &secret("battery staple"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot decrypt key main-secret, wrong passphrase?"
+        );
+        assert!(keystore.verify(&secret("battery staple")).is_err());
+        assert!(keystore.verify(&secret("correct horse")).is_ok());
+    }
+
+    #[test]
+    fn resolver_reads_the_keystore_entry() {
+        let path = path("resolver");
+        saved(&path, "correct horse");
+
+        env::set_var(KEYSTORE_PASSPHRASE_ENV, "correct horse");
+        let resolver = SecretResolver::new(path);
+        let secret = resolver
+            .resolve(&SecretSource::Keystore {
+                keystore: "main-secret".to_owned(),
+            })
+            .unwrap();
+        assert_eq!(secret.expose(), "s3cr3t-value");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_keystore_is_only_readable_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = path("mode");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        saved(&path, "correct horse");
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
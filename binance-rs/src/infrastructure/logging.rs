@@ -0,0 +1,231 @@
+use std::{
+    borrow::Cow,
+    io::{self, IsTerminal, Write},
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use tracing_subscriber::EnvFilter;
+
+/// Levels used when `RUST_LOG` is not set
+const DEFAULT_FILTER: &str = "warn,binance_rs=info";
+
+/// Secrets resolved by the process, masked wherever they show up in the logs
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// One human readable line per event
+    #[default]
+    Text,
+    /// One JSON object per event, with the fields of the current span
+    Json,
+}
+
+/// Send the `tracing` events, and the `log` records of the dependencies, to stderr
+/// through [redact]. The levels come from `RUST_LOG`, e.g. `RUST_LOG=binance_rs=debug`
+/// shows the span of every Binance request.
+pub fn init(format: LogFormat) -> Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(|| RedactingWriter(io::stderr()))
+        .with_ansi(io::stderr().is_terminal());
+    match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().with_current_span(true).try_init(),
+    }
+    .map_err(|err| anyhow!("cannot set up the logs: {err}"))
+}
+
+/// Mask the value in every later log line, done for each secret the config resolves
+pub fn register_secret(value: &str) {
+    // Too short values would mask unrelated text
+    if value.len() < 8 {
+        if !value.is_empty() {
+            tracing::warn!(
+                "a secret of {} characters is too short to be masked in the logs",
+                value.len()
+            );
+        }
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|err| err.into_inner());
+    if !secrets.iter().any(|secret| secret == value) {
+        secrets.push(value.to_owned());
+    }
+}
+
+/// Mask the registered secrets, request signatures and balances of the text
+///
+/// ```
+/// use binance_rs::infrastructure::logging::redact;
+///
+/// assert_eq!(
+///     redact("GET /api/v3/account?timestamp=1&signature=0f12"),
+///     "GET /api/v3/account?timestamp=1&signature=***"
+/// );
+/// assert_eq!(
+///     redact(r#"{"asset":"BTC","free":"1.5"}"#),
+///     r#"{"asset":"BTC","free":"***"}"#
+/// );
+/// ```
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut text = redact_secrets(text);
+    for (pattern, replacement) in patterns() {
+        if let Cow::Owned(replaced) = pattern.replace_all(&text, *replacement) {
+            text = Cow::Owned(replaced);
+        }
+    }
+    text
+}
+
+/// Mask only the registered secrets of the text
+pub fn redact_secrets(text: &str) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(text);
+    for secret in SECRETS.read().unwrap_or_else(|err| err.into_inner()).iter() {
+        if text.contains(secret.as_str()) {
+            text = Cow::Owned(text.replace(secret.as_str(), "***"));
+        }
+    }
+    text
+}
+
+fn patterns() -> &'static [(Regex, &'static str)] {
+    PATTERNS.get_or_init(|| {
+        [
+            (r#"(?i)(signature=)[^&\s\\"')]+"#, "${1}***"),
+            // Balance fields of account responses, escaped or not inside JSON logs
+            (
+                r#"(\\?"(?:free|locked|balance|walletBalance|netAsset)\\?"\s*:\s*\\?"?)[-0-9.eE]+"#,
+                "${1}***",
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid pattern"), replacement))
+        .collect()
+    })
+}
+
+/// Writer masking each formatted event, which the subscriber writes at once
+struct RedactingWriter<W: Write>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Lines written by the subscriber, shared with the test
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    /// The events of `log` written through [RedactingWriter] in the format
+    fn logged(format: LogFormat, log: impl FnOnce()) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let builder = tracing_subscriber::fmt()
+            .with_writer(move || RedactingWriter(writer.clone()))
+            .with_ansi(false);
+        match format {
+            LogFormat::Text => tracing::subscriber::with_default(builder.finish(), log),
+            LogFormat::Json => tracing::subscriber::with_default(builder.json().finish(), log),
+        }
+        captured.text()
+    }
+
+    fn log_sensitive_events() {
+        register_secret("registered-api-key-0123");
+        tracing::info!("GET /api/v3/account?timestamp=1&signature=0f12ab&recvWindow=5000");
+        tracing::info!("sent with key registered-api-key-0123 to Binance");
+        tracing::info!(
+            r#"response {{"balances":[{{"asset":"BTC","free":"1.5","locked":"0.1"}}],"walletBalance":"42.0"}}"#
+        );
+        tracing::info!(symbol = "BTCUSDT", "order 12 accepted by the risk limits");
+    }
+
+    #[test]
+    fn text_logs_are_redacted() {
+        let text = logged(LogFormat::Text, log_sensitive_events);
+        assert!(
+            text.contains("timestamp=1&signature=***&recvWindow=5000"),
+            "{text}"
+        );
+        assert!(text.contains("sent with key *** to Binance"), "{text}");
+        assert!(
+            text.contains(r#""asset":"BTC","free":"***","locked":"***"}],"walletBalance":"***"}"#),
+            "{text}"
+        );
+        assert!(
+            text.contains("order 12 accepted by the risk limits symbol=\"BTCUSDT\""),
+            "{text}"
+        );
+        for leaked in ["0f12ab", "registered-api-key-0123", "1.5", "0.1", "42.0"] {
+            assert!(!text.contains(leaked), "{leaked} in {text}");
+        }
+    }
+
+    #[test]
+    fn json_logs_are_redacted() {
+        let text = logged(LogFormat::Json, log_sensitive_events);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        for line in lines.iter() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+        assert!(text.contains("signature=***"), "{text}");
+        assert!(text.contains("sent with key *** to Binance"), "{text}");
+        assert!(text.contains(r#"\"free\":\"***\""#), "{text}");
+        assert!(text.contains(r#"\"walletBalance\":\"***\""#), "{text}");
+        assert!(text.contains(r#""symbol":"BTCUSDT""#), "{text}");
+        for leaked in ["0f12ab", "registered-api-key-0123", "1.5", "42.0"] {
+            assert!(!text.contains(leaked), "{leaked} in {text}");
+        }
+    }
+
+    #[test]
+    fn short_secrets_are_reported_instead_of_masked() {
+        let text = logged(LogFormat::Text, || {
+            register_secret("k3y");
+            tracing::info!("sent with key k3y");
+        });
+        assert!(
+            text.contains("a secret of 3 characters is too short to be masked"),
+            "{text}"
+        );
+        assert!(text.contains("sent with key k3y"), "{text}");
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::domain::binance::BinanceSpotOrder;
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Prometheus metrics of the process, recorded by [crate::infrastructure::reqwest_facade::ReqwestFacade]
+/// for every Binance request and by [crate::infrastructure::binance_repo::BinanceRepo] for the
+/// open orders it reads. Balances are left out like in the logs.
+///
+/// ```no_run
+/// use binance_rs::{domain::binance::BinanceSpotOrder, infrastructure::metrics::Metrics};
+/// # fn example(orders: &[BinanceSpotOrder]) -> anyhow::Result<()> {
+/// Metrics::global().set_open_orders(orders);
+/// let text = Metrics::global().encode()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    used_weight: IntGauge,
+    order_count: IntGaugeVec,
+    open_orders: IntGaugeVec,
+    /// When the open orders were last read
+    open_orders_read: Mutex<Option<Instant>>,
+}
+
+impl Metrics {
+    pub fn global() -> &'static Metrics {
+        METRICS.get_or_init(|| Metrics::new().expect("cannot register the metrics"))
+    }
+
+    fn new() -> Result<Self> {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("binance_requests_total", "Requests sent to Binance"),
+            &["method", "endpoint", "status"],
+        )?;
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "binance_request_duration_seconds",
+                "Time until Binance answered, or the request failed",
+            ),
+            &["method", "endpoint"],
+        )?;
+        let used_weight = IntGauge::new(
+            "binance_used_weight_1m",
+            "Request weight used in the current minute, from X-MBX-USED-WEIGHT-1M",
+        )?;
+        let order_count = IntGaugeVec::new(
+            Opts::new(
+                "binance_order_count",
+                "Orders placed in the current interval, from X-MBX-ORDER-COUNT-*",
+            ),
+            &["interval"],
+        )?;
+        let open_orders = IntGaugeVec::new(
+            Opts::new(
+                "binance_open_orders",
+                "Open orders of the symbol at the last read",
+            ),
+            &["symbol"],
+        )?;
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(request_duration.clone()))?;
+        registry.register(Box::new(used_weight.clone()))?;
+        registry.register(Box::new(order_count.clone()))?;
+        registry.register(Box::new(open_orders.clone()))?;
+        Ok(Self {
+            registry,
+            requests,
+            request_duration,
+            used_weight,
+            order_count,
+            open_orders,
+            open_orders_read: Mutex::new(None),
+        })
+    }
+
+    /// Record a request, `None` when it failed without a response
+    pub fn record_request(
+        &self,
+        method: &str,
+        endpoint: &str,
+        elapsed: Duration,
+        response: Option<(StatusCode, &HeaderMap)>,
+    ) {
+        let status = match response {
+            Some((status, _)) => status.as_u16().to_string(),
+            None => "error".to_owned(),
+        };
+        self.requests
+            .with_label_values(&[method, endpoint, &status])
+            .inc();
+        self.request_duration
+            .with_label_values(&[method, endpoint])
+            .observe(elapsed.as_secs_f64());
+
+        if let Some((_, headers)) = response {
+            for (name, value) in headers.iter() {
+                let value = match value.to_str().ok().and_then(|v| v.parse::<i64>().ok()) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let name = name.as_str();
+                if name.eq_ignore_ascii_case("x-mbx-used-weight-1m") {
+                    self.used_weight.set(value);
+                } else if let Some(interval) = name.strip_prefix("x-mbx-order-count-") {
+                    self.order_count.with_label_values(&[interval]).set(value);
+                }
+            }
+        }
+    }
+
+    /// Replace the open order counts by the ones of the orders of every symbol
+    pub fn set_open_orders(&self, orders: &[BinanceSpotOrder]) {
+        self.open_orders.reset();
+        for order in orders {
+            let symbol = order.symbol.as_deref().unwrap_or_default();
+            self.open_orders.with_label_values(&[symbol]).inc();
+        }
+        *self
+            .open_orders_read
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = Some(Instant::now());
+    }
+
+    /// Time since the open orders were last read, `None` before the first read
+    pub fn open_orders_age(&self) -> Option<Duration> {
+        self.open_orders_read
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .map(|read| read.elapsed())
+    }
+
+    /// The metrics in the Prometheus text format
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .context("cannot encode the metrics")?;
+        String::from_utf8(buffer).context("cannot encode the metrics")
+    }
+}
@@ -1,3 +1,15 @@
+pub mod binance_futures;
 pub mod binance_repo;
-pub mod reqwest_facade;
+pub mod binance_stream;
 pub mod config_facade;
+pub mod keystore;
+pub mod logging;
+pub mod metrics;
+pub mod notifier;
+pub mod query;
+pub mod reqwest_facade;
+pub mod risk_store;
+pub mod secret_facade;
+pub mod signer;
+pub mod transport;
+pub mod trigger_store;
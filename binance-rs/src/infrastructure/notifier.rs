@@ -0,0 +1,135 @@
+use std::{collections::BTreeMap, process::Command, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::domain::alert::Alert;
+
+/// Timeout of a webhook request, so a slow receiver doesn't hold the alerts back
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Backend delivering the alerts
+pub trait Notifier {
+    /// Short description for the logs, e.g. `webhook http://localhost:9000/alerts`
+    fn describe(&self) -> String;
+
+    fn notify(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Notifier backends of an alerts file. Stdout is handled by the application
+/// since it follows the output format.
+///
+/// ```toml
+/// [[notifiers]]
+/// type = "command"
+/// program = "notify-send"
+/// args = ["Binance {symbol}", "{message}"]
+///
+/// [[notifiers]]
+/// type = "webhook"
+/// url = "http://localhost:9000/alerts"
+/// headers = { Authorization = "Bearer secret" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Stdout,
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+/// Run a program for each alert, e.g. `notify-send` for a desktop notification.
+/// `{name}`, `{symbol}`, `{condition}`, `{message}` and `{price}` are replaced in the
+/// arguments, and the alert is also given in `BINANCE_ALERT_*` environment variables.
+pub struct CommandNotifier {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandNotifier {
+    pub fn new(program: String, args: Vec<String>) -> Self {
+        Self { program, args }
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn describe(&self) -> String {
+        format!("command {}", self.program)
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<()> {
+        let fields = [
+            ("name", alert.name.to_owned()),
+            ("symbol", alert.symbol.to_owned()),
+            ("condition", alert.condition.to_owned()),
+            ("message", alert.message.to_owned()),
+            ("price", alert.price.to_string()),
+        ];
+        let args = self.args.iter().map(|arg| {
+            fields.iter().fold(arg.to_owned(), |arg, (key, value)| {
+                arg.replace(&format!("{{{key}}}"), value)
+            })
+        });
+        let envs = fields
+            .iter()
+            .map(|(key, value)| (format!("BINANCE_ALERT_{}", key.to_uppercase()), value));
+        let status = Command::new(&self.program)
+            .args(args)
+            .envs(envs)
+            .status()
+            .with_context(|| format!("cannot run {}", self.program))?;
+        if !status.success() {
+            bail!("{} exited with {}", self.program, status);
+        }
+        Ok(())
+    }
+}
+
+/// POST each alert as a JSON object to an HTTP endpoint
+pub struct WebhookNotifier {
+    client: reqwest::blocking::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, headers: BTreeMap<String, String>) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .context("cannot create the webhook client")?;
+        Ok(Self {
+            client,
+            url,
+            headers,
+        })
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn describe(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<()> {
+        let mut request = self.client.post(&self.url).json(alert);
+        for (key, value) in self.headers.iter() {
+            request = request.header(key, value);
+        }
+        let resp = request
+            .send()
+            .with_context(|| format!("cannot post to {}", self.url))?;
+        if !resp.status().is_success() {
+            bail!("{} answered {}", self.url, resp.status());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+use std::{error::Error, fmt::Display};
+
+use serde::{
+    ser::{self, Impossible},
+    Serialize, Serializer,
+};
+
+/// Error of [to_query] on values that have no query representation
+#[derive(Debug)]
+pub struct QueryError(String);
+
+impl Error for QueryError {}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ser::Error for QueryError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Serialize a struct or a map into query parameters, in the order of its fields.
+/// `None` fields are left out, enums are sent as their variant name and numbers
+/// without exponent. Nested structs are flattened by prefixing their keys with the
+/// parent key. Sequences, bytes and enum variants holding data are refused.
+///
+/// ```
+/// use binance_rs::infrastructure::query::to_query;
+/// use serde_json::json;
+///
+/// let query = to_query(&json!({
+///     "symbol": "BTCUSDT",
+///     "quantity": 0.00000001,
+///     "price": null,
+///     "above": {"type": "LIMIT_MAKER", "price": 70000.5},
+/// }))
+/// .unwrap();
+/// assert_eq!(
+///     query,
+///     vec![
+///         ("symbol".to_owned(), "BTCUSDT".to_owned()),
+///         ("quantity".to_owned(), "0.00000001".to_owned()),
+///         ("aboveType".to_owned(), "LIMIT_MAKER".to_owned()),
+///         ("abovePrice".to_owned(), "70000.5".to_owned()),
+///     ]
+/// );
+/// assert!(to_query(&json!({"symbols": ["BTCUSDT"]})).is_err());
+/// ```
+pub fn to_query<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, String)>, QueryError> {
+    let mut query = vec![];
+    value.serialize(QuerySerializer {
+        key: String::new(),
+        query: &mut query,
+    })?;
+    Ok(query)
+}
+
+/// Serialize the value found at `key`, the empty key being the top level object
+struct QuerySerializer<'a> {
+    key: String,
+    query: &'a mut Vec<(String, String)>,
+}
+
+impl<'a> QuerySerializer<'a> {
+    fn push(self, value: String) -> Result<(), QueryError> {
+        if self.key.is_empty() {
+            return Err(QueryError(format!(
+                "cannot send the value {value} as query, expected a struct or a map"
+            )));
+        }
+        self.query.push((self.key, value));
+        Ok(())
+    }
+
+    fn unsupported(&self, shape: &str) -> QueryError {
+        if self.key.is_empty() {
+            QueryError(format!("cannot send {shape} as query"))
+        } else {
+            QueryError(format!(
+                "cannot send the {} {} as query parameter",
+                shape, self.key
+            ))
+        }
+    }
+
+    fn not_finite(&self, value: impl Display) -> QueryError {
+        QueryError(format!(
+            "cannot send the number {} as query parameter {}",
+            value, self.key
+        ))
+    }
+
+    /// Key of a nested field, e.g. `type` of `above` gives `aboveType`
+    fn child(&mut self, key: &str) -> QuerySerializer<'_> {
+        let mut chars = key.chars();
+        let key = match (self.key.as_str(), chars.next()) {
+            ("", _) | (_, None) => key.to_owned(),
+            (prefix, Some(first)) => {
+                prefix.to_owned() + &first.to_uppercase().to_string() + chars.as_str()
+            }
+        };
+        QuerySerializer {
+            key,
+            query: self.query,
+        }
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident: $ty:ty),*) => {
+        $(fn $method(self, value: $ty) -> Result<(), QueryError> {
+            self.push(value.to_string())
+        })*
+    };
+}
+
+impl<'a> Serializer for QuerySerializer<'a> {
+    type Ok = ();
+    type Error = QueryError;
+    type SerializeSeq = Impossible<(), QueryError>;
+    type SerializeTuple = Impossible<(), QueryError>;
+    type SerializeTupleStruct = Impossible<(), QueryError>;
+    type SerializeTupleVariant = Impossible<(), QueryError>;
+    type SerializeMap = QueryMapSerializer<'a>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), QueryError>;
+
+    serialize_display!(
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_char: char,
+        serialize_str: &str
+    );
+
+    /// Formatted as `f32`, widening to `f64` would send 0.1 as 0.10000000149011612
+    fn serialize_f32(self, value: f32) -> Result<(), QueryError> {
+        match value.is_finite() {
+            true => self.push(value.to_string()),
+            false => Err(self.not_finite(value)),
+        }
+    }
+
+    /// `Display` never uses an exponent, unlike the JSON form of small numbers
+    fn serialize_f64(self, value: f64) -> Result<(), QueryError> {
+        match value.is_finite() {
+            true => self.push(value.to_string()),
+            false => Err(self.not_finite(value)),
+        }
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<(), QueryError> {
+        Err(self.unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), QueryError> {
+        self.push(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), QueryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<(), QueryError> {
+        Err(self.unsupported(&format!("enum variant {variant}")))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, QueryError> {
+        Err(self.unsupported("sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, QueryError> {
+        Err(self.unsupported("tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, QueryError> {
+        Err(self.unsupported(name))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, QueryError> {
+        Err(self.unsupported(&format!("enum variant {variant}")))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, QueryError> {
+        Ok(QueryMapSerializer {
+            parent: self,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, QueryError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, QueryError> {
+        Err(self.unsupported(&format!("enum variant {variant}")))
+    }
+}
+
+impl<'a> ser::SerializeStruct for QuerySerializer<'a> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), QueryError> {
+        value.serialize(self.child(key))
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+/// Maps with string keys, e.g. `serde_json::Value` or `#[serde(flatten)]` fields
+struct QueryMapSerializer<'a> {
+    parent: QuerySerializer<'a>,
+    key: Option<String>,
+}
+
+impl<'a> ser::SerializeMap for QueryMapSerializer<'a> {
+    type Ok = ();
+    type Error = QueryError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), QueryError> {
+        // Keys are serialized like values, which must give a single parameter
+        let mut pairs = vec![];
+        key.serialize(QuerySerializer {
+            key: "key".to_owned(),
+            query: &mut pairs,
+        })?;
+        match (pairs.pop(), pairs.is_empty()) {
+            (Some((_, key)), true) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(QueryError("query keys must be strings".to_owned())),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), QueryError> {
+        match self.key.take() {
+            Some(key) => value.serialize(self.parent.child(&key)),
+            None => Err(QueryError(
+                "query value serialized before its key".to_owned(),
+            )),
+        }
+    }
+
+    fn end(self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{
+        app_config::AppConfig,
+        domain::{
+            binance::{
+                BinanceOrderSide, BinanceOrderType, BinanceSpotOrderRequest, BinanceTimeInForce,
+                F64String,
+            },
+            secret::SecretString,
+        },
+        infrastructure::{
+            binance_repo::BinanceRepo,
+            signer::{ApiCredentials, HmacSigner, RequestSigner},
+        },
+    };
+
+    const SECRET_KEY: &str = "SECRETKEY1234567890abcdef";
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Side {
+        Buy,
+        #[serde(rename = "SELL")]
+        Sell,
+    }
+
+    #[derive(Serialize)]
+    enum Shape {
+        Newtype(u32),
+        Tuple(u32, u32),
+        Struct { value: u32 },
+    }
+
+    #[test]
+    fn unit_variants_are_sent_by_name() {
+        #[derive(Serialize)]
+        struct Order {
+            buy: Side,
+            sell: Side,
+        }
+        let query = to_query(&Order {
+            buy: Side::Buy,
+            sell: Side::Sell,
+        })
+        .unwrap();
+        assert_eq!(query, pairs(&[("buy", "BUY"), ("sell", "SELL")]));
+    }
+
+    #[test]
+    fn variants_holding_data_are_refused() {
+        for shape in [
+            Shape::Newtype(1),
+            Shape::Tuple(1, 2),
+            Shape::Struct { value: 1 },
+        ] {
+            let err = to_query(&BTreeMap::from([("shape", shape)])).unwrap_err();
+            assert!(
+                err.to_string().starts_with("cannot send the enum variant"),
+                "{}",
+                err
+            );
+            assert!(err.to_string().ends_with("shape as query parameter"));
+        }
+    }
+
+    #[test]
+    fn f32_is_not_widened() {
+        let query = to_query(&BTreeMap::from([("price", 0.1f32)])).unwrap();
+        assert_eq!(query, pairs(&[("price", "0.1")]));
+        let query = to_query(&BTreeMap::from([("quantity", 0.00000001f32)])).unwrap();
+        assert_eq!(query, pairs(&[("quantity", "0.00000001")]));
+    }
+
+    #[test]
+    fn f64_has_no_exponent() {
+        let query = to_query(&BTreeMap::from([("a", 1e-8), ("b", 1e21), ("c", -2.5)])).unwrap();
+        assert_eq!(
+            query,
+            pairs(&[
+                ("a", "0.00000001"),
+                ("b", "1000000000000000000000"),
+                ("c", "-2.5")
+            ])
+        );
+    }
+
+    #[test]
+    fn non_finite_floats_are_refused() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let err = to_query(&BTreeMap::from([("price", value)])).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("cannot send the number {value} as query parameter price")
+            );
+        }
+        let err = to_query(&BTreeMap::from([("price", f32::NAN)])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot send the number NaN as query parameter price"
+        );
+    }
+
+    #[test]
+    fn nested_keys_are_prefixed_at_every_level() {
+        #[derive(Serialize)]
+        struct Leg {
+            #[serde(rename = "type")]
+            order_type: &'static str,
+            limit: Limit,
+        }
+        #[derive(Serialize)]
+        struct Limit {
+            price: f64,
+            #[serde(rename = "timeInForce")]
+            time_in_force: Option<&'static str>,
+        }
+        #[derive(Serialize)]
+        struct List {
+            symbol: &'static str,
+            above: Leg,
+        }
+        let query = to_query(&List {
+            symbol: "BTCUSDT",
+            above: Leg {
+                order_type: "STOP_LOSS_LIMIT",
+                limit: Limit {
+                    price: 60000.0,
+                    time_in_force: None,
+                },
+            },
+        })
+        .unwrap();
+        assert_eq!(
+            query,
+            pairs(&[
+                ("symbol", "BTCUSDT"),
+                ("aboveType", "STOP_LOSS_LIMIT"),
+                ("aboveLimitPrice", "60000"),
+            ])
+        );
+    }
+
+    #[test]
+    fn top_level_scalars_are_refused() {
+        assert_eq!(
+            to_query("BTCUSDT").unwrap_err().to_string(),
+            "cannot send the value BTCUSDT as query, expected a struct or a map"
+        );
+        assert_eq!(
+            to_query(&1.5).unwrap_err().to_string(),
+            "cannot send the value 1.5 as query, expected a struct or a map"
+        );
+        assert_eq!(
+            to_query(&vec![1]).unwrap_err().to_string(),
+            "cannot send sequence as query"
+        );
+        assert_eq!(to_query(&None::<u32>).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn map_keys_must_be_strings() {
+        let err = to_query(&BTreeMap::from([(vec![1], 1)])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot send the sequence key as query parameter"
+        );
+    }
+
+    #[test]
+    fn order_round_trips_through_the_signed_query() {
+        let profile = toml::from_str::<AppConfig>("api_key = \"k\"\nenvironment = \"mock\"")
+            .unwrap()
+            .profile(None)
+            .unwrap();
+        let credentials = ApiCredentials {
+            api_key: SecretString::new("k".to_owned()),
+            signer: Box::new(HmacSigner::new(SecretString::new(SECRET_KEY.to_owned()))),
+        };
+        let binance = BinanceRepo::new(profile.binance, credentials).unwrap();
+        let mut order = BinanceSpotOrderRequest::new(
+            "BTCUSDT".to_owned(),
+            BinanceOrderSide::Buy,
+            BinanceOrderType::Limit,
+        );
+        order.quantity = Some(F64String::from(0.00000001));
+        order.price = Some(F64String::from(70000.5));
+        order.time_in_force = Some(BinanceTimeInForce::Gtc);
+
+        let query = to_query(&order).unwrap();
+        let mut signed = binance.compose_query(query.clone()).unwrap();
+        let (key, signature) = signed.pop().unwrap();
+        assert_eq!(key, "signature");
+        let payload = serde_urlencoded::to_string(&signed).unwrap();
+        let signer = HmacSigner::new(SecretString::new(SECRET_KEY.to_owned()));
+        assert_eq!(signature, signer.sign(&payload).unwrap());
+
+        let (key, _) = signed.pop().unwrap();
+        assert_eq!(key, "timestamp");
+        assert_eq!(
+            signed,
+            pairs(&[
+                ("symbol", "BTCUSDT"),
+                ("side", "BUY"),
+                ("type", "LIMIT"),
+                ("quantity", "0.00000001"),
+                ("price", "70000.5"),
+                ("timeInForce", "GTC"),
+            ])
+        );
+        let decoded: Vec<(String, String)> = serde_urlencoded::from_str(&payload).unwrap();
+        assert_eq!(decoded[..decoded.len() - 1], query[..]);
+    }
+}
@@ -1,10 +1,18 @@
-use std::collections::HashMap;
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
 
 use anyhow::{Context, Result};
-use reqwest::blocking::Response;
+use reqwest::blocking::{RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+
+use crate::infrastructure::{metrics::Metrics, query, transport::HttpTransport};
+
+/// Id of the last request sent, shown on its span to follow it through the logs
+static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug)]
 pub struct ResponseError(String);
@@ -20,6 +28,42 @@ impl Display for ResponseError {
 pub struct ReqwestFacade {}
 
 impl ReqwestFacade {
+    /// Send the request with the transport in a `binance_request` span, recording its status,
+    /// latency and the rate limit usage Binance reports in the [Metrics]
+    pub fn send(transport: &dyn HttpTransport, request: RequestBuilder) -> Result<Response> {
+        let request = request.build().context("cannot build the request")?;
+        let method = request.method().to_string();
+        let endpoint = request.url().path().to_owned();
+        let span = tracing::debug_span!(
+            "binance_request",
+            id = REQUEST_ID.fetch_add(1, Ordering::Relaxed) + 1,
+            method = %method,
+            endpoint = %endpoint,
+        );
+        let _entered = span.enter();
+        let started = Instant::now();
+        let result = transport.execute(request);
+        let elapsed = started.elapsed();
+        let response = result
+            .as_ref()
+            .ok()
+            .map(|resp| (resp.status(), resp.headers()));
+        Metrics::global().record_request(&method, &endpoint, elapsed, response);
+        match &result {
+            Ok(resp) => tracing::debug!(
+                status = resp.status().as_u16(),
+                latency_ms = elapsed.as_millis() as u64,
+                "binance response"
+            ),
+            Err(err) => tracing::warn!(
+                latency_ms = elapsed.as_millis() as u64,
+                "binance request failed: {:#}",
+                err
+            ),
+        }
+        result.with_context(|| format!("{method} {endpoint}"))
+    }
+
     /// Handle a response from reqwest using anyhow
     /// Check if it is Success then try to serialize it to json
     pub fn handle_response_json<T>(resp: Response) -> Result<T>
@@ -34,40 +78,38 @@ impl ReqwestFacade {
             ))));
         }
         let text = resp.text().unwrap_or("".to_owned());
-        log::debug!("reqwest response text: {}", text);
         let obj = serde_json::from_str::<T>(&text).context("failed json")?;
 
         Ok(obj)
     }
 
-    /// Use serde_json to serialize the object into vector (key, value) to be used with reqwest client
+    /// Serialize the object into (key, value) pairs, in the order of its fields, to be used
+    /// with the reqwest client. See [query::to_query] for the supported shapes.
     ///
     /// ```
-    /// let request = BinanceSpotOrderRequest {
-    ///     symbol: "BTCUSDT"
-    ///     order_type: Limit,
-    ///     side: Buy,
-    ///     quantity: "0.001",
+    /// use binance_rs::{
+    ///     domain::binance::{BinanceOrderSide, BinanceOrderType, BinanceSpotOrderRequest, F64String},
+    ///     infrastructure::reqwest_facade::ReqwestFacade,
     /// };
-    /// let query = object_to_query(request);
-    /// assert!(query, vec![("symbol", "BTCUSDT"), ("type", "LIMIT"), ("side", "BUY"), ("quantity", "0.001")]);
     ///
+    /// let mut request = BinanceSpotOrderRequest::new(
+    ///     "BTCUSDT".to_owned(),
+    ///     BinanceOrderSide::Buy,
+    ///     BinanceOrderType::Market,
+    /// );
+    /// request.quantity = Some(F64String::from(0.001));
+    /// let query = ReqwestFacade::object_to_query(&request).unwrap();
+    /// assert_eq!(
+    ///     query,
+    ///     vec![
+    ///         ("symbol".to_owned(), "BTCUSDT".to_owned()),
+    ///         ("side".to_owned(), "BUY".to_owned()),
+    ///         ("type".to_owned(), "MARKET".to_owned()),
+    ///         ("quantity".to_owned(), "0.001".to_owned()),
+    ///     ]
+    /// );
     /// ```
-    pub fn object_to_query<T>(obj: T) -> Vec<(String, String)>
-    where
-        T: for<'a> Deserialize<'a> + Serialize,
-    {
-        let lookup: HashMap<String, Value> =
-            serde_json::from_value(serde_json::to_value::<T>(obj).unwrap()).unwrap();
-        lookup
-            .iter()
-            .filter(|(_, val)| !val.is_null())
-            .map(|(key, val)| {
-                (
-                    key.to_owned(),
-                    serde_json::to_string(val).unwrap().replace("\"", ""),
-                )
-            })
-            .collect::<Vec<(String, String)>>()
+    pub fn object_to_query<T: Serialize + ?Sized>(obj: &T) -> Result<Vec<(String, String)>> {
+        query::to_query(obj).context("cannot serialize query")
     }
 }
@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::domain::risk::RiskState;
+
+/// The [RiskState] of a profile, stored in a local toml file so the limits hold across commands
+///
+/// ```no_run
+/// use binance_rs::infrastructure::risk_store::RiskStore;
+/// use chrono::Utc;
+/// # fn main() -> anyhow::Result<()> {
+/// let store = RiskStore::new(RiskStore::default_path("default"));
+/// store.update(|state| state.orders.push(Utc::now()))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RiskStore {
+    path: PathBuf,
+}
+
+impl RiskStore {
+    /// `<config dir>/binance-rs/risk-<profile>.toml`, e.g. `~/.config/binance-rs/risk-default.toml`
+    pub fn default_path(profile: &str) -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("binance-rs")
+            .join(format!("risk-{profile}.toml"))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the state. A missing file is an empty state.
+    pub fn load(&self) -> Result<RiskState> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => toml::from_str::<RiskState>(&content)
+                .with_context(|| format!("cannot parse risk state {}", self.path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(RiskState::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("cannot read risk state {}", self.path.display()))
+            }
+        }
+    }
+
+    /// Read the state, apply the change and write it back.
+    /// The file is replaced atomically so a crash never leaves it half written.
+    pub fn update<T>(&self, change: impl FnOnce(&mut RiskState) -> T) -> Result<T> {
+        let mut state = self.load()?;
+        let result = change(&mut state);
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("cannot create risk state directory")?;
+        }
+        let content = toml::to_string(&state).context("cannot serialize risk state")?;
+        let tmp = self.path.with_extension("toml.tmp");
+        fs::write(&tmp, content)
+            .with_context(|| format!("cannot write risk state {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("cannot write risk state {}", self.path.display()))?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,95 @@
+use std::{cell::RefCell, env, fs, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{
+    app_config::{Profile, SecretSource, SigningMethod},
+    domain::secret::SecretString,
+    infrastructure::{
+        keystore::{read_passphrase, Keystore},
+        logging,
+        signer::{ApiCredentials, Ed25519Signer, HmacSigner, RequestSigner, RsaSigner},
+    },
+};
+
+/// Resolve [SecretSource] to the secret value.
+/// The keystore is opened and the passphrase asked at most once, on first use.
+pub struct SecretResolver {
+    keystore_path: PathBuf,
+    keystore: RefCell<Option<(Keystore, SecretString)>>,
+}
+
+impl SecretResolver {
+    pub fn new(keystore_path: PathBuf) -> Self {
+        Self {
+            keystore_path,
+            keystore: RefCell::new(None),
+        }
+    }
+
+    /// Resolve the secret and mask it in the logs from now on
+    pub fn resolve(&self, source: &SecretSource) -> Result<SecretString> {
+        let secret = self.read(source)?;
+        logging::register_secret(secret.expose());
+        Ok(secret)
+    }
+
+    fn read(&self, source: &SecretSource) -> Result<SecretString> {
+        match source {
+            SecretSource::Value(value) => Ok(value.clone()),
+            SecretSource::Env { env } => env::var(env)
+                .map(SecretString::new)
+                .with_context(|| format!("cannot read secret from env {env}")),
+            SecretSource::File { file } => {
+                let content = fs::read_to_string(file)
+                    .with_context(|| format!("cannot read secret from {}", file.display()))?;
+                Ok(SecretString::new(
+                    content.trim_end_matches(['\n', '\r']).to_owned(),
+                ))
+            }
+            SecretSource::Keystore { keystore: name } => {
+                let mut keystore = self.keystore.borrow_mut();
+                if keystore.is_none() {
+                    let store = Keystore::open(self.keystore_path.clone())?;
+                    let passphrase = read_passphrase(false)?;
+                    *keystore = Some((store, passphrase));
+                }
+                let (store, passphrase) = keystore.as_ref().unwrap();
+                store.get(name, passphrase)
+            }
+        }
+    }
+
+    /// Resolve the api key and create the signer selected by the profile
+    pub fn credentials(&self, profile: &Profile) -> Result<ApiCredentials> {
+        let api_key = self
+            .resolve(&profile.api_key)
+            .with_context(|| format!("profile {} api_key", profile.name))?;
+        let signer: Box<dyn RequestSigner> = match profile.signing {
+            SigningMethod::Hmac => {
+                let secret_key = profile.secret_key.as_ref().ok_or_else(|| {
+                    anyhow!("profile {} missing secret_key for hmac", profile.name)
+                })?;
+                let secret = self
+                    .resolve(secret_key)
+                    .with_context(|| format!("profile {} secret_key", profile.name))?;
+                Box::new(HmacSigner::new(secret))
+            }
+            SigningMethod::Ed25519 => {
+                Box::new(Ed25519Signer::from_pem_file(private_key_path(profile)?)?)
+            }
+            SigningMethod::Rsa => Box::new(RsaSigner::from_pem_file(private_key_path(profile)?)?),
+        };
+        Ok(ApiCredentials { api_key, signer })
+    }
+}
+
+fn private_key_path(profile: &Profile) -> Result<&PathBuf> {
+    profile.private_key.as_ref().ok_or_else(|| {
+        anyhow!(
+            "profile {} missing private_key for {:?} signing",
+            profile.name,
+            profile.signing
+        )
+    })
+}
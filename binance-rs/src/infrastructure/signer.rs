@@ -0,0 +1,135 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::{pkcs8::DecodePrivateKey, Signer};
+use hmac::{Hmac, Mac};
+use rsa::{pkcs1::DecodeRsaPrivateKey, pkcs1v15, signature::SignatureEncoding, RsaPrivateKey};
+use sha2::Sha256;
+
+use crate::domain::secret::SecretString;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign the query string of a request for the `signature` parameter
+pub trait RequestSigner: Send + Sync {
+    fn sign(&self, payload: &str) -> Result<String>;
+}
+
+/// The API key sent in the `X-MBX-APIKEY` header and the signer matching its type
+pub struct ApiCredentials {
+    pub api_key: SecretString,
+    pub signer: Box<dyn RequestSigner>,
+}
+
+/// HMAC SHA256 signature of the payload with the secret key, hex encoded
+///
+/// ```
+/// use binance_rs::{domain::secret::SecretString, infrastructure::signer::*};
+///
+/// let secret = "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j";
+/// let signer = HmacSigner::new(SecretString::new(secret.to_owned()));
+/// let payload = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+/// assert_eq!(
+///     signer.sign(payload).unwrap(),
+///     "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+/// );
+/// ```
+pub struct HmacSigner {
+    secret: SecretString,
+}
+
+impl HmacSigner {
+    pub fn new(secret: SecretString) -> Self {
+        Self { secret }
+    }
+}
+
+impl RequestSigner for HmacSigner {
+    fn sign(&self, payload: &str) -> Result<String> {
+        let mut mac = HmacSha256::new_from_slice(self.secret.expose().as_bytes())
+            .map_err(|e| anyhow!("invalid HMAC key: {e}"))?;
+        mac.update(payload.as_bytes());
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+}
+
+/// Ed25519 signature of the payload, base64 encoded.
+/// The private key is a PKCS#8 PEM file as generated by Binance's key generator.
+///
+/// ```
+/// use binance_rs::infrastructure::signer::*;
+///
+/// let signer = Ed25519Signer::from_pem_file("testdata/ed25519.pem").unwrap();
+/// let payload = "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=1&price=0.2&timestamp=1668481559918";
+/// assert_eq!(
+///     signer.sign(payload).unwrap(),
+///     "1rQPcNjM/2CAhuzEZoZgiYRu4ByMHUxyLnPGcySCkaxkl1NaJ4Noe5L9WPw34fPWUj76hoQCPgCkZLdRkSjuCw=="
+/// );
+/// ```
+pub struct Ed25519Signer {
+    key: ed25519_dalek::SigningKey,
+}
+
+impl Ed25519Signer {
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let key = ed25519_dalek::SigningKey::from_pkcs8_pem(pem)
+            .map_err(|e| anyhow!("cannot parse Ed25519 private key: {e}"))?;
+        Ok(Self { key })
+    }
+
+    pub fn from_pem_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_pem(&read_pem(path.as_ref())?)
+    }
+}
+
+impl RequestSigner for Ed25519Signer {
+    fn sign(&self, payload: &str) -> Result<String> {
+        let signature = self.key.sign(payload.as_bytes());
+        Ok(BASE64.encode(signature.to_bytes()))
+    }
+}
+
+/// RSA PKCS#1 v1.5 SHA256 signature of the payload, base64 encoded.
+/// The private key is a PKCS#8 (`BEGIN PRIVATE KEY`) or PKCS#1 (`BEGIN RSA PRIVATE KEY`) PEM file.
+///
+/// ```
+/// use binance_rs::infrastructure::signer::*;
+///
+/// let signer = RsaSigner::from_pem_file("testdata/rsa.pem").unwrap();
+/// let payload = "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=1&price=0.2&timestamp=1668481559918";
+/// assert_eq!(
+///     signer.sign(payload).unwrap(),
+///     "g27V19z6mQr0f+dNNqgjsfTiUwt5sBKUmSsOJle4uJ4pNNuUwJkNWnu68TZNNab57ZOhg2s37mKtWg/iIRkd1ICrttulGaqF7lHJCdRpqhrQ4Kfx2x1G4JWA+z4/ztCuAS4cB1wwKXiDkUUjH8upX0sYnXIFTUnYmRQZBWIx9XG+m1UkQeYX+UUGzABvx26TrDC1WvBqhLK6/8ZZUndvnDJnPsdfqcVbXA2WT/9BzPTUDkY/zFOVX6dc0dUqOBYMTDP0vaCfmSsReuigfZ2lfqmkvHhX1fTwp2Nt2Do3KpYiQ57CHYt0pjwWHlcAg8AZ4c66NO0xaRPo1pDamzchKA=="
+/// );
+/// ```
+pub struct RsaSigner {
+    key: pkcs1v15::SigningKey<Sha256>,
+}
+
+impl RsaSigner {
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|e| anyhow!("cannot parse RSA private key: {e}"))?;
+        Ok(Self {
+            key: pkcs1v15::SigningKey::<Sha256>::new(key),
+        })
+    }
+
+    pub fn from_pem_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_pem(&read_pem(path.as_ref())?)
+    }
+}
+
+impl RequestSigner for RsaSigner {
+    fn sign(&self, payload: &str) -> Result<String> {
+        let signature = rsa::signature::Signer::try_sign(&self.key, payload.as_bytes())
+            .map_err(|e| anyhow!("cannot sign with RSA key: {e}"))?;
+        Ok(BASE64.encode(signature.to_bytes()))
+    }
+}
+
+fn read_pem(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("cannot read private key {}", path.display()))
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::blocking::{Client, Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::logging::redact_secrets;
+
+/// Query parameters changing on every call, neither recorded nor matched
+const VOLATILE_PARAMS: [&str; 2] = ["timestamp", "signature"];
+
+/// Response headers left out of the cassettes
+const SKIPPED_HEADERS: [&str; 4] = [
+    "set-cookie",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+];
+
+/// Sends the requests built by [crate::infrastructure::binance_repo::BinanceRepo]
+pub trait HttpTransport: Send + Sync {
+    fn execute(&self, request: Request) -> Result<Response>;
+}
+
+/// Sends the requests to Binance with the reqwest client
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn execute(&self, request: Request) -> Result<Response> {
+        // The url of the error holds the signature
+        Ok(self
+            .client
+            .execute(request)
+            .map_err(reqwest::Error::without_url)?)
+    }
+}
+
+/// Request/response pairs saved by [RecordingTransport] and served by [ReplayTransport]
+///
+/// ```json
+/// {"interactions": [{
+///   "request": {"method": "GET", "path": "/api/v3/openOrders", "query": [["symbol", "BTCUSDT"]]},
+///   "response": {"status": 200, "headers": {"content-type": "application/json"}, "body": "[]"}
+/// }]}
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// What a request is matched on: the method, the path and the sorted query
+/// without the [VOLATILE_PARAMS]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("cannot read cassette {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("cannot parse cassette {}", path.display()))
+    }
+
+    /// Write through a temporary file so an interrupted recording keeps the previous content
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create cassette directory {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self).context("cannot serialize cassette")?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content)
+            .with_context(|| format!("cannot write cassette {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("cannot write cassette {}", path.display()))
+    }
+}
+
+impl RecordedRequest {
+    pub fn new(request: &Request) -> Self {
+        let mut query = request
+            .url()
+            .query_pairs()
+            .filter(|(key, _)| !VOLATILE_PARAMS.contains(&key.as_ref()))
+            .map(|(key, value)| (key.into_owned(), redact_secrets(&value).into_owned()))
+            .collect::<Vec<_>>();
+        query.sort();
+        Self {
+            method: request.method().to_string(),
+            path: request.url().path().to_owned(),
+            query,
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn to_response(&self) -> Result<Response> {
+        let mut response = http::Response::builder().status(self.status);
+        for (name, value) in self.headers.iter() {
+            response = response.header(name, value);
+        }
+        let response = response
+            .body(self.body.clone())
+            .context("cannot rebuild the recorded response")?;
+        Ok(Response::from(response))
+    }
+}
+
+/// Send the requests with another transport and append each exchange to the cassette,
+/// with the keys and signatures scrubbed. The file is rewritten after every request.
+///
+/// ```no_run
+/// use std::path::PathBuf;
+///
+/// use binance_rs::infrastructure::binance_repo::BinanceRepo;
+/// # fn example(binance: BinanceRepo) -> anyhow::Result<()> {
+/// let binance = binance.recording(PathBuf::from("cassettes/account.json"));
+/// binance.get_account()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordingTransport {
+    inner: Box<dyn HttpTransport>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingTransport {
+    /// Start an empty cassette at `path`, replacing any previous one
+    pub fn new(inner: Box<dyn HttpTransport>, path: PathBuf) -> Self {
+        Self {
+            inner,
+            path,
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+}
+
+impl HttpTransport for RecordingTransport {
+    fn execute(&self, request: Request) -> Result<Response> {
+        let recorded_request = RecordedRequest::new(&request);
+        let response = self.inner.execute(request)?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+            .collect::<BTreeMap<_, _>>();
+        let body = response
+            .text()
+            .context("cannot read the response to record")?;
+
+        let replayed = RecordedResponse {
+            status,
+            headers,
+            body,
+        };
+        let response = replayed.to_response()?;
+        let recorded_response = RecordedResponse {
+            body: redact_secrets(&replayed.body).into_owned(),
+            ..replayed
+        };
+
+        let mut cassette = self.cassette.lock().unwrap_or_else(|err| err.into_inner());
+        cassette.interactions.push(Interaction {
+            request: recorded_request,
+            response: recorded_response,
+        });
+        cassette.write_to_file(&self.path)?;
+        Ok(response)
+    }
+}
+
+/// Answer the requests from a cassette without any network access.
+/// Requests matching several interactions get them in the recorded order,
+/// then the last one again.
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use binance_rs::{
+///     app_config::AppConfigBinance,
+///     infrastructure::{binance_repo::BinanceRepo, signer::ApiCredentials, transport::ReplayTransport},
+/// };
+/// # fn example(cfg: AppConfigBinance, credentials: ApiCredentials) -> anyhow::Result<()> {
+/// let transport = ReplayTransport::read_from_file(Path::new("cassettes/account.json"))?;
+/// let binance = BinanceRepo::new(cfg, credentials)?.with_transport(Box::new(transport));
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReplayTransport {
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Self {
+            interactions: cassette.interactions,
+            used: Mutex::new(used),
+        }
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        Ok(Self::new(Cassette::read_from_file(path)?))
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn execute(&self, request: Request) -> Result<Response> {
+        let wanted = RecordedRequest::new(&request);
+        let matching = self
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request == wanted)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let mut used = self.used.lock().unwrap_or_else(|err| err.into_inner());
+        let index = matching
+            .iter()
+            .find(|index| !used[**index])
+            .or(matching.last())
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(
+                    "no interaction of the cassette matches {} {} {:?}",
+                    wanted.method,
+                    wanted.path,
+                    wanted.query
+                )
+            })?;
+        used[index] = true;
+        self.interactions[index].response.to_response()
+    }
+}
+
+/// Repos of the mock environment for the unit tests, answered by the cassettes
+/// of `testdata/cassettes` recorded against it
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::path::PathBuf;
+
+    use super::ReplayTransport;
+    use crate::{
+        app_config::AppConfig,
+        domain::secret::SecretString,
+        infrastructure::{
+            binance_repo::BinanceRepo,
+            signer::{ApiCredentials, HmacSigner},
+        },
+    };
+
+    /// Keys of the mock environment the cassettes were recorded with
+    pub const API_KEY: &str = "APIKEY1234567890abcdef";
+    pub const SECRET_KEY: &str = "SECRETKEY1234567890abcdef";
+
+    pub fn cassette(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/cassettes")
+            .join(name)
+    }
+
+    /// Repo sending the requests to the mock environment
+    pub fn binance() -> BinanceRepo {
+        let profile = toml::from_str::<AppConfig>("api_key = \"k\"\nenvironment = \"mock\"")
+            .unwrap()
+            .profile(None)
+            .unwrap();
+        let credentials = ApiCredentials {
+            api_key: SecretString::new(API_KEY.to_owned()),
+            signer: Box::new(HmacSigner::new(SecretString::new(SECRET_KEY.to_owned()))),
+        };
+        BinanceRepo::new(profile.binance, credentials).unwrap()
+    }
+
+    /// Repo answered by the cassette `name`
+    pub fn replayed(name: &str) -> BinanceRepo {
+        let transport = ReplayTransport::read_from_file(&cassette(name)).unwrap();
+        binance().with_transport(Box::new(transport))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::blocking::Client;
+
+    use super::{testing::*, *};
+    use crate::domain::binance::{
+        BinanceOrderSide, BinanceOrderType, BinanceSpotOrderRequest, BinanceTimeInForce, F64String,
+    };
+
+    const CASSETTE: &str = "account_and_order.json";
+
+    fn replay() -> Box<dyn HttpTransport> {
+        Box::new(ReplayTransport::read_from_file(&cassette(CASSETTE)).unwrap())
+    }
+
+    fn order() -> BinanceSpotOrderRequest {
+        let mut order = BinanceSpotOrderRequest::new(
+            "BTCUSDT".to_owned(),
+            BinanceOrderSide::Buy,
+            BinanceOrderType::Limit,
+        );
+        order.quantity = Some(F64String::from(0.001));
+        order.price = Some(F64String::from(25000.0));
+        order.time_in_force = Some(BinanceTimeInForce::Gtc);
+        order
+    }
+
+    fn assert_scrubbed(content: &str) {
+        for leaked in ["timestamp", "signature", API_KEY, SECRET_KEY] {
+            assert!(!content.contains(leaked), "{leaked} left in the cassette");
+        }
+    }
+
+    #[test]
+    fn cassette_replays_the_account_and_a_signed_order() {
+        let binance = replayed(CASSETTE);
+        let account = binance.get_account().unwrap();
+        assert_eq!(account.balances[0].asset, "BTC");
+        assert_eq!(account.balances[0].free.value(), 1.5);
+
+        let placed = binance.make_spot_order(order()).unwrap();
+        assert_eq!(placed.symbol.as_deref(), Some("BTCUSDT"));
+        assert_eq!(placed.order_id, Some(2));
+    }
+
+    #[test]
+    fn request_not_in_the_cassette_fails() {
+        let binance = replayed(CASSETTE);
+        let mut order = order();
+        order.symbol = "ETHUSDT".to_owned();
+        let err = binance.make_spot_order(order).unwrap_err();
+        assert!(format!("{err:#}").contains("no interaction of the cassette matches"));
+    }
+
+    #[test]
+    fn requests_match_without_timestamp_and_signature() {
+        let client = Client::new();
+        let url = "http://127.0.0.1:8080/api/v3/order";
+        let first = client
+            .post(url)
+            .query(&[
+                ("symbol", "BTCUSDT"),
+                ("timestamp", "1"),
+                ("signature", "aa"),
+            ])
+            .build()
+            .unwrap();
+        let second = client
+            .post(url)
+            .query(&[
+                ("timestamp", "2"),
+                ("symbol", "BTCUSDT"),
+                ("signature", "bb"),
+            ])
+            .build()
+            .unwrap();
+        let other = client
+            .post(url)
+            .query(&[
+                ("symbol", "ETHUSDT"),
+                ("timestamp", "1"),
+                ("signature", "aa"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(RecordedRequest::new(&first), RecordedRequest::new(&second));
+        assert_ne!(RecordedRequest::new(&first), RecordedRequest::new(&other));
+        assert_eq!(
+            RecordedRequest::new(&first).query,
+            vec![("symbol".to_owned(), "BTCUSDT".to_owned())]
+        );
+    }
+
+    #[test]
+    fn cassette_is_scrubbed() {
+        assert_scrubbed(&fs::read_to_string(cassette(CASSETTE)).unwrap());
+    }
+
+    #[test]
+    fn recording_scrubs_the_keys_and_signatures() {
+        let path =
+            std::env::temp_dir().join(format!("binance-rs-cassette-{}.json", std::process::id()));
+        let binance = binance().with_transport(replay()).recording(path.clone());
+        binance.get_account().unwrap();
+        binance.make_spot_order(order()).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_scrubbed(&content);
+        let recorded: Cassette = serde_json::from_str(&content).unwrap();
+        assert_eq!(recorded.interactions.len(), 2);
+        assert_eq!(recorded.interactions[1].request.path, "/api/v3/order");
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::trigger::Trigger;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TriggerFile {
+    #[serde(default)]
+    triggers: Vec<Trigger>,
+}
+
+/// The armed [Trigger] of the order emulator, stored in a local toml file so they survive restarts.
+/// Every change reads the file again so `bot arm` and a running `bot run` don't lose each other's changes.
+///
+/// ```no_run
+/// use binance_rs::{domain::trigger::Trigger, infrastructure::trigger_store::TriggerStore};
+/// # fn example(trigger: Trigger) -> anyhow::Result<()> {
+/// let store = TriggerStore::new(TriggerStore::default_path());
+/// store.update(|triggers| triggers.push(trigger))?;
+/// let triggers = store.load()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TriggerStore {
+    path: PathBuf,
+}
+
+impl TriggerStore {
+    /// `<config dir>/binance-rs/triggers.toml`, e.g. `~/.config/binance-rs/triggers.toml`
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("binance-rs")
+            .join("triggers.toml")
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the triggers. A missing file has no triggers.
+    pub fn load(&self) -> Result<Vec<Trigger>> {
+        let file = match fs::read_to_string(&self.path) {
+            Ok(content) => toml::from_str::<TriggerFile>(&content)
+                .with_context(|| format!("cannot parse triggers {}", self.path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => TriggerFile::default(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("cannot read triggers {}", self.path.display()))
+            }
+        };
+        Ok(file.triggers)
+    }
+
+    /// Last modification of the file, to notice the changes made by other processes
+    pub fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Read the triggers, apply the change and write them back.
+    /// The file is replaced atomically so a crash never leaves it half written.
+    pub fn update<T>(&self, change: impl FnOnce(&mut Vec<Trigger>) -> T) -> Result<T> {
+        let mut triggers = self.load()?;
+        let result = change(&mut triggers);
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("cannot create triggers directory")?;
+        }
+        let content =
+            toml::to_string(&TriggerFile { triggers }).context("cannot serialize triggers")?;
+        let tmp = self.path.with_extension("toml.tmp");
+        fs::write(&tmp, content)
+            .with_context(|| format!("cannot write triggers {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("cannot write triggers {}", self.path.display()))?;
+        Ok(result)
+    }
+}
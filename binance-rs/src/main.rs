@@ -1,25 +1,27 @@
 use anyhow::Result;
 use binance_rs::{
     app_config::AppConfig,
-    application::command::{Args, CommandHandler},
-    domain::binance::BinanceKeypair,
-    infrastructure::{binance_repo::BinanceRepo, config_facade::config_with_path},
+    application::{
+        command::{handle_keys, Args, Command, CommandHandler},
+        output::Output,
+    },
+    infrastructure::{config_facade::config_with_path, logging},
 };
 use clap::Parser;
-use env_logger::Env;
 
 fn main() -> Result<()> {
-    env_logger::init_from_env(Env::default().default_filter_or("debug"));
-
     let args = Args::parse();
+    logging::init(args.log_format)?;
+
     let config_path = args.config.to_str().unwrap();
     let cfg = config_with_path::<AppConfig>(config_path)?;
 
-    let keypair = BinanceKeypair {
-        key: cfg.api_key,
-        secret: cfg.secret_key,
-    };
-    let binance_svc = BinanceRepo::new(cfg.binance, keypair);
+    let output = Output::new(args.output);
+    if let Command::Keys { command } = &args.command {
+        return handle_keys(&cfg, command, output);
+    }
 
-    CommandHandler::new(binance_svc).handle_args(args)
+    CommandHandler::new(cfg, args.profile.as_deref(), output)?
+        .with_cassette(args.record_cassette.clone(), args.replay_cassette.clone())?
+        .handle_args(args)
 }